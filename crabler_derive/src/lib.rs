@@ -6,13 +6,43 @@ use proc_macro_error::*;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(MutableWebScraper, attributes(on_html, on_response))]
+#[proc_macro_derive(
+    MutableWebScraper,
+    attributes(
+        on_html,
+        on_html_all,
+        on_html_for,
+        on_response,
+        on_json_ld,
+        on_lines,
+        on_batch,
+        on_stream,
+        on_sse,
+        on_feed_item
+    )
+)]
 #[proc_macro_error]
 /// Macro to derive MutableWebScraper trait on to a given struct.
 /// Supported options:
 /// * `#[on_html("css selector", method_name)]` - will bind given css selector to a method. When page
 /// is loaded this method will be invoked for all elements that match given selector.
+/// * `#[on_html_all("css selector", method_name)]` - same as `on_html`, but method is invoked once per
+/// page with a `Vec<Element>` of all elements that match given selector, even when there are none.
+/// * `#[on_html_for("/url/glob/*", "css selector", method_name)]` - same as `on_html`, but only on pages
+/// with url matching given `UrlPattern` glob. Use `regex = "pattern"` instead of the glob for a regex.
 /// * `#[on_response(method_name)]` - will bind given method to a successful page load action.
+/// * `#[on_json_ld(method_name)]` - will bind given method to every JSON-LD block
+/// (`<script type="application/ld+json">`) found on a loaded page.
+/// * `#[on_lines("content/type", method_name)]` - will stream body of responses with given content type
+/// to a method line by line instead of parsing it as html.
+/// * `#[on_batch(size, method_name)]` - will pass items emitted with `Response::emit` to a method
+/// in batches of given size, remaining items are passed once the crawl ends.
+/// * `#[on_stream(method_name)]` - will pass body of every fetched page to a method while it is
+/// still downloading, before `on_html` handlers run on the whole page.
+/// * `#[on_sse(method_name)]` - will pass every event of `text/event-stream` responses to a method
+/// as it arrives, the connection is kept open until the server closes the stream.
+/// * `#[on_feed_item(method_name)]` - will pass every item of RSS and Atom feeds found with
+/// `Opts::with_follow_feeds` to a method.
 pub fn mutable_web_scraper_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
 
@@ -22,13 +52,43 @@ pub fn mutable_web_scraper_derive(input: TokenStream) -> TokenStream {
     }
 }
 
-#[proc_macro_derive(ImmutableWebScraper, attributes(on_html, on_response))]
+#[proc_macro_derive(
+    ImmutableWebScraper,
+    attributes(
+        on_html,
+        on_html_all,
+        on_html_for,
+        on_response,
+        on_json_ld,
+        on_lines,
+        on_batch,
+        on_stream,
+        on_sse,
+        on_feed_item
+    )
+)]
 #[proc_macro_error]
 /// Macro to derive ImmutableWebScraper trait on to a given struct.
 /// Supported options:
 /// * `#[on_html("css selector", method_name)]` - will bind given css selector to a method. When page
 /// is loaded this method will be invoked for all elements that match given selector.
+/// * `#[on_html_all("css selector", method_name)]` - same as `on_html`, but method is invoked once per
+/// page with a `Vec<Element>` of all elements that match given selector, even when there are none.
+/// * `#[on_html_for("/url/glob/*", "css selector", method_name)]` - same as `on_html`, but only on pages
+/// with url matching given `UrlPattern` glob. Use `regex = "pattern"` instead of the glob for a regex.
 /// * `#[on_response(method_name)]` - will bind given method to a successful page load action.
+/// * `#[on_json_ld(method_name)]` - will bind given method to every JSON-LD block
+/// (`<script type="application/ld+json">`) found on a loaded page.
+/// * `#[on_lines("content/type", method_name)]` - will stream body of responses with given content type
+/// to a method line by line instead of parsing it as html.
+/// * `#[on_batch(size, method_name)]` - will pass items emitted with `Response::emit` to a method
+/// in batches of given size, remaining items are passed once the crawl ends.
+/// * `#[on_stream(method_name)]` - will pass body of every fetched page to a method while it is
+/// still downloading, before `on_html` handlers run on the whole page.
+/// * `#[on_sse(method_name)]` - will pass every event of `text/event-stream` responses to a method
+/// as it arrives, the connection is kept open until the server closes the stream.
+/// * `#[on_feed_item(method_name)]` - will pass every item of RSS and Atom feeds found with
+/// `Opts::with_follow_feeds` to a method.
 pub fn immutable_web_scraper_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
 
@@ -45,7 +105,18 @@ fn impl_web_scraper(ast: &syn::DeriveInput, is_mutable: bool) -> TokenStream {
 
     let mut selectors = vec![];
     let mut matches = vec![];
+    let mut all_selectors = vec![];
+    let mut all_matches = vec![];
+    let mut url_patterns = vec![];
+    let mut url_pattern_matches = vec![];
     let mut responses = vec![];
+    let mut json_lds = vec![];
+    let mut feed_items = vec![];
+    let mut content_types = vec![];
+    let mut line_matches = vec![];
+    let mut batch = None;
+    let mut stream = None;
+    let mut sse = None;
 
     for attr in &ast.attrs {
         let meta = attr.parse_meta();
@@ -58,12 +129,68 @@ fn impl_web_scraper(ast: &syn::DeriveInput, is_mutable: bool) -> TokenStream {
                 selectors.push(selector);
                 matches.push(match_clause);
             }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_html_all" =>
+            {
+                let (selector, match_clause) = handle_on_html_all_attr(nested);
+                all_selectors.push(selector);
+                all_matches.push(match_clause);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_html_for" =>
+            {
+                let index = url_patterns.len();
+                let (url_pattern, match_clause) = handle_on_html_for_attr(nested, index);
+                url_patterns.push(url_pattern);
+                url_pattern_matches.push(match_clause);
+            }
             Ok(Meta::List(MetaList { path, nested, .. }))
                 if path.segments[0].ident == "on_response" =>
             {
                 let response = handle_on_response_attr(nested);
                 responses.push(response);
             }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_json_ld" =>
+            {
+                let json_ld = handle_on_json_ld_attr(nested);
+                json_lds.push(json_ld);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_lines" =>
+            {
+                let (content_type, match_clause) = handle_on_lines_attr(nested);
+                content_types.push(content_type);
+                line_matches.push(match_clause);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_batch" =>
+            {
+                if batch.is_some() {
+                    abort_call_site!("Only one on_batch attribute is supported");
+                }
+                batch = Some(handle_on_batch_attr(nested));
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_stream" =>
+            {
+                if stream.is_some() {
+                    abort_call_site!("Only one on_stream attribute is supported");
+                }
+                stream = Some(handle_on_stream_attr(nested));
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_feed_item" =>
+            {
+                let feed_item = handle_on_feed_item_attr(nested);
+                feed_items.push(feed_item);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. })) if path.segments[0].ident == "on_sse" => {
+                if sse.is_some() {
+                    abort_call_site!("Only one on_sse attribute is supported");
+                }
+                sse = Some(handle_on_sse_attr(nested));
+            }
             Err(err) => {
                 abort_call_site!("Failed to parse attribute: {}", err);
             }
@@ -87,9 +214,23 @@ fn impl_web_scraper(ast: &syn::DeriveInput, is_mutable: bool) -> TokenStream {
         scraper_type = quote!(ImmutableWebScraper);
     }
 
+    let has_json_ld_handlers = !json_lds.is_empty();
+
+    let (batch_size, batch_call) = match batch {
+        Some((size, call)) => (quote! { Some(#size) }, call),
+        None => (quote! { None }, quote! { let _ = items; }),
+    };
+
+    let has_stream_handler = stream.is_some();
+    let stream_call = stream.unwrap_or_else(|| quote! { let _ = (request, body); });
+
+    let has_sse_handler = sse.is_some();
+    let sse_call = sse.unwrap_or_else(|| quote! { let _ = (request, event); });
+
     let gen = quote! {
         #[async_trait(?Send)]
         impl #scraper_type for #name {
+            #[allow(unreachable_code)]
             async fn dispatch_on_html(
                 #self_ref,
                 selector: &str,
@@ -109,6 +250,46 @@ fn impl_web_scraper(ast: &syn::DeriveInput, is_mutable: bool) -> TokenStream {
                 vec![#( #selectors ),*]
             }
 
+            #[allow(unreachable_code)]
+            async fn dispatch_on_html_all(
+                #self_ref,
+                selector: &str,
+                request: Response,
+                elements: Vec<Element>,
+            ) -> std::result::Result<(), CrablerError> {
+
+                match selector {
+                    #( #all_matches, )*
+                    _ => panic!("Failed to dispatch {}", selector),
+                };
+
+                Ok(())
+            }
+
+            fn all_html_all_selectors(&self) -> Vec<&str> {
+                vec![#( #all_selectors ),*]
+            }
+
+            #[allow(unreachable_code)]
+            async fn dispatch_on_html_for(
+                #self_ref,
+                index: usize,
+                request: Response,
+                element: Element,
+            ) -> std::result::Result<(), CrablerError> {
+
+                match index {
+                    #( #url_pattern_matches, )*
+                    _ => panic!("Failed to dispatch url pattern handler {}", index),
+                };
+
+                Ok(())
+            }
+
+            fn all_html_url_patterns(&self) -> Vec<(UrlPattern, &str)> {
+                vec![#( #url_patterns ),*]
+            }
+
             async fn dispatch_on_response(
                 #self_ref,
                 request: Response,
@@ -118,22 +299,100 @@ fn impl_web_scraper(ast: &syn::DeriveInput, is_mutable: bool) -> TokenStream {
                 Ok(())
             }
 
+            async fn dispatch_on_json_ld(
+                #self_ref,
+                request: Response,
+                value: JsonValue,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #json_lds; )*
+
+                Ok(())
+            }
+
+            fn has_json_ld_handlers(&self) -> bool {
+                #has_json_ld_handlers
+            }
+
+            #[allow(unreachable_code)]
+            async fn dispatch_on_lines(
+                #self_ref,
+                content_type: &str,
+                request: Response,
+                line: String,
+            ) -> std::result::Result<(), CrablerError> {
+
+                match content_type {
+                    #( #line_matches, )*
+                    _ => panic!("Failed to dispatch lines of {}", content_type),
+                };
+
+                Ok(())
+            }
+
+            fn all_line_content_types(&self) -> Vec<&str> {
+                vec![#( #content_types ),*]
+            }
+
+            async fn dispatch_on_batch(
+                #self_ref,
+                items: Vec<JsonValue>,
+            ) -> std::result::Result<(), CrablerError> {
+                #batch_call
+
+                Ok(())
+            }
+
+            fn batch_size(&self) -> Option<usize> {
+                #batch_size
+            }
+
+            async fn dispatch_on_stream(
+                #self_ref,
+                request: Response,
+                body: BodyStream,
+            ) -> std::result::Result<(), CrablerError> {
+                #stream_call
+
+                Ok(())
+            }
+
+            fn has_stream_handler(&self) -> bool {
+                #has_stream_handler
+            }
+
+            async fn dispatch_on_sse(
+                #self_ref,
+                request: Response,
+                event: SseEvent,
+            ) -> std::result::Result<(), CrablerError> {
+                #sse_call
+
+                Ok(())
+            }
+
+            fn has_sse_handler(&self) -> bool {
+                #has_sse_handler
+            }
+
+            async fn dispatch_on_feed_item(
+                #self_ref,
+                request: Response,
+                item: FeedItem,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #feed_items; )*
+
+                Ok(())
+            }
+
             async fn run(
                 #self_ref,
                 opts: Opts,
             ) -> std::result::Result<(), CrablerError> {
                 use crabler::#crabler_type;
 
-                let mut crabler = #crabler_type::new(self);
-
-                for url in &opts.urls {
-                    crabler.navigate(url).await?;
-                }
-
-                for _ in 0..opts.threads {
-                    crabler.start_worker();
-                }
+                let mut crabler = #crabler_type::with_opts(self, opts);
 
+                crabler.start().await?;
                 crabler.run().await
             }
         }
@@ -168,6 +427,80 @@ fn handle_on_html_attr(
     (selector, match_clause)
 }
 
+fn handle_on_html_all_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 2 {
+        abort_call_site!(
+            "Not enough argument provided to on_html_all attribute: {}",
+            l
+        );
+    }
+
+    let token = match &nested[0] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => lit_str,
+        _ => abort_call_site!("Cant find on_html_all selector"),
+    };
+
+    let f = match &nested[1] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_html_all method"),
+    };
+
+    let selector = quote! { #token };
+    let match_clause = quote! { #token => self.#f(request, elements).await? };
+
+    (selector, match_clause)
+}
+
+fn handle_on_html_for_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+    index: usize,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 3 {
+        abort_call_site!(
+            "Not enough argument provided to on_html_for attribute: {}",
+            l
+        );
+    }
+
+    let url_pattern = match &nested[0] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => quote! { UrlPattern::glob(#lit_str) },
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(lit_str),
+            ..
+        })) if path.is_ident("regex") => {
+            if let Err(err) = regex::Regex::new(&lit_str.value()) {
+                abort_call_site!("Invalid on_html_for regex: {}", err);
+            }
+            quote! { UrlPattern::regex(#lit_str).expect("regex is checked by the derive macro") }
+        }
+        _ => abort_call_site!("Cant find on_html_for url pattern"),
+    };
+
+    let token = match &nested[1] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => lit_str,
+        _ => abort_call_site!("Cant find on_html_for selector"),
+    };
+
+    let f = match &nested[2] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_html_for method"),
+    };
+
+    let url_pattern = quote! { (#url_pattern, #token) };
+    let match_clause = quote! { #index => self.#f(request, element).await? };
+
+    (url_pattern, match_clause)
+}
+
 fn handle_on_response_attr(
     nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
 ) -> proc_macro2::TokenStream {
@@ -188,3 +521,133 @@ fn handle_on_response_attr(
 
     quote! { self.#f(request).await? }
 }
+
+fn handle_on_json_ld_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough argument provided to on_json_ld attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_json_ld method"),
+    };
+
+    quote! { self.#f(request, value).await? }
+}
+
+fn handle_on_lines_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 2 {
+        abort_call_site!("Not enough argument provided to on_lines attribute: {}", l);
+    }
+
+    let token = match &nested[0] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => lit_str,
+        _ => abort_call_site!("Cant find on_lines content type"),
+    };
+
+    let f = match &nested[1] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_lines method"),
+    };
+
+    let content_type = quote! { #token };
+    let match_clause = quote! { #token => self.#f(request, line).await? };
+
+    (content_type, match_clause)
+}
+
+fn handle_on_batch_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 2 {
+        abort_call_site!("Not enough argument provided to on_batch attribute: {}", l);
+    }
+
+    let size = match &nested[0] {
+        NestedMeta::Lit(Lit::Int(lit_int)) => match lit_int.base10_parse::<usize>() {
+            Ok(size) if size > 0 => size,
+            _ => abort_call_site!("on_batch size must be a positive integer"),
+        },
+        _ => abort_call_site!("Cant find on_batch size"),
+    };
+
+    let f = match &nested[1] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_batch method"),
+    };
+
+    (quote! { #size }, quote! { self.#f(items).await?; })
+}
+
+fn handle_on_stream_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!("Not enough argument provided to on_stream attribute: {}", l);
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_stream method"),
+    };
+
+    quote! { self.#f(request, body).await?; }
+}
+
+fn handle_on_sse_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!("Not enough argument provided to on_sse attribute: {}", l);
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_sse method"),
+    };
+
+    quote! { self.#f(request, event).await?; }
+}
+
+fn handle_on_feed_item_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough argument provided to on_feed_item attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Cant find on_feed_item method"),
+    };
+
+    quote! { self.#f(request.clone(), item.clone()).await? }
+}
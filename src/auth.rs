@@ -0,0 +1,278 @@
+use sha2::{Digest as _, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Username and password for a host, see `Opts::with_credentials`
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "MD5-SESS" => Some(Algorithm::Md5Sess),
+            "SHA-256" => Some(Algorithm::Sha256),
+            "SHA-256-SESS" => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn hash(self, input: &str) -> String {
+        let bytes = match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => md5(input.as_bytes()).to_vec(),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => Sha256::digest(input.as_bytes()).to_vec(),
+        };
+
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// Challenge from `WWW-Authenticate` header of a host, kept so later requests to the host
+/// authenticate up front instead of getting another 401 first
+#[derive(Clone, Debug)]
+pub(crate) enum Challenge {
+    Basic,
+    Digest {
+        realm: String,
+        nonce: String,
+        opaque: Option<String>,
+        qop_auth: bool,
+        algorithm: Algorithm,
+        /// Requests sent with this nonce so far, the `nc` of digest response
+        count: u32,
+    },
+}
+
+impl Challenge {
+    /// Pick the strongest supported challenge from `WWW-Authenticate` header values,
+    /// Digest is preferred over Basic
+    pub(crate) fn parse<'a>(values: impl Iterator<Item = &'a str>) -> Option<Challenge> {
+        let mut basic = None;
+
+        for (scheme, params) in values.flat_map(parse_challenges) {
+            match scheme.as_str() {
+                "digest" => {
+                    let algorithm = match params.get("algorithm") {
+                        Some(name) => match Algorithm::parse(name) {
+                            Some(algorithm) => algorithm,
+                            None => continue,
+                        },
+                        None => Algorithm::Md5,
+                    };
+                    let qop = params.get("qop").map(String::as_str).unwrap_or_default();
+                    let qop_auth = qop.split(',').any(|qop| qop.trim() == "auth");
+                    if !qop.is_empty() && !qop_auth {
+                        continue;
+                    }
+
+                    return Some(Challenge::Digest {
+                        realm: params.get("realm").cloned().unwrap_or_default(),
+                        nonce: params.get("nonce").cloned()?,
+                        opaque: params.get("opaque").cloned(),
+                        qop_auth,
+                        algorithm,
+                        count: 0,
+                    });
+                }
+                "basic" => basic = Some(Challenge::Basic),
+                _ => {}
+            }
+        }
+
+        basic
+    }
+
+    /// Value of `Authorization` header for a `GET` of given request target, e.g. `/path?query`
+    pub(crate) fn authorization(&mut self, credentials: &Credentials, uri: &str) -> String {
+        match self {
+            Challenge::Basic => format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", credentials.username, credentials.password))
+            ),
+            Challenge::Digest {
+                realm,
+                nonce,
+                opaque,
+                qop_auth,
+                algorithm,
+                count,
+            } => {
+                *count += 1;
+                let nc = format!("{:08x}", count);
+                let cnonce = format!("{:016x}", rand::random::<u64>());
+
+                let mut ha1 = algorithm.hash(&format!(
+                    "{}:{}:{}",
+                    credentials.username, realm, credentials.password
+                ));
+                if let Algorithm::Md5Sess | Algorithm::Sha256Sess = algorithm {
+                    ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, nonce, cnonce));
+                }
+                let ha2 = algorithm.hash(&format!("GET:{}", uri));
+                let response = if *qop_auth {
+                    algorithm.hash(&format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2))
+                } else {
+                    algorithm.hash(&format!("{}:{}:{}", ha1, nonce, ha2))
+                };
+
+                let mut header = format!(
+                    r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+                    quote(&credentials.username),
+                    quote(realm),
+                    quote(nonce),
+                    quote(uri),
+                    algorithm.name(),
+                    response
+                );
+                if let Some(opaque) = opaque {
+                    header.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+                }
+                if *qop_auth {
+                    header.push_str(&format!(r#", qop=auth, nc={}, cnonce="{}""#, nc, cnonce));
+                }
+
+                header
+            }
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Split header value like `Basic realm="a", Digest realm="b", nonce="c"` into schemes
+/// with their lowercased parameter names
+fn parse_challenges(header: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut challenges: Vec<(String, HashMap<String, String>)> = vec![];
+    let mut rest = header;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+
+        let end = rest
+            .find(|c: char| c == ',' || c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let token = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        if !rest.starts_with('=') {
+            challenges.push((token.to_lowercase(), HashMap::new()));
+            continue;
+        }
+
+        rest = rest[1..].trim_start();
+        let mut value = String::new();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &quoted[end..];
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            value.push_str(rest[..end].trim());
+            rest = &rest[end..];
+        }
+
+        if let Some((_, params)) = challenges.last_mut() {
+            params.insert(token.to_lowercase(), value);
+        }
+    }
+
+    challenges
+}
+
+/// MD5 of given bytes, still the most common digest auth algorithm
+fn md5(input: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect();
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i / 16 * 4 + i % 4]));
+        }
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0; 16];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+
+    digest
+}
@@ -0,0 +1,61 @@
+use crate::{CrablerError, Headers, Result};
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Page stored in the cache directory, see `Opts::with_cache_dir`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CachedPage {
+    pub url: String,
+    pub status: u16,
+    pub headers: Headers,
+    pub text: String,
+}
+
+impl CachedPage {
+    /// Load cached page for given url, returns `None` if it was never cached
+    pub(crate) async fn load(dir: &str, url: &str) -> Result<Option<Self>> {
+        let path = cache_path(dir, url);
+        if !path.exists().await {
+            return Ok(None);
+        }
+
+        let content = fs::read(&path).await?;
+        let page: CachedPage = serde_json::from_slice(&content)
+            .map_err(|e| CrablerError::Parse(format!("failed to parse {:?}: {}", path, e)))?;
+
+        // file names are hashes of urls, a collision is treated as a miss
+        if page.url != url {
+            warn!(
+                "Cache entry {:?} belongs to {}, not {}",
+                path, page.url, url
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(page))
+    }
+
+    /// Save page into given cache directory, replacing previous entry of the same url
+    pub(crate) async fn save(&self, dir: &str) -> Result<()> {
+        let content = serde_json::to_vec(self).map_err(|e| CrablerError::Parse(e.to_string()))?;
+        let path = cache_path(dir, &self.url);
+        let tmp_path = path.with_extension("tmp");
+
+        fs::create_dir_all(dir).await?;
+        fs::write(&tmp_path, content).await?;
+        fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+}
+
+/// Cache file of given url, named by FNV-1a hash of the url so names stay stable across builds
+fn cache_path(dir: &str, url: &str) -> PathBuf {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+
+    Path::new(dir).join(format!("{:016x}.json", hash))
+}
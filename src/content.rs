@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of content a navigation expects, see `Response::navigate_expecting`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContentKind {
+    Html,
+    Json,
+    Xml,
+}
+
+impl ContentKind {
+    /// Value of `Accept` header sent when this kind of content is expected
+    pub fn accept(self) -> &'static str {
+        match self {
+            ContentKind::Html => "text/html, application/xhtml+xml",
+            ContentKind::Json => "application/json",
+            ContentKind::Xml => "application/xml, text/xml",
+        }
+    }
+
+    /// Check if `Content-Type` header value, parameters included, is this kind of content
+    pub fn matches(self, content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        match self {
+            ContentKind::Html => mime == "text/html" || mime == "application/xhtml+xml",
+            ContentKind::Json => mime == "application/json" || mime.ends_with("+json"),
+            ContentKind::Xml => {
+                mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml")
+            }
+        }
+    }
+}
+
+/// What to do with a page whose body can not be decoded with its charset, e.g. invalid UTF-8
+/// or an unknown charset, see `Opts::with_body_decode_policy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyDecodePolicy {
+    /// Fail the page with a parse error
+    #[default]
+    Fail,
+    /// Log a warning and skip the page, it gets a 304 response like other skipped work
+    Skip,
+    /// Decode as lossy UTF-8, undecodable bytes become U+FFFD replacement characters
+    Lossy,
+    /// Skip html handlers, raw bytes are only handed to the `on_stream` handler.
+    /// Without one it is the same as `Skip`
+    Binary,
+}
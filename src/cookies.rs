@@ -0,0 +1,67 @@
+use log::warn;
+use surf::http::Cookie;
+
+/// Cookies set by servers during the crawl, enabled with the `cookie-store` feature
+#[derive(Debug, Default)]
+pub(crate) struct CookieJar {
+    cookies: Vec<(String, Cookie<'static>)>,
+}
+
+impl CookieJar {
+    /// Store cookies from `Set-Cookie` header values sent by given host
+    pub(crate) fn store<'a>(&mut self, host: &str, values: impl Iterator<Item = &'a str>) {
+        for value in values {
+            let cookie = match Cookie::parse(value.to_string()) {
+                Ok(cookie) => cookie,
+                Err(e) => {
+                    warn!("Ignoring malformed cookie from {}: {}", host, e);
+                    continue;
+                }
+            };
+
+            let domain = cookie
+                .domain()
+                .map(|domain| domain.trim_start_matches('.').to_lowercase())
+                .unwrap_or_else(|| host.to_string());
+
+            self.cookies.retain(|(d, c)| {
+                !(*d == domain && c.name() == cookie.name() && c.path() == cookie.path())
+            });
+
+            let expired = cookie
+                .max_age()
+                .map(|age| age.whole_seconds() <= 0)
+                .unwrap_or(false);
+            if !expired {
+                self.cookies.push((domain, cookie));
+            }
+        }
+    }
+
+    /// Cookies that would be sent to given host
+    pub(crate) fn cookies_for(&self, host: &str) -> Vec<Cookie<'static>> {
+        let host = host.to_lowercase();
+
+        self.cookies
+            .iter()
+            .filter(|(domain, _)| host == *domain || host.ends_with(&format!(".{}", domain)))
+            .map(|(_, cookie)| cookie.clone())
+            .collect()
+    }
+
+    /// Value for `Cookie` request header to given host and path
+    pub(crate) fn header_for(&self, host: &str, path: &str) -> Option<String> {
+        let pairs = self
+            .cookies_for(host)
+            .iter()
+            .filter(|cookie| path.starts_with(cookie.path().unwrap_or("/")))
+            .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+            .collect::<Vec<_>>();
+
+        if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.join("; "))
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{CrablerError, Result};
+use percent_encoding::percent_decode_str;
+
+/// Content of an inline `data:` uri, see `Opts::with_handle_data_uris`
+#[derive(Debug, PartialEq)]
+pub(crate) struct DataUri {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Check if url uses `data:` scheme, such urls are never fetched from the network
+pub(crate) fn is_data_uri(url: &str) -> bool {
+    url.get(..5)
+        .map(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .unwrap_or(false)
+}
+
+impl DataUri {
+    /// Decode `data:[<media type>][;base64],<data>` uri
+    pub(crate) fn parse(url: &str) -> Result<Self> {
+        let (header, data) = url[5..]
+            .split_once(',')
+            .ok_or_else(|| CrablerError::Parse("data uri without a comma".to_string()))?;
+
+        let (media_type, is_base64) = match header.strip_suffix(";base64") {
+            Some(media_type) => (media_type, true),
+            None => (header, false),
+        };
+        let media_type = if media_type.is_empty() {
+            "text/plain;charset=US-ASCII".to_string()
+        } else {
+            media_type.to_string()
+        };
+
+        let data = percent_decode_str(data).collect::<Vec<_>>();
+        let data = if is_base64 {
+            let data = data
+                .into_iter()
+                .filter(|byte| !byte.is_ascii_whitespace())
+                .collect::<Vec<_>>();
+            base64::decode(data).map_err(|e| CrablerError::Parse(e.to_string()))?
+        } else {
+            data
+        };
+
+        Ok(DataUri { media_type, data })
+    }
+}
@@ -0,0 +1,127 @@
+use percent_encoding::percent_decode;
+use url::Url;
+
+/// Name of a file saved into a directory, see `Response::download_to_dir`.
+/// Taken from `Content-Disposition` header when server sent one, from the last segment
+/// of url path otherwise, falls back to `download`
+pub(crate) fn download_file_name(url: &str, content_disposition: Option<&str>) -> String {
+    content_disposition
+        .and_then(disposition_file_name)
+        .or_else(|| {
+            let url = Url::parse(url).ok()?;
+            let segment = url.path_segments()?.next_back()?.to_string();
+            let segment = percent_decode(segment.as_bytes()).decode_utf8().ok()?;
+            Some(segment.to_string())
+        })
+        .map(|name| sanitize(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "download".to_string())
+}
+
+/// Join directory and file name, directory may or may not end with a separator
+pub(crate) fn join_dir(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') || dir.ends_with(std::path::MAIN_SEPARATOR) {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// File name from `Content-Disposition` header value, `filename*` (RFC 5987) wins over `filename`
+fn disposition_file_name(value: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+
+    for (name, value) in parameters(value) {
+        match name.as_str() {
+            "filename*" => extended = extended.or_else(|| decode_extended(&value)),
+            "filename" => plain = plain.or(Some(value)),
+            _ => {}
+        }
+    }
+
+    extended.or(plain)
+}
+
+/// Parameters of a header value like `attachment; filename="a; b.txt"; size=3`,
+/// names are lowercased, quoted values are unescaped
+fn parameters(value: &str) -> Vec<(String, String)> {
+    let mut parameters = vec![];
+    // disposition type comes first, it has no value
+    let mut rest = match value.find(';') {
+        Some(start) => &value[start + 1..],
+        None => return parameters,
+    };
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+
+        let name_end = rest.find(['=', ';']).unwrap_or(rest.len());
+        let name = rest[..name_end].trim().to_lowercase();
+        rest = &rest[name_end..];
+        if !rest.starts_with('=') {
+            continue;
+        }
+        rest = rest[1..].trim_start();
+
+        let mut value = String::new();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &quoted[end..];
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            value.push_str(rest[..end].trim());
+            rest = &rest[end..];
+        }
+
+        parameters.push((name, value));
+    }
+
+    parameters
+}
+
+/// Decode `charset'language'percent-encoded` value, UTF-8 and ISO-8859-1 are supported
+fn decode_extended(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?.to_lowercase();
+    let _language = parts.next()?;
+    let bytes: Vec<u8> = percent_decode(parts.next()?.as_bytes()).collect();
+
+    match charset.as_str() {
+        "utf-8" => String::from_utf8(bytes).ok(),
+        "iso-8859-1" => Some(bytes.into_iter().map(char::from).collect()),
+        _ => None,
+    }
+}
+
+/// Keep only the last path component, so server can not write outside of the directory
+fn sanitize(name: &str) -> String {
+    let name = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let name = name.trim();
+
+    if name == "." || name == ".." {
+        String::new()
+    } else {
+        name.to_string()
+    }
+}
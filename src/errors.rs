@@ -1,38 +1,141 @@
 use async_std::channel::{RecvError, SendError};
 use std::fmt::Debug;
 use std::io;
+use std::string::FromUtf8Error;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum CrablerError {
+    /// Request failed before a response was received, e.g. DNS or connection failure
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// Request or reading of response body timed out
+    #[error("timeout: {0}")]
+    Timeout(String),
+
+    /// Server responded with status that makes the result unusable, e.g. for a download
+    #[error("http error {0}")]
+    Http(u16),
+
+    /// Response body could not be parsed or decoded
+    #[error("parse error: {0}")]
+    Parse(String),
+
+    /// Local IO failure, e.g. writing a downloaded file
     #[error("io error {0}")]
     Io(#[from] io::Error),
 
+    /// Url was disallowed by robots.txt
+    #[error("disallowed by robots.txt: {0}")]
+    Robots(String),
+
+    /// Response was not the kind of content navigation expected, see `Response::navigate_expecting`
+    #[error("unexpected content: {0}")]
+    UnexpectedContent(String),
+
+    /// Request would need network access while crawl is offline, see `Opts::with_offline`
+    #[error("not cached and crawl is offline: {0}")]
+    Offline(String),
+
+    /// Response body was not received in time, see `Opts::with_body_read_timeout`
+    #[error("body read timed out: {0}")]
+    BodyTimeout(String),
+
+    /// Response body was larger than allowed limit of bytes
+    #[error("body larger than {0} bytes")]
+    BodyTooLarge(u64),
+
     #[error("failed to recieve workload from async channel {0}")]
     AsyncRecvError(#[from] RecvError),
 
     #[error("failed to send workload to async channel: {0}")]
     AsyncSendError(String),
 
-    #[error("surf error {0}: {1}")]
-    SurfError(surf::StatusCode, String),
+    #[error("state file error: {0}")]
+    StateFile(String),
+
+    #[error("crawl already finished")]
+    CrawlFinished,
+
+    #[error("all workers stopped after {0} restarts each")]
+    WorkersDown(usize),
 
-    #[error("body parsing error: {0}")]
-    BodyParsing(String),
+    /// First url that failed in a crawl with `Opts::with_fail_fast`, with its error
+    #[error("{url} failed: {error}")]
+    Failed {
+        url: String,
+        error: Box<CrablerError>,
+    },
+}
+
+// io errors can not be cloned, copy keeps their kind and message
+impl Clone for CrablerError {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Network(e) => Self::Network(e.clone()),
+            Self::Timeout(e) => Self::Timeout(e.clone()),
+            Self::BodyTimeout(url) => Self::BodyTimeout(url.clone()),
+            Self::Http(status) => Self::Http(*status),
+            Self::Parse(e) => Self::Parse(e.clone()),
+            Self::Io(e) => Self::Io(io::Error::new(e.kind(), e.to_string())),
+            Self::Robots(url) => Self::Robots(url.clone()),
+            Self::UnexpectedContent(e) => Self::UnexpectedContent(e.clone()),
+            Self::Offline(url) => Self::Offline(url.clone()),
+            Self::BodyTooLarge(limit) => Self::BodyTooLarge(*limit),
+            Self::AsyncRecvError(e) => Self::AsyncRecvError(*e),
+            Self::AsyncSendError(e) => Self::AsyncSendError(e.clone()),
+            Self::StateFile(e) => Self::StateFile(e.clone()),
+            Self::CrawlFinished => Self::CrawlFinished,
+            Self::WorkersDown(restarts) => Self::WorkersDown(*restarts),
+            Self::Failed { url, error } => Self::Failed {
+                url: url.clone(),
+                error: error.clone(),
+            },
+        }
+    }
+}
+
+impl CrablerError {
+    /// Whether the same request may succeed when retried: network failures, timeouts,
+    /// server errors and 429 responses. Parse, IO and policy errors are permanent
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Timeout(_) | Self::BodyTimeout(_) => true,
+            Self::Http(status) => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
 }
 
 impl<T: Debug> From<SendError<T>> for CrablerError {
     fn from(err: SendError<T>) -> Self {
-        Self::AsyncSendError(format!(
-            "{:?}",
-            err.into_inner()
-        ))
+        Self::AsyncSendError(format!("{:?}", err.into_inner()))
+    }
+}
+
+impl From<FromUtf8Error> for CrablerError {
+    fn from(err: FromUtf8Error) -> Self {
+        Self::Parse(err.to_string())
     }
 }
 
 impl From<surf::Error> for CrablerError {
     fn from(err: surf::Error) -> Self {
-        Self::SurfError(err.status(), format!("{:?}", err))
+        if err.downcast_ref::<FromUtf8Error>().is_some() {
+            return Self::Parse(err.to_string());
+        }
+
+        let timed_out = match err.downcast_ref::<io::Error>() {
+            Some(e) => e.kind() == io::ErrorKind::TimedOut,
+            None => err.to_string().to_lowercase().contains("timed out"),
+        };
+
+        if timed_out {
+            Self::Timeout(err.to_string())
+        } else {
+            Self::Network(err.to_string())
+        }
     }
 }
 
@@ -0,0 +1,150 @@
+use markup5ever_arcdom::{ArcDom, Handle, NodeData};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use xml5ever::driver::parse_document;
+use xml5ever::tendril::TendrilSink;
+
+/// Content types of feeds discovered with `<link rel="alternate">` on html pages
+pub(crate) const FEED_CONTENT_TYPES: &[&str] = &["application/rss+xml", "application/atom+xml"];
+
+/// Item of an RSS or Atom feed passed to `#[on_feed_item]` handler
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedItem {
+    /// Url of the feed this item was found in
+    pub feed: String,
+    pub title: Option<String>,
+    /// Absolute url of the item, relative links are resolved against the feed url
+    pub link: Option<String>,
+    /// RSS `guid` or Atom `id`
+    pub id: Option<String>,
+    /// Date as written in the feed, RSS `pubDate` or Atom `published`/`updated`
+    pub published: Option<String>,
+    /// RSS `description` or Atom `summary`, falling back to the content of the item
+    pub summary: Option<String>,
+}
+
+/// Parse RSS 2.0, RSS 1.0 or Atom feed, returns `None` when input is not a feed
+pub(crate) fn parse_feed(text: &str, url: &str) -> Option<Vec<FeedItem>> {
+    let dom = parse_document(ArcDom::default(), Default::default()).one(text);
+    let root = children(&dom.document)
+        .into_iter()
+        .find(|node| local_name(node).is_some())?;
+
+    let entries: Vec<Handle> = match local_name(&root)?.as_str() {
+        "rss" => children(&root)
+            .into_iter()
+            .filter(|node| is_element(node, "channel"))
+            .flat_map(|channel| children(&channel))
+            .filter(|node| is_element(node, "item"))
+            .collect(),
+        "RDF" => children(&root)
+            .into_iter()
+            .filter(|node| is_element(node, "item"))
+            .collect(),
+        "feed" => children(&root)
+            .into_iter()
+            .filter(|node| is_element(node, "entry"))
+            .collect(),
+        _ => return None,
+    };
+
+    let base = Url::parse(url).ok();
+    Some(
+        entries
+            .iter()
+            .map(|entry| feed_item(entry, url, base.as_ref()))
+            .collect(),
+    )
+}
+
+fn feed_item(entry: &Handle, feed: &str, base: Option<&Url>) -> FeedItem {
+    let mut item = FeedItem {
+        feed: feed.to_string(),
+        ..FeedItem::default()
+    };
+
+    for child in children(entry) {
+        let name = match local_name(&child) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        match name.as_str() {
+            "title" => item.title = text(&child),
+            // Atom links are attributes, only the alternate one points to the item itself
+            "link" => match attr(&child, "href") {
+                Some(href) => {
+                    let alternate =
+                        attr(&child, "rel").as_deref().unwrap_or("alternate") == "alternate";
+                    if alternate && item.link.is_none() {
+                        item.link = Some(href);
+                    }
+                }
+                None => item.link = text(&child),
+            },
+            "guid" | "id" => item.id = text(&child),
+            "pubDate" | "published" | "date" => item.published = text(&child),
+            "updated" => item.published = item.published.or_else(|| text(&child)),
+            "description" | "summary" => item.summary = text(&child),
+            "content" | "encoded" => item.summary = item.summary.or_else(|| text(&child)),
+            _ => {}
+        }
+    }
+
+    item.link = item
+        .link
+        .map(|link| match base.and_then(|base| base.join(&link).ok()) {
+            Some(link) => link.to_string(),
+            None => link,
+        });
+
+    item
+}
+
+fn children(node: &Handle) -> Vec<Handle> {
+    node.children.borrow().clone()
+}
+
+fn local_name(node: &Handle) -> Option<String> {
+    match &node.data {
+        NodeData::Element { name, .. } => Some(name.local.to_string()),
+        _ => None,
+    }
+}
+
+fn is_element(node: &Handle, name: &str) -> bool {
+    local_name(node).as_deref() == Some(name)
+}
+
+fn attr(node: &Handle, name: &str) -> Option<String> {
+    match &node.data {
+        NodeData::Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attr| &*attr.name.local == name)
+            .map(|attr| attr.value.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// Trimmed text of all descendants, `None` when there is none
+fn text(node: &Handle) -> Option<String> {
+    fn collect(node: &Handle, out: &mut String) {
+        if let NodeData::Text { contents } = &node.data {
+            out.push_str(&contents.borrow());
+        }
+        for child in node.children.borrow().iter() {
+            collect(child, out);
+        }
+    }
+
+    let mut out = String::new();
+    collect(node, &mut out);
+    let out = out.trim();
+
+    if out.is_empty() {
+        None
+    } else {
+        Some(out.to_string())
+    }
+}
@@ -0,0 +1,676 @@
+use crate::{
+    simhash, trap, Challenge, Channels, CrablerError, CrawlReport, Credentials, JsonValue,
+    RedirectChain, Result, TrapDetection, WorkInput, WorkOutput,
+};
+#[cfg(feature = "cookie-store")]
+use crate::{Cookie, CookieJar};
+use async_std::channel::{bounded, Receiver, Sender};
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::any::{Any, TypeId};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Counter value of a crawl that ran out of work
+const FINISHED: usize = usize::MAX;
+
+/// How a worker got the response for a url, kept for `Opts::with_request_log`
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct RequestTrace {
+    pub(crate) duration: Duration,
+    pub(crate) from_cache: bool,
+}
+
+/// Cloneable control handle for a running crawl.
+///
+/// Create one up front and pass it in with `Opts::with_handle` to control a crawl
+/// started through `run`, or get one from `Response::handle` inside of a handler.
+#[derive(Clone, Debug)]
+pub struct CrablerHandle {
+    pub(crate) workinput_ch: Channels<WorkInput>,
+    pub(crate) counter: Arc<AtomicUsize>,
+    pub(crate) live_workers: Arc<AtomicUsize>,
+    stopping: Arc<AtomicBool>,
+    /// `Opts::with_abort_on_found` of the crawl, set once it starts
+    pub(crate) abort_on_found: Arc<AtomicBool>,
+    /// `Opts::with_require_https` and `Opts::with_block_non_http_schemes` of the crawl
+    require_https: Arc<AtomicBool>,
+    block_non_http_schemes: Arc<AtomicBool>,
+    paused_hosts: Arc<Mutex<HashMap<String, Instant>>>,
+    contacted_hosts: Arc<Mutex<HashSet<String>>>,
+    frontier: Arc<Mutex<HashMap<String, WorkInput>>>,
+    pub(crate) report: Arc<Mutex<CrawlReport>>,
+    pub(crate) rng: Arc<Mutex<StdRng>>,
+    page_hashes: Arc<Mutex<Vec<u64>>>,
+    content_hashes: Arc<Mutex<HashSet<u64>>>,
+    items: Arc<Mutex<Vec<JsonValue>>>,
+    host_pages: Arc<Mutex<HashMap<String, usize>>>,
+    query_patterns: Arc<Mutex<HashMap<String, usize>>>,
+    pub(crate) dequeue: Arc<async_std::sync::Mutex<()>>,
+    host_turns: Arc<Mutex<HashMap<String, Receiver<()>>>>,
+    request_traces: Arc<Mutex<HashMap<String, RequestTrace>>>,
+    auth_challenges: Arc<Mutex<HashMap<String, Challenge>>>,
+    redirect_chains: Arc<Mutex<HashMap<String, RedirectChain>>>,
+    /// Senders of events by their type, see `CrablerHandle::events`
+    events: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>>,
+    #[cfg(feature = "metrics")]
+    pub(crate) requests_in_flight: Arc<AtomicUsize>,
+    #[cfg(feature = "cookie-store")]
+    pub(crate) cookies: Arc<Mutex<CookieJar>>,
+}
+
+impl CrablerHandle {
+    pub fn new() -> Self {
+        CrablerHandle {
+            workinput_ch: Channels::new(),
+            counter: Arc::new(AtomicUsize::new(0)),
+            live_workers: Arc::new(AtomicUsize::new(0)),
+            stopping: Arc::new(AtomicBool::new(false)),
+            abort_on_found: Arc::new(AtomicBool::new(false)),
+            require_https: Arc::new(AtomicBool::new(false)),
+            block_non_http_schemes: Arc::new(AtomicBool::new(false)),
+            paused_hosts: Arc::new(Mutex::new(HashMap::new())),
+            contacted_hosts: Arc::new(Mutex::new(HashSet::new())),
+            frontier: Arc::new(Mutex::new(HashMap::new())),
+            report: Arc::new(Mutex::new(CrawlReport::default())),
+            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+            page_hashes: Arc::new(Mutex::new(vec![])),
+            content_hashes: Arc::new(Mutex::new(HashSet::new())),
+            items: Arc::new(Mutex::new(vec![])),
+            host_pages: Arc::new(Mutex::new(HashMap::new())),
+            query_patterns: Arc::new(Mutex::new(HashMap::new())),
+            dequeue: Arc::new(async_std::sync::Mutex::new(())),
+            host_turns: Arc::new(Mutex::new(HashMap::new())),
+            request_traces: Arc::new(Mutex::new(HashMap::new())),
+            auth_challenges: Arc::new(Mutex::new(HashMap::new())),
+            redirect_chains: Arc::new(Mutex::new(HashMap::new())),
+            events: Arc::new(Mutex::new(HashMap::new())),
+            #[cfg(feature = "metrics")]
+            requests_in_flight: Arc::new(AtomicUsize::new(0)),
+            #[cfg(feature = "cookie-store")]
+            cookies: Arc::new(Mutex::new(CookieJar::default())),
+        }
+    }
+
+    /// Stop accepting new work and return once in-flight work is done.
+    /// Work that is queued but not started yet is drained without being processed,
+    /// further calls to `Response::navigate` and `Response::download_file` become no-ops.
+    pub fn stop_after_current(&self) {
+        info!("Stopping after current work");
+        self.stopping.store(true, Ordering::SeqCst);
+
+        // idle keep-alive crawl has no output coming to notice the stop, send work that wakes it
+        let woken = self
+            .counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |counter| {
+                if counter == FINISHED {
+                    None
+                } else {
+                    Some(counter + 1)
+                }
+            });
+        if woken.is_ok() && self.workinput_ch.tx.try_send(WorkInput::Wake).is_err() {
+            self.counter.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Stop the crawl like `stop_after_current`, but also skip handlers of work that finishes
+    /// after this call. Skipped work is kept as pending, so a crawl with `Opts::with_state_file`
+    /// resumes it. Reason of the first abort is kept in `CrawlReport::aborted`
+    pub fn abort(&self, reason: &str) {
+        info!("Aborting crawl: {}", reason);
+        self.report
+            .lock()
+            .unwrap()
+            .aborted
+            .get_or_insert_with(|| reason.to_string());
+        self.stop_after_current();
+    }
+
+    /// Check if `abort` was called for this crawl
+    pub fn is_aborted(&self) -> bool {
+        self.report.lock().unwrap().aborted.is_some()
+    }
+
+    /// Check if `stop_after_current` was called for this crawl
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Add url to the crawl from outside of handlers, e.g. from another task of a crawl service.
+    /// Can be called before the crawl starts or while it is running. Crawl still ends once it
+    /// runs out of work, after that this fails with `CrablerError::CrawlFinished`.
+    /// With `Opts::with_keep_alive` crawl instead waits for more work until it is stopped
+    pub async fn enqueue(&self, url: &str) -> Result<()> {
+        self.enqueue_work(WorkInput::Navigate {
+            url: url.to_string(),
+            context: None,
+            expecting: None,
+        })
+        .await
+    }
+
+    /// Same as `enqueue`, but downloads url into destination path
+    pub async fn enqueue_download(&self, url: &str, destination: &str) -> Result<()> {
+        self.enqueue_work(WorkInput::Download {
+            url: url.to_string(),
+            destination: destination.to_string(),
+        })
+        .await
+    }
+
+    async fn enqueue_work(&self, workinput: WorkInput) -> Result<()> {
+        if self.is_stopping() {
+            debug!("Crawl is stopping, ignoring {:?}", workinput);
+            return Ok(());
+        }
+
+        if !self.claim_pending(&workinput) {
+            return Ok(());
+        }
+
+        // counter is bumped only if crawl did not finish yet, see `try_finish`
+        let bumped = self
+            .counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |counter| {
+                if counter == FINISHED {
+                    None
+                } else {
+                    Some(counter + 1)
+                }
+            });
+
+        if bumped.is_err() {
+            if let Some(url) = workinput.url() {
+                self.finish_work(url);
+            }
+            return Err(CrablerError::CrawlFinished);
+        }
+
+        self.workinput_ch.tx.send(workinput).await?;
+
+        Ok(())
+    }
+
+    /// Mark crawl as finished if there is no work left,
+    /// done atomically so work enqueued from outside is either processed or rejected
+    pub(crate) fn try_finish(&self) -> bool {
+        self.counter
+            .compare_exchange(0, FINISHED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Snapshot of the crawl report so far
+    pub fn report(&self) -> CrawlReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Snapshot of cookies currently stored for given host, including ones set for a parent domain.
+    /// Later changes to the jar are not reflected in returned cookies
+    #[cfg(feature = "cookie-store")]
+    pub fn cookies_for(&self, host: &str) -> Vec<Cookie<'static>> {
+        self.cookies.lock().unwrap().cookies_for(host)
+    }
+
+    /// Pause requests to given host for a duration, rest of the crawl continues as usual.
+    /// Useful to back off when a handler detects a soft block page.
+    /// Pause is tracked as a deadline rather than an extra delay: calling it again
+    /// for the same host keeps whichever deadline ends later.
+    pub fn throttle_host(&self, host: &str, duration: Duration) {
+        info!("Throttling {} for {:?}", host, duration);
+        self.pause_host(host, Instant::now() + duration);
+    }
+
+    /// Pause host for given delay when it is contacted for the first time,
+    /// see `Opts::with_initial_host_delay`
+    pub(crate) fn delay_first_contact(&self, host: &str, delay: Duration) {
+        if self
+            .contacted_hosts
+            .lock()
+            .unwrap()
+            .insert(host.to_string())
+        {
+            debug!("Delaying first request to {} by {:?}", host, delay);
+            self.pause_host(host, Instant::now() + delay);
+        }
+    }
+
+    fn pause_host(&self, host: &str, until: Instant) {
+        let mut paused_hosts = self.paused_hosts.lock().unwrap();
+        let entry = paused_hosts.entry(host.to_string()).or_insert(until);
+
+        if *entry < until {
+            *entry = until;
+        }
+    }
+
+    /// How long requests to given host still have to wait
+    pub(crate) fn host_pause(&self, host: &str) -> Option<Duration> {
+        let mut paused_hosts = self.paused_hosts.lock().unwrap();
+        let until = *paused_hosts.get(host)?;
+        let now = Instant::now();
+
+        if until > now {
+            Some(until - now)
+        } else {
+            paused_hosts.remove(host);
+            None
+        }
+    }
+
+    pub(crate) async fn send_work(&self, mut workinput: WorkInput) -> Result<()> {
+        if self.is_stopping() {
+            debug!("Crawl is stopping, ignoring {:?}", workinput);
+            return Ok(());
+        }
+
+        if let Some(url) = workinput.url_mut() {
+            if !self.apply_scheme_policy(url) {
+                return Ok(());
+            }
+        }
+
+        if !self.claim_pending(&workinput) {
+            return Ok(());
+        }
+
+        debug!("Increasing counter by 1");
+        self.counter.fetch_add(1, Ordering::SeqCst);
+
+        self.workinput_ch.tx.send(workinput).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn set_scheme_policy(&self, require_https: bool, block_non_http_schemes: bool) {
+        self.require_https.store(require_https, Ordering::SeqCst);
+        self.block_non_http_schemes
+            .store(block_non_http_schemes, Ordering::SeqCst);
+    }
+
+    /// Upgrade `http` url to `https` with `Opts::with_require_https`. Returns false when url
+    /// is skipped because of its scheme, see `Opts::with_block_non_http_schemes`
+    fn apply_scheme_policy(&self, url: &mut String) -> bool {
+        let mut parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+
+        let scheme = parsed.scheme().to_string();
+        if scheme == "http" && self.require_https.load(Ordering::SeqCst) {
+            if parsed.set_scheme("https").is_ok() {
+                debug!("Upgrading {} to https", url);
+                *url = parsed.into();
+                self.report.lock().unwrap().https_upgrades += 1;
+            }
+            return true;
+        }
+
+        if self.block_non_http_schemes.load(Ordering::SeqCst)
+            && !matches!(scheme.as_str(), "http" | "https" | "data")
+        {
+            debug!("Skipping {}, {} urls are blocked", url, scheme);
+            *self
+                .report
+                .lock()
+                .unwrap()
+                .scheme_blocked
+                .entry(scheme)
+                .or_default() += 1;
+            return false;
+        }
+
+        true
+    }
+
+    /// Add work to the frontier. Returns false when navigation to the same url is already
+    /// queued or in-flight, such duplicate is coalesced with the pending one and not sent
+    fn claim_pending(&self, workinput: &WorkInput) -> bool {
+        let url = match workinput.url() {
+            Some(url) => url,
+            None => return true,
+        };
+
+        let mut frontier = self.frontier.lock().unwrap();
+        if let (WorkInput::Navigate { .. }, Some(WorkInput::Navigate { .. })) =
+            (workinput, frontier.get(url))
+        {
+            debug!("Navigation to {} is already pending, coalescing", url);
+            return false;
+        }
+
+        frontier.insert(url.to_string(), workinput.clone());
+        true
+    }
+
+    /// Work that is queued or in-flight right now
+    pub(crate) fn frontier(&self) -> Vec<WorkInput> {
+        self.frontier.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Number of urls queued or in-flight right now
+    #[cfg(feature = "metrics")]
+    pub(crate) fn frontier_len(&self) -> usize {
+        self.frontier.lock().unwrap().len()
+    }
+
+    /// Update report counters with processed work output, returns total bytes received so far
+    pub(crate) fn record_output(&self, output: &WorkOutput) -> u64 {
+        let mut report = self.report.lock().unwrap();
+        report.total_bytes += output.body_len();
+
+        match output {
+            WorkOutput::Markup { .. }
+            | WorkOutput::Headers { .. }
+            | WorkOutput::Lines { .. }
+            | WorkOutput::Download { .. } => report.pages += 1,
+            WorkOutput::Error(url, e) => report.errors.push((url.clone(), e.clone())),
+            _ => {}
+        }
+
+        match output {
+            WorkOutput::Markup { status, .. }
+            | WorkOutput::Headers { status, .. }
+            | WorkOutput::Lines { status, .. } => *report.statuses.entry(*status).or_insert(0) += 1,
+            _ => {}
+        }
+
+        if let WorkOutput::Markup { url, .. }
+        | WorkOutput::Headers { url, .. }
+        | WorkOutput::Lines { url, .. }
+        | WorkOutput::Download { url, .. } = output
+        {
+            if let Some(host) = Url::parse(url).ok().as_ref().and_then(Url::host_str) {
+                *report.hosts.entry(host.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        #[cfg(feature = "testing")]
+        match output {
+            WorkOutput::Markup { url, .. }
+            | WorkOutput::Headers { url, .. }
+            | WorkOutput::Lines { url, .. }
+            | WorkOutput::Download { url, .. } => report.visited.push(url.clone()),
+            WorkOutput::Error(url, e) => report.failed.push((url.clone(), e.to_string())),
+            _ => {}
+        }
+
+        report.total_bytes
+    }
+
+    /// Keep how the response for url was fetched until its output is logged
+    pub(crate) fn trace_request(&self, url: &str, trace: RequestTrace) {
+        self.request_traces
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), trace);
+    }
+
+    /// Take trace kept for url, `None` when the url was not fetched, e.g. it was skipped
+    pub(crate) fn take_request_trace(&self, url: &str) -> Option<RequestTrace> {
+        self.request_traces.lock().unwrap().remove(url)
+    }
+
+    /// Keep redirects followed while fetching url until its response is handled
+    pub(crate) fn record_redirect_chain(&self, url: &str, chain: RedirectChain) {
+        self.redirect_chains
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), chain);
+    }
+
+    /// Take redirect chain kept for url, empty when fetching it was not redirected
+    pub(crate) fn take_redirect_chain(&self, url: &str) -> RedirectChain {
+        self.redirect_chains
+            .lock()
+            .unwrap()
+            .remove(url)
+            .unwrap_or_default()
+    }
+
+    /// Remember latest authentication challenge of a host
+    pub(crate) fn store_challenge(&self, host: &str, challenge: Challenge) {
+        self.auth_challenges
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), challenge);
+    }
+
+    /// `Authorization` header answering challenge remembered for host, `None` until host sent one
+    pub(crate) fn authorization_for(
+        &self,
+        host: &str,
+        credentials: &Credentials,
+        uri: &str,
+    ) -> Option<String> {
+        self.auth_challenges
+            .lock()
+            .unwrap()
+            .get_mut(host)
+            .map(|challenge| challenge.authorization(credentials, uri))
+    }
+
+    /// Count elements matched by `on_html` selector on a page
+    #[cfg(feature = "testing")]
+    pub(crate) fn record_selector_matches(&self, selector: &str, matches: usize) {
+        *self
+            .report
+            .lock()
+            .unwrap()
+            .selector_matches
+            .entry(selector.to_string())
+            .or_insert(0) += matches;
+    }
+
+    /// Count a page against budget of its host, returns false once host already used up its budget.
+    /// Urls over budget are counted in the report
+    pub(crate) fn take_host_budget(&self, url: &str, max_pages: usize) -> bool {
+        let host = match Url::parse(url) {
+            Ok(parsed) => parsed.host_str().unwrap_or_default().to_string(),
+            Err(_) => return true,
+        };
+
+        let mut host_pages = self.host_pages.lock().unwrap();
+        let pages = host_pages.entry(host.clone()).or_insert(0);
+
+        if *pages < max_pages {
+            *pages += 1;
+            true
+        } else {
+            info!("Skipping {}, page budget of {} is used up", url, host);
+            let mut report = self.report.lock().unwrap();
+            *report.over_budget.entry(host).or_insert(0) += 1;
+            false
+        }
+    }
+
+    /// Check url against crawler trap heuristics, counting its query pattern when it passes.
+    /// Trap urls are recorded in the report
+    pub(crate) fn is_trap(&self, url: &str, config: &TrapDetection) -> bool {
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => return false,
+        };
+
+        let trap = if trap::segment_repeats(&parsed) > config.max_segment_repeats {
+            true
+        } else if let Some(pattern) = trap::query_pattern(&parsed) {
+            let mut query_patterns = self.query_patterns.lock().unwrap();
+            let variants = query_patterns.entry(pattern).or_insert(0);
+            if *variants < config.max_query_variants {
+                *variants += 1;
+                false
+            } else {
+                true
+            }
+        } else {
+            false
+        };
+
+        if trap {
+            info!("Skipping {}, it looks like a crawler trap", url);
+            self.report.lock().unwrap().traps.push(url.to_string());
+        }
+
+        trap
+    }
+
+    /// Take the next turn to send a request to given ordered host, see `Opts::with_ordered_hosts`
+    pub(crate) fn take_host_turn(&self, host: &str) -> HostTurn {
+        let (done, finished) = bounded(1);
+        let previous = self
+            .host_turns
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), finished);
+
+        HostTurn {
+            previous,
+            _done: done,
+        }
+    }
+
+    /// Check page hash against hashes of pages seen so far, remembering it when page is new.
+    /// Near-duplicates are recorded in the report
+    pub(crate) fn is_near_duplicate(&self, url: &str, hash: u64, max_distance: u32) -> bool {
+        let mut page_hashes = self.page_hashes.lock().unwrap();
+        let duplicate = page_hashes
+            .iter()
+            .any(|seen| simhash::distance(*seen, hash) <= max_distance);
+
+        if duplicate {
+            info!("Skipping {}, near-duplicate of an already seen page", url);
+            self.report
+                .lock()
+                .unwrap()
+                .near_duplicates
+                .push(url.to_string());
+        } else {
+            page_hashes.push(hash);
+        }
+
+        duplicate
+    }
+
+    /// Check if a page with byte-identical markup was already processed, remembering markup
+    /// when it is new. Duplicates are recorded in the report
+    pub(crate) fn is_duplicate_content(&self, url: &str, text: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+
+        let duplicate = !self.content_hashes.lock().unwrap().insert(hasher.finish());
+        if duplicate {
+            info!("Skipping {}, same markup as an already seen page", url);
+            self.report
+                .lock()
+                .unwrap()
+                .duplicate_content
+                .push(url.to_string());
+        }
+
+        duplicate
+    }
+
+    /// Context attached to queued or in-flight work for given url
+    pub(crate) fn work_context(&self, url: &str) -> Option<String> {
+        match self.frontier.lock().unwrap().get(url) {
+            Some(WorkInput::Navigate { context, .. }) => context.clone(),
+            _ => None,
+        }
+    }
+
+    /// Buffer item emitted with `Response::emit` until its batch fills
+    /// Receive events of type `E` emitted by handlers with `Response::emit_event`, e.g.
+    /// milestones or custom metrics that are not scraped items. Channel holds up to `capacity`
+    /// events, once it is full `emit_event` waits for the receiver, so a slow receiver slows
+    /// down handlers and with them the crawl. Channel is closed once the crawl is done.
+    /// Calling it again for the same type replaces the previous receiver
+    pub fn events<E: Send + 'static>(&self, capacity: usize) -> Receiver<E> {
+        let (tx, rx) = bounded(capacity.max(1));
+        self.events
+            .lock()
+            .unwrap()
+            .insert(TypeId::of::<E>(), Box::new(tx));
+
+        rx
+    }
+
+    pub(crate) fn event_sender<E: Send + 'static>(&self) -> Option<Sender<E>> {
+        self.events
+            .lock()
+            .unwrap()
+            .get(&TypeId::of::<E>())
+            .and_then(|tx| tx.downcast_ref::<Sender<E>>())
+            .cloned()
+    }
+
+    /// Drop senders of all events, so receivers see the end of the crawl
+    pub(crate) fn close_events(&self) {
+        self.events.lock().unwrap().clear();
+    }
+
+    pub(crate) fn push_item(&self, item: JsonValue) {
+        self.items.lock().unwrap().push(item);
+    }
+
+    /// Take full batches of buffered items, with `all` the last partial batch as well.
+    /// Items are discarded when there is no batch handler to pass them to
+    pub(crate) fn take_batches(&self, size: Option<usize>, all: bool) -> Vec<Vec<JsonValue>> {
+        let mut items = self.items.lock().unwrap();
+        let size = match size {
+            Some(size) => size,
+            None => {
+                items.clear();
+                return vec![];
+            }
+        };
+
+        let full = items.len() / size * size;
+        let take = if all { items.len() } else { full };
+
+        items
+            .drain(..take)
+            .collect::<Vec<_>>()
+            .chunks(size)
+            .map(|batch| batch.to_vec())
+            .collect()
+    }
+
+    /// Queued or in-flight work for given url
+    pub(crate) fn pending_work(&self, url: &str) -> Option<WorkInput> {
+        self.frontier.lock().unwrap().get(url).cloned()
+    }
+
+    /// Remove work for given url from the frontier once it was fully processed
+    pub(crate) fn finish_work(&self, url: &str) {
+        self.frontier.lock().unwrap().remove(url);
+    }
+}
+
+impl Default for CrablerHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turn of a work item on an ordered host, the next turn starts once this one is dropped
+pub(crate) struct HostTurn {
+    previous: Option<Receiver<()>>,
+    _done: Sender<()>,
+}
+
+impl HostTurn {
+    /// Wait until the work of the previous turn is done
+    pub(crate) async fn wait(&self) {
+        if let Some(previous) = &self.previous {
+            // nothing is ever sent, channel closes once previous turn is dropped
+            let _ = previous.recv().await;
+        }
+    }
+}
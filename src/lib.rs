@@ -27,29 +27,125 @@
 //!
 //!#[async_std::main]
 //!async fn main() -> Result<()> {
-//!    let scraper = Scraper {};
+//!    let mut scraper = Scraper {};
 //!
 //!    scraper.run(Opts::new().with_urls(vec!["https://www.rust-lang.org/"])).await
 //!}
 //!```
 
 mod opts;
+use async_lock::Semaphore;
 use async_std::task::JoinHandle;
 pub use opts::*;
 
 mod errors;
 pub use errors::*;
 
-use async_std::channel::{unbounded, Receiver, RecvError, Sender};
-use async_std::fs::File;
+mod handle;
+pub use handle::*;
+
+mod sitemap;
+use sitemap::*;
+
+mod feed;
+pub use feed::FeedItem;
+use feed::{parse_feed, FEED_CONTENT_TYPES};
+
+mod state;
+use state::*;
+
+mod simhash;
+
+mod trap;
+pub use trap::TrapDetection;
+
+mod termination;
+pub use termination::*;
+
+mod content;
+pub use content::*;
+
+mod url_pattern;
+pub use url_pattern::*;
+
+mod text;
+pub use text::*;
+
+mod stream;
+pub use stream::*;
+
+mod sse;
+use sse::SseParser;
+pub use sse::{SseEvent, SSE_CONTENT_TYPE};
+
+mod visited;
+use visited::VisitedLinks;
+pub use visited::{BloomVisitedStore, MemoryVisitedStore, VisitedStore};
+
+mod cache;
+use cache::*;
+
+mod data_uri;
+use data_uri::*;
+
+mod pagination;
+pub use pagination::{AjaxPagination, AjaxPaginationStop, PAGE_PLACEHOLDER};
+
+mod disposition;
+use disposition::{download_file_name, join_dir};
+
+mod warc;
+use warc::*;
+
+mod request_log;
+use request_log::*;
+
+mod auth;
+use auth::Challenge;
+pub use auth::Credentials;
+
+#[cfg(feature = "cookie-store")]
+mod cookies;
+#[cfg(feature = "cookie-store")]
+use cookies::*;
+#[cfg(feature = "cookie-store")]
+pub use surf::http::Cookie;
+
+mod report;
+pub use report::*;
+
+#[cfg(feature = "metrics")]
+mod metrics;
+
+mod robots;
+pub use robots::*;
+
+mod pool;
+pub use pool::*;
+
+mod sink;
+pub use sink::*;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "testing")]
+pub use testing::{ChaosConfig, ResponseBuilder};
+
+use async_std::channel::{bounded, unbounded, Receiver, RecvError, Sender};
 use async_std::prelude::*;
 use async_std::sync::RwLock;
 pub use crabquery::{Document, Element};
 use log::{debug, error, info, warn};
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+pub use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+pub use surf;
+pub use surf::http::Mime;
+pub use url::Url;
 
 pub use async_trait::async_trait;
 pub use crabler_derive::ImmutableWebScraper;
@@ -72,8 +168,49 @@ pub trait MutableWebScraper {
         element: Element,
     ) -> Result<()>;
     async fn dispatch_on_response(&mut self, response: Response) -> Result<()>;
+    async fn dispatch_on_json_ld(&mut self, response: Response, value: JsonValue) -> Result<()>;
+    async fn dispatch_on_lines(
+        &mut self,
+        content_type: &str,
+        response: Response,
+        line: String,
+    ) -> Result<()>;
     fn all_html_selectors(&self) -> Vec<&str>;
+    async fn dispatch_on_html_all(
+        &mut self,
+        selector: &str,
+        response: Response,
+        elements: Vec<Element>,
+    ) -> Result<()>;
+    fn all_html_all_selectors(&self) -> Vec<&str>;
+    async fn dispatch_on_html_for(
+        &mut self,
+        index: usize,
+        response: Response,
+        element: Element,
+    ) -> Result<()>;
+    fn all_html_url_patterns(&self) -> Vec<(UrlPattern, &str)>;
+    fn has_json_ld_handlers(&self) -> bool;
+    fn all_line_content_types(&self) -> Vec<&str>;
+    async fn dispatch_on_batch(&mut self, items: Vec<JsonValue>) -> Result<()>;
+    async fn dispatch_on_stream(&mut self, response: Response, body: BodyStream) -> Result<()>;
+    fn has_stream_handler(&self) -> bool;
+    async fn dispatch_on_sse(&mut self, response: Response, event: SseEvent) -> Result<()>;
+    fn has_sse_handler(&self) -> bool;
+    async fn dispatch_on_feed_item(&mut self, response: Response, item: FeedItem) -> Result<()>;
+    fn batch_size(&self) -> Option<usize>;
     async fn run(&mut self, opts: Opts) -> Result<()>;
+
+    /// Convenience wrapper that runs the crawl on async-std runtime and blocks until it is done,
+    /// returning the crawl report. Must not be called from within an async context,
+    /// use `run` there instead
+    #[cfg(feature = "blocking")]
+    fn run_blocking(&mut self, opts: Opts) -> Result<CrawlReport> {
+        let handle = opts.handle.clone().unwrap_or_default();
+        async_std::task::block_on(self.run(opts.with_handle(handle.clone())))?;
+
+        Ok(handle.report())
+    }
 }
 
 #[async_trait(?Send)]
@@ -85,70 +222,396 @@ pub trait ImmutableWebScraper {
         element: Element,
     ) -> Result<()>;
     async fn dispatch_on_response(&self, response: Response) -> Result<()>;
+    async fn dispatch_on_json_ld(&self, response: Response, value: JsonValue) -> Result<()>;
+    async fn dispatch_on_lines(
+        &self,
+        content_type: &str,
+        response: Response,
+        line: String,
+    ) -> Result<()>;
     fn all_html_selectors(&self) -> Vec<&str>;
+    async fn dispatch_on_html_all(
+        &self,
+        selector: &str,
+        response: Response,
+        elements: Vec<Element>,
+    ) -> Result<()>;
+    fn all_html_all_selectors(&self) -> Vec<&str>;
+    async fn dispatch_on_html_for(
+        &self,
+        index: usize,
+        response: Response,
+        element: Element,
+    ) -> Result<()>;
+    fn all_html_url_patterns(&self) -> Vec<(UrlPattern, &str)>;
+    fn has_json_ld_handlers(&self) -> bool;
+    fn all_line_content_types(&self) -> Vec<&str>;
+    async fn dispatch_on_batch(&self, items: Vec<JsonValue>) -> Result<()>;
+    async fn dispatch_on_stream(&self, response: Response, body: BodyStream) -> Result<()>;
+    fn has_stream_handler(&self) -> bool;
+    async fn dispatch_on_sse(&self, response: Response, event: SseEvent) -> Result<()>;
+    fn has_sse_handler(&self) -> bool;
+    async fn dispatch_on_feed_item(&self, response: Response, item: FeedItem) -> Result<()>;
+    fn batch_size(&self) -> Option<usize>;
     async fn run(&self, opts: Opts) -> Result<()>;
+
+    /// Convenience wrapper that runs the crawl on async-std runtime and blocks until it is done,
+    /// returning the crawl report. Must not be called from within an async context,
+    /// use `run` there instead
+    #[cfg(feature = "blocking")]
+    fn run_blocking(&self, opts: Opts) -> Result<CrawlReport> {
+        let handle = opts.handle.clone().unwrap_or_default();
+        async_std::task::block_on(self.run(opts.with_handle(handle.clone())))?;
+
+        Ok(handle.report())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum WorkInput {
-    Navigate(String),
-    Download { url: String, destination: String },
+    Navigate {
+        url: String,
+        context: Option<String>,
+        #[serde(default)]
+        expecting: Option<ContentKind>,
+    },
+    Download {
+        url: String,
+        destination: String,
+    },
+    Sitemap {
+        url: String,
+        depth: usize,
+    },
+    Feed {
+        url: String,
+    },
+    /// Wakes up idle event loop of a keep-alive crawl, see `CrablerHandle::stop_after_current`
+    Wake,
     Exit,
 }
 
+impl WorkInput {
+    fn url(&self) -> Option<&str> {
+        match self {
+            WorkInput::Navigate { url, .. }
+            | WorkInput::Download { url, .. }
+            | WorkInput::Sitemap { url, .. }
+            | WorkInput::Feed { url } => Some(url),
+            WorkInput::Wake | WorkInput::Exit => None,
+        }
+    }
+
+    fn url_mut(&mut self) -> Option<&mut String> {
+        match self {
+            WorkInput::Navigate { url, .. }
+            | WorkInput::Download { url, .. }
+            | WorkInput::Sitemap { url, .. }
+            | WorkInput::Feed { url } => Some(url),
+            WorkInput::Wake | WorkInput::Exit => None,
+        }
+    }
+}
+
+/// Response headers keyed by lowercase header name,
+/// repeated headers are joined with `, `
+pub type Headers = HashMap<String, String>;
+/// Urls requested while following redirects with their statuses, see `Response::redirect_chain`
+pub type RedirectChain = Vec<(String, u16)>;
+
+#[derive(Clone)]
 pub struct Response {
     pub url: String,
     pub status: u16,
+    pub headers: Headers,
     pub download_destination: Option<String>,
-    workinput_tx: Sender<WorkInput>,
-    counter: Arc<AtomicUsize>,
+    /// Context attached with `navigate_with_context` to the work that produced this response
+    pub context: Option<String>,
+    /// Directives from `<meta name="robots">` and `X-Robots-Tag` of the page,
+    /// see `Opts::with_respect_meta_robots`
+    pub meta_robots: MetaRobots,
+    /// Every request of a redirected page with its status, starting with `url` and ending
+    /// with the page itself. Empty when page was not redirected, see `Opts::with_max_redirects`
+    pub redirect_chain: RedirectChain,
+    base_url: Option<String>,
+    document: Option<Arc<Document>>,
+    json: Option<Arc<JsonValue>>,
+    follow_fragment_links: bool,
+    max_links: Option<usize>,
+    /// Links navigated to from this page, shared by all clones of the response
+    links: Arc<AtomicUsize>,
+    /// Set by `skip_html`, shared by all clones of the response
+    html_skipped: Arc<AtomicBool>,
+    /// Set by `found`, shared by all clones of the response
+    found: Arc<AtomicBool>,
+    handle: CrablerHandle,
 }
 
 impl Response {
     fn new(
         status: u16,
         url: String,
+        headers: Headers,
         download_destination: Option<String>,
-        workinput_tx: Sender<WorkInput>,
-        counter: Arc<AtomicUsize>,
+        context: Option<String>,
+        handle: CrablerHandle,
     ) -> Self {
         Response {
             status,
             url,
+            headers,
             download_destination,
-            workinput_tx,
-            counter,
+            context,
+            meta_robots: MetaRobots::default(),
+            redirect_chain: vec![],
+            base_url: None,
+            document: None,
+            json: None,
+            follow_fragment_links: false,
+            max_links: None,
+            links: Arc::new(AtomicUsize::new(0)),
+            html_skipped: Arc::new(AtomicBool::new(false)),
+            found: Arc::new(AtomicBool::new(false)),
+            handle,
         }
     }
 
-    /// Schedule scraper to visit given url,
-    /// this will be executed on one of worker tasks
+    /// Schedule scraper to visit given url, relative urls are resolved with `resolve_url`.
+    /// This will be executed on one of worker tasks.
+    /// No-op when page asked not to follow its links with `nofollow`, when url only adds
+    /// a fragment to this page like `#section` (see `Opts::with_follow_fragment_links`),
+    /// once the page used up `Opts::with_max_links_per_page`,
+    /// or when navigation to the same url is already queued or in-flight
     pub async fn navigate(&mut self, url: String) -> Result<()> {
-        debug!("Increasing counter by 1");
-        self.counter.fetch_add(1, Ordering::SeqCst);
-        self.workinput_tx.send(WorkInput::Navigate(url)).await?;
+        self.navigate_work(url, None, None).await
+    }
 
-        Ok(())
+    /// Same as `navigate`, but given context is carried along with the work
+    /// and handed back as `Response::context` to handlers of the visited page
+    pub async fn navigate_with_context(&mut self, url: String, context: String) -> Result<()> {
+        self.navigate_work(url, Some(context), None).await
+    }
+
+    /// Same as `navigate`, but sends `Accept` header asking for given kind of content.
+    /// With `Opts::with_reject_unexpected_content` response of other `Content-Type`
+    /// is reported as `CrablerError::UnexpectedContent` instead of being handled
+    pub async fn navigate_expecting(&mut self, url: String, kind: ContentKind) -> Result<()> {
+        self.navigate_work(url, None, Some(kind)).await
+    }
+
+    async fn navigate_work(
+        &mut self,
+        url: String,
+        context: Option<String>,
+        expecting: Option<ContentKind>,
+    ) -> Result<()> {
+        if self.meta_robots.nofollow {
+            debug!("Not following {} from {}, page is nofollow", url, self.url);
+            return Ok(());
+        }
+
+        let url = self.resolve_url(&url);
+        if !self.follow_fragment_links && is_fragment_of(&url, &self.url) {
+            debug!("Not following {}, it is a fragment of {}", url, self.url);
+            return Ok(());
+        }
+
+        if let Some(max_links) = self.max_links {
+            let links = self.links.fetch_add(1, Ordering::SeqCst);
+            if links == max_links {
+                warn!(
+                    "Not following {} and further links from {}, limit of {} links per page reached",
+                    url, self.url, max_links
+                );
+                self.handle
+                    .report
+                    .lock()
+                    .unwrap()
+                    .link_capped
+                    .push(self.url.clone());
+            }
+            if links >= max_links {
+                debug!("Not following {} from {}, too many links", url, self.url);
+                return Ok(());
+            }
+        }
+
+        self.handle
+            .send_work(WorkInput::Navigate {
+                url,
+                context,
+                expecting,
+            })
+            .await
     }
 
-    /// Schedule scraper to download file from url into destination path
+    /// Schedule scraper to download file from url into destination path,
+    /// relative urls are resolved with `resolve_url`.
+    /// Destination that is an existing directory or ends with `/` is handled like `download_to_dir`
     pub async fn download_file(&mut self, url: String, destination: String) -> Result<()> {
-        debug!("Increasing counter by 1");
-        self.counter.fetch_add(1, Ordering::SeqCst);
-        self.workinput_tx
-            .send(WorkInput::Download { url, destination })
-            .await?;
+        let url = self.resolve_url(&url);
+        self.handle
+            .send_work(WorkInput::Download { url, destination })
+            .await
+    }
+
+    /// Schedule scraper to download file from url into given directory, file is named
+    /// by `Content-Disposition` header of the response (`filename*` or `filename`),
+    /// or by the last segment of url path when server does not name it. Directory parts
+    /// of the name are dropped, so server can not write outside of the directory
+    pub async fn download_to_dir(&mut self, url: String, dir: String) -> Result<()> {
+        let dir = if dir.ends_with('/') {
+            dir
+        } else {
+            format!("{}/", dir)
+        };
+        self.download_file(url, dir).await
+    }
+
+    /// Parsed body of a page fetched by `Opts::with_ajax_pagination`
+    pub fn json(&self) -> Option<&JsonValue> {
+        self.json.as_deref()
+    }
+
+    /// Media type from `Content-Type` header without parameters such as `; charset=`,
+    /// e.g. `text/html`. Returned as sent, media types are case-insensitive
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Parsed `Content-Type` header, parameters included. `None` when missing or malformed
+    pub fn mime(&self) -> Option<Mime> {
+        self.headers.get("content-type")?.parse().ok()
+    }
+
+    /// First element of the page matching given selector, `None` when nothing matches
+    /// or response is not an html page. See `Response::select_all` for supported selectors
+    pub fn select_one(&self, selector: &str) -> Option<Element> {
+        self.select_all(selector).into_iter().next()
+    }
+
+    /// All elements of the page matching given selector in document order,
+    /// empty when response is not an html page, e.g. a download or an error.
+    ///
+    /// Selectors are matched by crabquery, which supports tag `a`, class `.button`,
+    /// id `#main` and attribute selectors `[href]`, `[href="exact"]`, `[href*="contains"]`,
+    /// `[href^="begins"]`, `[href$="ends"]`, descendant `div a` and child `div > a` combinators,
+    /// and combinations like `div.content > a[href]`. Pseudo-classes such as `:nth-child`,
+    /// selector lists with `,` and namespaces are not supported, select a wider set
+    /// and filter the elements instead
+    pub fn select_all(&self, selector: &str) -> Vec<Element> {
+        match &self.document {
+            Some(document) => document.select(selector),
+            None => vec![],
+        }
+    }
+
+    /// Resolve possibly relative url found on the page against `<base href>` of the page
+    /// when it has one, or against page url otherwise. Protocol-relative urls like
+    /// `//cdn.example.com/x.js` take the scheme of the page. Absolute urls are returned as is
+    pub fn resolve_url(&self, url: &str) -> String {
+        let base = self.base_url.as_ref().unwrap_or(&self.url);
+
+        match Url::parse(base).and_then(|base| base.join(url)) {
+            Ok(resolved) => resolved.into(),
+            Err(_) => url.to_string(),
+        }
+    }
+
+    /// Emit an item scraped from this response, items are passed to `on_batch` handler
+    /// in batches. Dropped when scraper has no `on_batch` handler
+    pub fn emit<T: Serialize>(&self, item: &T) -> Result<()> {
+        let item = serde_json::to_value(item).map_err(|e| CrablerError::Parse(e.to_string()))?;
+        self.handle.push_item(item);
 
         Ok(())
     }
+
+    /// Send an event to the receiver from `CrablerHandle::events` of its type, waiting while
+    /// the receiver is behind and its channel is full. Dropped when there is no receiver
+    pub async fn emit_event<E: Send + 'static>(&self, event: E) {
+        if let Some(events) = self.handle.event_sender::<E>() {
+            // receiver may be gone already, same as not having one
+            let _ = events.send(event).await;
+        }
+    }
+
+    /// Skip html handlers of this page, along with following its feeds, meta refresh and
+    /// links of `Opts::with_link_attributes`. Only has an effect when called from `on_response`
+    /// with `DispatchOrder::ResponseFirst`, otherwise they already ran
+    pub fn skip_html(&self) {
+        self.html_skipped.store(true, Ordering::SeqCst);
+    }
+
+    /// Mark this page as the target a focused crawl was looking for. Page is listed in
+    /// `CrawlReport::found` and its remaining `on_html`, `on_html_all`, `on_html_for`
+    /// and `on_json_ld` handlers are not called, `on_response` still is.
+    /// With `Opts::with_abort_on_found` the whole crawl is aborted as well
+    pub fn found(&self) {
+        if self.found.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        info!("Found target on {}", self.url);
+        self.handle
+            .report
+            .lock()
+            .unwrap()
+            .found
+            .push(self.url.clone());
+        if self.handle.abort_on_found.load(Ordering::SeqCst) {
+            self.handle.abort(&format!("found target on {}", self.url));
+        }
+    }
+
+    /// Whether a handler called `found` for this page
+    pub fn is_found(&self) -> bool {
+        self.found.load(Ordering::SeqCst)
+    }
+
+    /// Abort the whole crawl, e.g. once the page that was looked for is found or a ban page
+    /// shows up. Handlers already running finish, see `CrablerHandle::abort`
+    pub fn abort_crawl(&self, reason: &str) {
+        self.handle.abort(reason);
+    }
+
+    /// Get handle of the crawl this response belongs to
+    pub fn handle(&self) -> CrablerHandle {
+        self.handle.clone()
+    }
 }
 
-#[derive(Clone)]
+/// Check if url only adds a fragment to the page url, e.g. `https://x.com/a#b` of `https://x.com/a`
+fn is_fragment_of(url: &str, page: &str) -> bool {
+    match (Url::parse(url), Url::parse(page)) {
+        (Ok(mut url), Ok(mut page)) if url.fragment().is_some() => {
+            url.set_fragment(None);
+            page.set_fragment(None);
+            url == page
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug)]
 struct Channels<T> {
     tx: Sender<T>,
     rx: Receiver<T>,
 }
 
+impl<T> Clone for Channels<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            rx: self.rx.clone(),
+        }
+    }
+}
+
 impl<T> Channels<T> {
     fn new() -> Self {
         let (tx, rx) = unbounded();
@@ -157,141 +620,834 @@ impl<T> Channels<T> {
     }
 }
 
+/// Crawl started by `run` or `run_steps`, kept between steps
+struct RunState {
+    // failed work kept for the final retry pass, and urls attempted again once it started
+    failed: Vec<WorkInput>,
+    retrying: Option<HashSet<String>>,
+    started: Instant,
+    /// Checkpoints, progress log and snapshots, cancelled when the crawl is done
+    background: Vec<JoinHandle<()>>,
+    finished: bool,
+}
+
 pub struct MutableCrabler<'a, T: MutableWebScraper> {
-    visited_links: Arc<RwLock<HashSet<String>>>,
-    workinput_ch: Channels<WorkInput>,
+    visited_links: Arc<RwLock<VisitedLinks>>,
     workoutput_ch: Channels<WorkOutput>,
     scraper: &'a mut T,
-    counter: Arc<AtomicUsize>,
+    handle: CrablerHandle,
+    opts: Arc<Opts>,
     workers: Vec<async_std::task::JoinHandle<()>>,
+    warc: Option<WarcWriter>,
+    request_log: Option<RequestLog>,
+    html_url_patterns: Vec<(UrlPattern, String)>,
+    run: Option<RunState>,
 }
 
 macro_rules! scraper_new_impl {
-    ( true,$identifier:ident ) => {
+    ( true,$identifier:ident,$opts:ident ) => {{
+        let handle: CrablerHandle = $opts.handle.clone().unwrap_or_default();
+        *handle.rng.lock().unwrap() = $opts.rng();
+        handle.set_scheme_policy($opts.require_https, $opts.block_non_http_schemes);
+        let mut $opts = $opts;
+        #[cfg(feature = "crawl-id")]
+        if $opts.id.is_none() {
+            $opts.id = Some(uuid_v4());
+        }
+        $opts.document_slots = $opts
+            .max_concurrent_documents
+            .map(|documents| Arc::new(Semaphore::new(documents)));
+        $opts.inflight_slots = $opts
+            .max_inflight
+            .map(|requests| Arc::new(Semaphore::new(requests)));
+        let html_url_patterns = $identifier
+            .all_html_url_patterns()
+            .into_iter()
+            .map(|(pattern, selector)| (pattern, selector.to_string()))
+            .collect();
+
         MutableCrabler {
-            visited_links: Arc::new(RwLock::new(HashSet::new())),
-            workinput_ch: Channels::new(),
+            visited_links: Arc::new(RwLock::new($opts.visited_links())),
             workoutput_ch: Channels::new(),
             scraper: $identifier,
-            counter: Arc::new(AtomicUsize::new(0)),
+            handle,
+            warc: $opts.warc_output.as_deref().map(WarcWriter::new),
+            request_log: $opts.request_log.as_deref().map(RequestLog::new),
+            opts: Arc::new($opts),
             workers: vec![],
+            html_url_patterns,
+            run: None,
         }
-    };
-    ( false,$identifier:ident ) => {
+    }};
+    ( false,$identifier:ident,$opts:ident ) => {{
+        let handle: CrablerHandle = $opts.handle.clone().unwrap_or_default();
+        *handle.rng.lock().unwrap() = $opts.rng();
+        handle.set_scheme_policy($opts.require_https, $opts.block_non_http_schemes);
+        let mut $opts = $opts;
+        #[cfg(feature = "crawl-id")]
+        if $opts.id.is_none() {
+            $opts.id = Some(uuid_v4());
+        }
+        $opts.document_slots = $opts
+            .max_concurrent_documents
+            .map(|documents| Arc::new(Semaphore::new(documents)));
+        $opts.inflight_slots = $opts
+            .max_inflight
+            .map(|requests| Arc::new(Semaphore::new(requests)));
+        let html_url_patterns = $identifier
+            .all_html_url_patterns()
+            .into_iter()
+            .map(|(pattern, selector)| (pattern, selector.to_string()))
+            .collect();
+
         ImmutableCrabler {
-            visited_links: Arc::new(RwLock::new(HashSet::new())),
-            workinput_ch: Channels::new(),
+            visited_links: Arc::new(RwLock::new($opts.visited_links())),
             workoutput_ch: Channels::new(),
             scraper: $identifier,
-            counter: Arc::new(AtomicUsize::new(0)),
+            handle,
+            warc: $opts.warc_output.as_deref().map(WarcWriter::new),
+            request_log: $opts.request_log.as_deref().map(RequestLog::new),
+            opts: Arc::new($opts),
             workers: vec![],
+            html_url_patterns,
+            run: None,
         }
-    };
+    }};
 }
 
 macro_rules! scraper_run_impl {
-    ( $identifier:ident ) => {{
-        enable_logging();
+    ( $identifier:ident, $max_steps:expr ) => {{
+        match &$identifier.run {
+            Some(state) if state.finished => {
+                return Ok(CrawlProgress {
+                    processed: 0,
+                    pending: 0,
+                    finished: true,
+                });
+            }
+            Some(_) => {}
+            None => {
+                enable_logging();
+
+                {
+                    let mut report = $identifier.handle.report.lock().unwrap();
+                    report.honored_robots = $identifier.opts.respect_robots_txt;
+                    report.crawl_id = $identifier.opts.id.clone();
+                }
+                $identifier
+                    .handle
+                    .abort_on_found
+                    .store($identifier.opts.abort_on_found, Ordering::SeqCst);
+                if let Some(id) = &$identifier.opts.id {
+                    info!("Starting crawl {}", id);
+                }
+
+                let background = vec![
+                    $identifier.start_checkpoints(),
+                    scraper_start_progress_log(&$identifier.opts, &$identifier.handle),
+                    scraper_start_snapshots(&$identifier.opts, &$identifier.handle),
+                ];
+                $identifier.run = Some(RunState {
+                    failed: vec![],
+                    retrying: None,
+                    started: Instant::now(),
+                    background: background.into_iter().flatten().collect(),
+                    finished: false,
+                });
+            }
+        }
+
+        let ret = $identifier.event_loop($max_steps).await;
+        if let Ok(progress) = &ret {
+            if !progress.finished {
+                return ret;
+            }
+        }
 
-        let ret = $identifier.event_loop().await;
-        $identifier.shutdown().await?;
+        // workers are stopped also when event loop failed, so they do not outlive the crawl
+        let shutdown = $identifier.shutdown().await;
+        $identifier.handle.close_events();
+        let run = $identifier.run.as_mut().unwrap();
+        run.finished = true;
+
+        if let Some(warc) = &$identifier.warc {
+            warc.flush().await?;
+        }
+
+        if let Some(request_log) = &$identifier.request_log {
+            request_log.flush().await?;
+        }
+
+        for task in run.background.drain(..) {
+            task.cancel().await;
+        }
+
+        $identifier.save_state().await?;
+        shutdown?;
         ret
     }};
 }
 
 macro_rules! event_loop_impl {
-    ( $identifier:ident ) => {
+    ( $identifier:ident, $max_steps:expr ) => {{
+        let run = $identifier.run.as_mut().unwrap();
+        let mut failed = std::mem::take(&mut run.failed);
+        let mut retrying = run.retrying.take();
+        let started = run.started;
+        let mut processed = 0;
+
         loop {
+            if $max_steps.is_some_and(|max_steps: usize| processed >= max_steps) {
+                let run = $identifier.run.as_mut().unwrap();
+                run.failed = failed;
+                run.retrying = retrying;
+                return Ok(CrawlProgress {
+                    processed,
+                    pending: $identifier.handle.counter.load(Ordering::SeqCst),
+                    finished: false,
+                });
+            }
+            processed += 1;
+
             let output = $identifier.workoutput_ch.rx.recv().await?;
-            let response_url;
-            let response_status;
-            let mut response_destination = None;
-
-            match output {
-                WorkOutput::Markup { text, url, status } => {
-                    info!("Fetched markup from: {}", url);
-                    let document = Document::from(text);
-                    response_url = url.clone();
-                    response_status = status;
-
-                    let selectors = $identifier
+            // work finishing after an abort is not handled, same as work drained from the queue
+            let output = match output.url() {
+                Some(url)
+                    if $identifier.handle.is_aborted()
+                        && !matches!(output, WorkOutput::Drained(_)) =>
+                {
+                    WorkOutput::Drained(url.to_string())
+                }
+                _ => output,
+            };
+            let finished_url = match &output {
+                // drained work was never processed, keep it in the frontier so it is resumed
+                WorkOutput::Drained(_) => None,
+                // page itself follows as markup, work is not done yet
+                WorkOutput::Stream { .. } => None,
+                output => output.url().map(String::from),
+            };
+            let context = output
+                .url()
+                .and_then(|url| $identifier.handle.work_context(url));
+            let redirect_chain = output
+                .url()
+                .map(|url| $identifier.handle.take_redirect_chain(url))
+                .unwrap_or_default();
+
+            let recovered = match (&output, retrying.as_mut()) {
+                (WorkOutput::Error(url, _), None) if $identifier.opts.final_retry_pass => {
+                    failed.extend($identifier.handle.pending_work(url));
+                    false
+                }
+                (
+                    WorkOutput::Markup { url, .. }
+                    | WorkOutput::Headers { url, .. }
+                    | WorkOutput::Lines { url, .. }
+                    | WorkOutput::Download { url, .. },
+                    Some(retrying),
+                ) => retrying.remove(url),
+                _ => false,
+            };
+            if recovered {
+                info!(
+                    "Recovered {} in the final retry pass",
+                    output.url().unwrap_or_default()
+                );
+                $identifier.handle.report.lock().unwrap().recovered += 1;
+            }
+
+            let total_bytes = $identifier.handle.record_output(&output);
+            if let Some(request_log) = &$identifier.request_log {
+                scraper_log_request(request_log, &$identifier.handle, &output).await?;
+            }
+            if let (true, WorkOutput::Error(url, e)) = ($identifier.opts.fail_fast, &output) {
+                error!("Failing fast on error from {}: {}", url, e);
+                return Err(CrablerError::Failed {
+                    url: url.clone(),
+                    error: Box::new(e.clone()),
+                });
+            }
+            let termination = &$identifier.opts.termination;
+            if !matches!(termination, TerminationCondition::QueueEmpty)
+                && !$identifier.handle.is_stopping()
+            {
+                let report = $identifier.handle.report();
+                if $identifier
+                    .opts
+                    .termination
+                    .is_met(&report, started.elapsed())
+                {
+                    info!("Termination condition met after {} pages", report.pages);
+                    $identifier.handle.stop_after_current();
+                }
+            }
+            if let Some(max_total_bytes) = $identifier.opts.max_total_bytes {
+                if total_bytes >= max_total_bytes && !$identifier.handle.is_stopping() {
+                    info!("Downloaded {} bytes, limit reached", total_bytes);
+                    $identifier.handle.stop_after_current();
+                }
+            }
+
+            let is_markup = matches!(output, WorkOutput::Markup { .. });
+            let response = match output {
+                // e.g. 204, 304 or HEAD responses, an empty document would match nothing useful
+                WorkOutput::Markup {
+                    text,
+                    url,
+                    status,
+                    headers,
+                } if text.trim().is_empty() => {
+                    debug!("No body in {} ({}), skipping html handlers", url, status);
+                    if let Some(warc) = &$identifier.warc {
+                        if url.starts_with("http") {
+                            warc.write_response(&url, status, &headers, &text).await?;
+                        }
+                    }
+
+                    let mut page = Response::new(
+                        status,
+                        url,
+                        headers,
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    );
+                    page.redirect_chain = redirect_chain;
+
+                    Some(page)
+                }
+                WorkOutput::Markup {
+                    text,
+                    url,
+                    status,
+                    headers,
+                } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Fetched { url: &url, status },
+                    );
+                    if let Some(warc) = &$identifier.warc {
+                        if url.starts_with("http") {
+                            warc.write_response(&url, status, &headers, &text).await?;
+                        }
+                    }
+
+                    let text = match &$identifier.opts.html_preprocessor {
+                        Some(html_preprocessor) => (html_preprocessor.0)(text),
+                        None => text,
+                    };
+                    let document = Arc::new(Document::from(text.as_str()));
+                    warn_on_parse_anomaly(&url, &text, &document);
+
+                    let mut page = Response::new(
+                        status,
+                        url.clone(),
+                        headers,
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    );
+                    if $identifier.opts.respect_meta_robots {
+                        page.meta_robots = MetaRobots::from_document(&document);
+                        if let Some(tag) = page.headers.get("x-robots-tag") {
+                            let header = MetaRobots::from_x_robots_tag(tag);
+                            if header != MetaRobots::default() {
+                                debug!("Applying X-Robots-Tag of {}: {}", url, tag);
+                                $identifier.handle.report.lock().unwrap().x_robots_tags += 1;
+                            }
+                            page.meta_robots = page.meta_robots.union(header);
+                        }
+                    }
+                    if let Some((pagination, page_number)) = $identifier
+                        .opts
+                        .ajax_pagination
+                        .iter()
+                        .find_map(|pagination| Some((pagination, pagination.page_of(&url)?)))
+                    {
+                        match serde_json::from_str::<JsonValue>(&text) {
+                            Ok(json) => {
+                                if (200..300).contains(&status) && !(pagination.stop.0)(&json) {
+                                    let next = pagination.page_url(page_number + 1);
+                                    debug!("Following page {} of {}", page_number + 1, url);
+                                    scraper_navigate(&$identifier.handle, &next, None).await?;
+                                }
+                                page.json = Some(Arc::new(json));
+                            }
+                            Err(e) => warn!("Stopping pagination at {}, not JSON: {}", url, e),
+                        }
+                    }
+                    // links of a redirected page are relative to where it was redirected to
+                    let location = redirect_chain
+                        .last()
+                        .map(|(location, _)| location.clone())
+                        .unwrap_or_else(|| url.clone());
+                    page.base_url = document
+                        .select("base[href]")
+                        .first()
+                        .and_then(|base| base.attr("href"))
+                        .and_then(|href| Url::parse(&location).ok()?.join(&href).ok())
+                        .map(String::from)
+                        .or_else(|| Some(location).filter(|location| *location != url));
+                    page.redirect_chain = redirect_chain;
+                    page.document = Some(document.clone());
+                    page.follow_fragment_links = $identifier.opts.follow_fragment_links;
+                    page.max_links = $identifier.opts.max_links_per_page;
+
+                    let soft_404 = match &$identifier.opts.soft_404_detection {
+                        Some(soft_404_detection) => (soft_404_detection.0)(&url, &document),
+                        None => false,
+                    };
+
+                    if soft_404 {
+                        scraper_log_outcome(&$identifier.opts, UrlOutcome::Soft404 { url: &url });
+                        $identifier.handle.report.lock().unwrap().soft_404s += 1;
+                        page.status = 404;
+                    }
+
+                    let response_first =
+                        $identifier.opts.dispatch_order == DispatchOrder::ResponseFirst;
+                    if response_first {
+                        $identifier
+                            .scraper
+                            .dispatch_on_response(page.clone())
+                            .await?;
+                    }
+
+                    if page.html_skipped.load(Ordering::SeqCst) {
+                        scraper_log_outcome(
+                            &$identifier.opts,
+                            UrlOutcome::HtmlSkipped { url: &url },
+                        );
+                    } else if !soft_404 {
+                        let selectors = $identifier
+                            .scraper
+                            .all_html_selectors()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect::<Vec<_>>();
+
+                        for selector in selectors {
+                            let elements = document.select(selector.as_str());
+                            #[cfg(feature = "testing")]
+                            $identifier
+                                .handle
+                                .record_selector_matches(&selector, elements.len());
+
+                            for el in elements {
+                                if page.is_found() {
+                                    break;
+                                }
+                                $identifier
+                                    .scraper
+                                    .dispatch_on_html(selector.as_str(), page.clone(), el)
+                                    .await?;
+                            }
+                        }
+
+                        let selectors = $identifier
+                            .scraper
+                            .all_html_all_selectors()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect::<Vec<_>>();
+
+                        for selector in selectors {
+                            if page.is_found() {
+                                break;
+                            }
+                            let elements = document.select(selector.as_str());
+                            $identifier
+                                .scraper
+                                .dispatch_on_html_all(selector.as_str(), page.clone(), elements)
+                                .await?;
+                        }
+
+                        for (index, (pattern, selector)) in
+                            $identifier.html_url_patterns.iter().enumerate()
+                        {
+                            if pattern.matches(&url) {
+                                for el in document.select(selector.as_str()) {
+                                    if page.is_found() {
+                                        break;
+                                    }
+                                    $identifier
+                                        .scraper
+                                        .dispatch_on_html_for(index, page.clone(), el)
+                                        .await?;
+                                }
+                            }
+                        }
+
+                        let mut follower = page.clone();
+                        for (selector, attr) in &$identifier.opts.link_attributes {
+                            for el in document.select(selector.as_str()) {
+                                for link in element_links(&el, attr) {
+                                    let link = follower.resolve_url(&link);
+                                    if link.starts_with("http://") || link.starts_with("https://") {
+                                        follower.navigate(link).await?;
+                                    }
+                                }
+                            }
+                        }
+
+                        if $identifier.opts.follow_feeds {
+                            for link in document.select(r#"link[rel="alternate"]"#) {
+                                let is_feed = link.attr("type").map_or(false, |kind| {
+                                    FEED_CONTENT_TYPES
+                                        .iter()
+                                        .any(|feed| kind.trim().eq_ignore_ascii_case(feed))
+                                });
+
+                                if let (true, Some(href)) = (is_feed, link.attr("href")) {
+                                    $identifier
+                                        .handle
+                                        .send_work(WorkInput::Feed {
+                                            url: page.resolve_url(&href),
+                                        })
+                                        .await?;
+                                }
+                            }
+                        }
+
+                        if $identifier.opts.follow_meta_refresh {
+                            match meta_refresh(&document) {
+                                Some((delay, target))
+                                    if delay <= $identifier.opts.meta_refresh_max_delay =>
+                                {
+                                    debug!("Following meta refresh of {} to {}", url, target);
+                                    let target = follower.resolve_url(&target);
+                                    follower.navigate(target).await?;
+                                }
+                                Some((delay, target)) => debug!(
+                                    "Not following meta refresh of {} to {}, delay of {:?}",
+                                    url, target, delay
+                                ),
+                                None => {}
+                            }
+                        }
+
+                        if $identifier.scraper.has_json_ld_handlers() {
+                            for script in document.select(r#"script[type="application/ld+json"]"#) {
+                                if page.is_found() {
+                                    break;
+                                }
+                                let text = script.text().unwrap_or_default();
+
+                                match serde_json::from_str(&text) {
+                                    Ok(value) => {
+                                        $identifier
+                                            .scraper
+                                            .dispatch_on_json_ld(page.clone(), value)
+                                            .await?;
+                                    }
+                                    Err(e) => warn!("Skipping malformed JSON-LD on {}: {}", url, e),
+                                }
+                            }
+                        }
+                    }
+
+                    if response_first {
+                        None
+                    } else {
+                        Some(page)
+                    }
+                }
+                WorkOutput::Lines {
+                    url,
+                    status,
+                    headers,
+                    content_type,
+                    lines,
+                } => {
+                    scraper_log_outcome(&$identifier.opts, UrlOutcome::Lines { url: &url, status });
+                    let page = Response::new(
+                        status,
+                        url,
+                        headers,
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    );
+
+                    let to_lines = $identifier
                         .scraper
-                        .all_html_selectors()
+                        .all_line_content_types()
                         .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<_>>();
-
-                    for selector in selectors {
-                        for el in document.select(selector.as_str()) {
-                            let response = Response::new(
-                                status,
-                                url.clone(),
-                                None,
-                                $identifier.workinput_ch.tx.clone(),
-                                $identifier.counter.clone(),
-                            );
+                        .any(|line_type| line_type.eq_ignore_ascii_case(&content_type));
+                    let mut sse = if content_type == SSE_CONTENT_TYPE
+                        && $identifier.scraper.has_sse_handler()
+                    {
+                        Some(SseParser::default())
+                    } else {
+                        None
+                    };
+
+                    while let Ok(line) = lines.recv().await {
+                        $identifier.handle.report.lock().unwrap().total_bytes +=
+                            line.len() as u64 + 1;
+
+                        if let Some(event) = sse.as_mut().and_then(|sse| sse.push_line(&line)) {
+                            $identifier
+                                .scraper
+                                .dispatch_on_sse(page.clone(), event)
+                                .await?;
+                        }
+
+                        if to_lines {
                             $identifier
                                 .scraper
-                                .dispatch_on_html(selector.as_str(), response, el)
+                                .dispatch_on_lines(&content_type, page.clone(), line)
                                 .await?;
                         }
                     }
+
+                    Some(page)
                 }
-                WorkOutput::Download { url, destination } => {
-                    info!("Downloaded: {} -> {}", url, destination);
-                    response_url = url;
-                    response_destination = Some(destination);
-                    response_status = 200;
+                WorkOutput::Headers {
+                    url,
+                    status,
+                    headers,
+                } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::BodySkipped { url: &url, status },
+                    );
+                    Some(Response::new(
+                        status,
+                        url,
+                        headers,
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    ))
+                }
+                WorkOutput::Download {
+                    url, destination, ..
+                } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Downloaded {
+                            url: &url,
+                            destination: &destination,
+                        },
+                    );
+                    Some(Response::new(
+                        200,
+                        url,
+                        Headers::new(),
+                        Some(destination),
+                        context,
+                        $identifier.handle.clone(),
+                    ))
                 }
                 WorkOutput::Noop(url) => {
-                    info!("Noop: {}", url);
-                    response_url = url;
-                    response_status = 304;
+                    scraper_log_outcome(&$identifier.opts, UrlOutcome::Noop { url: &url });
+                    Some(Response::new(
+                        304,
+                        url,
+                        Headers::new(),
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    ))
                 }
                 WorkOutput::Error(url, e) => {
-                    error!("Error from {}: {}", url, e);
-                    response_url = url;
-                    response_status = 500;
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Error {
+                            url: &url,
+                            error: &e,
+                        },
+                    );
+                    Some(Response::new(
+                        500,
+                        url,
+                        Headers::new(),
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    ))
+                }
+                WorkOutput::Drained(url) => {
+                    scraper_log_outcome(&$identifier.opts, UrlOutcome::Drained { url: &url });
+                    None
+                }
+                WorkOutput::Sitemap { url, discovered } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Sitemap {
+                            url: &url,
+                            entries: discovered,
+                        },
+                    );
+                    None
+                }
+                WorkOutput::Feed { url, items } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Feed {
+                            url: &url,
+                            items: items.len(),
+                        },
+                    );
+                    let page = Response::new(
+                        200,
+                        url,
+                        Headers::new(),
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    );
+
+                    for item in items {
+                        $identifier
+                            .scraper
+                            .dispatch_on_feed_item(page.clone(), item)
+                            .await?;
+                    }
+                    None
+                }
+                WorkOutput::Stream {
+                    url,
+                    status,
+                    headers,
+                    chunks,
+                } => {
+                    scraper_log_outcome(
+                        &$identifier.opts,
+                        UrlOutcome::Streamed { url: &url, status },
+                    );
+                    let page = Response::new(
+                        status,
+                        url,
+                        headers,
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    );
+
+                    $identifier
+                        .scraper
+                        .dispatch_on_stream(page, BodyStream { chunks })
+                        .await?;
+                    None
+                }
+                WorkOutput::Wake => None,
+                WorkOutput::WorkersDown => {
+                    return Err(CrablerError::WorkersDown(
+                        $identifier.opts.max_worker_restarts.unwrap_or_default(),
+                    ));
                 }
                 WorkOutput::Exit => {
                     error!("Recieved exit output");
-                    response_url = "".to_string();
-                    response_status = 500;
+                    Some(Response::new(
+                        500,
+                        "".to_string(),
+                        Headers::new(),
+                        None,
+                        context,
+                        $identifier.handle.clone(),
+                    ))
                 }
+            };
+
+            if let Some(response) = response {
+                $identifier.scraper.dispatch_on_response(response).await?;
             }
 
-            let response = Response::new(
-                response_status,
-                response_url,
-                response_destination,
-                $identifier.workinput_ch.tx.clone(),
-                $identifier.counter.clone(),
-            );
-            $identifier.scraper.dispatch_on_response(response).await?;
+            if let (true, Some(slots)) = (is_markup, &$identifier.opts.document_slots) {
+                slots.add_permits(1);
+            }
+
+            if let Some(url) = finished_url {
+                $identifier.handle.finish_work(&url);
+            }
 
             debug!("Decreasing counter by 1");
-            $identifier.counter.fetch_sub(1, Ordering::SeqCst);
+            $identifier.handle.counter.fetch_sub(1, Ordering::SeqCst);
 
             debug!(
                 "Done processing work output, counter is at {}",
-                $identifier.counter.load(Ordering::SeqCst)
+                $identifier.handle.counter.load(Ordering::SeqCst)
             );
-            if $identifier.counter.load(Ordering::SeqCst) == 0 {
-                return Ok(());
+
+            if retrying.is_none()
+                && !failed.is_empty()
+                && !$identifier.handle.is_stopping()
+                && $identifier.handle.counter.load(Ordering::SeqCst) == 0
+            {
+                info!("Retrying {} failed urls", failed.len());
+                let mut urls = HashSet::new();
+                $identifier.handle.report.lock().unwrap().retried = failed.len();
+
+                for work in failed.drain(..) {
+                    if let Some(url) = work.url() {
+                        let key = $identifier.opts.dedup_key(url);
+                        $identifier.visited_links.write().await.remove(&key);
+                        urls.insert(url.to_string());
+                    }
+                    $identifier.handle.send_work(work).await?;
+                }
+                retrying = Some(urls);
+            }
+
+            // full batches are handed over as they fill, the rest once the crawl is done
+            let idle_ends_crawl = !$identifier.opts.keep_alive || $identifier.handle.is_stopping();
+            let finished = idle_ends_crawl && $identifier.handle.try_finish();
+            let batch_size = $identifier.scraper.batch_size();
+            for batch in $identifier.handle.take_batches(batch_size, finished) {
+                $identifier.scraper.dispatch_on_batch(batch).await?;
+            }
+
+            if finished {
+                return Ok(CrawlProgress {
+                    processed,
+                    pending: 0,
+                    finished: true,
+                });
             }
         }
-    };
+    }};
 }
 
 macro_rules! start_worker_impl {
     ( $identifier:ident ) => {
         let visited_links = $identifier.visited_links.clone();
-        let workinput_rx = $identifier.workinput_ch.rx.clone();
+        let workinput_rx = $identifier.handle.workinput_ch.rx.clone();
         let workoutput_tx = $identifier.workoutput_ch.tx.clone();
+        let handle = $identifier.handle.clone();
+        let opts = $identifier.opts.clone();
+        let mut line_content_types = $identifier
+            .scraper
+            .all_line_content_types()
+            .iter()
+            .map(|content_type| content_type.to_lowercase())
+            .collect::<Vec<_>>();
+        // event streams are read line by line too, framing is parsed in the event loop
+        if $identifier.scraper.has_sse_handler() {
+            line_content_types.push(SSE_CONTENT_TYPE.to_string());
+        }
+        let line_content_types = Arc::new(line_content_types);
+        let stream_pages = $identifier.scraper.has_stream_handler();
 
-        let worker = Worker::new(visited_links, workinput_rx, workoutput_tx);
+        let worker = Worker::new(
+            visited_links,
+            workinput_rx,
+            workoutput_tx,
+            handle,
+            opts,
+            line_content_types,
+            stream_pages,
+        );
+        $identifier
+            .handle
+            .live_workers
+            .fetch_add(1, Ordering::SeqCst);
 
         let handle = async_std::task::spawn(async move {
+            let mut restarts = 0;
+
             loop {
                 info!("🐿️ Starting http worker");
 
@@ -300,9 +1456,19 @@ macro_rules! start_worker_impl {
                         info!("Shutting down worker");
                         break;
                     }
-                    Err(e) => warn!("❌ Restarting worker: {}", e),
-                }
-            }
+                    Err(e) if worker.opts.max_worker_restarts == Some(restarts) => {
+                        error!("❌ Worker failed after {} restarts: {}", restarts, e);
+                        worker.give_up().await;
+                        break;
+                    }
+                    Err(e) => {
+                        let delay = worker.opts.worker_restart_delay(restarts);
+                        warn!("❌ Restarting worker in {:?}: {}", delay, e);
+                        async_std::task::sleep(delay).await;
+                        restarts += 1;
+                    }
+                }
+            }
         });
 
         $identifier.workers.push(handle);
@@ -315,26 +1481,121 @@ where
 {
     /// Create new MutableWebScraper out of given scraper struct
     pub fn new(scraper: &'a mut T) -> Self {
-        scraper_new_impl!(true, scraper)
+        Self::with_opts(scraper, Opts::new())
+    }
+
+    /// Create new MutableWebScraper out of given scraper struct and options
+    pub fn with_opts(scraper: &'a mut T, opts: Opts) -> Self {
+        scraper_new_impl!(true, scraper, opts)
     }
 
     async fn shutdown(&self) -> Result<()> {
-        scraper_shutdown(&self.workers, &self.workinput_ch, &self.workoutput_ch).await
+        scraper_shutdown(
+            &self.workers,
+            &self.handle.workinput_ch,
+            &self.workoutput_ch,
+        )
+        .await
     }
 
     /// Schedule scraper to visit given url,
     /// this will be executed on one of worker tasks
     pub async fn navigate(&self, url: &str) -> Result<()> {
-        scraper_navigate(&self.counter, &self.workinput_ch, url).await
+        scraper_navigate(&self.handle, url, None).await
+    }
+
+    /// Same as `navigate`, but given context is handed back as `Response::context`
+    pub async fn navigate_with_context(&self, url: &str, context: &str) -> Result<()> {
+        scraper_navigate(&self.handle, url, Some(context.to_string())).await
+    }
+
+    /// Schedule scraper to discover urls from given sitemap
+    pub async fn navigate_sitemap(&self, url: &str) -> Result<()> {
+        scraper_navigate_sitemap(&self.handle, url).await
+    }
+
+    /// Schedule scraper to download file from given url into destination path
+    pub async fn download(&self, url: &str, destination: &str) -> Result<()> {
+        scraper_download(&self.handle, url, destination).await
+    }
+
+    /// Restore visited links and pending work from the state file, if there is one,
+    /// and queue pages of `Opts::with_replay_from`.
+    /// Should be called before scheduling any navigation
+    pub async fn restore_state(&self) -> Result<()> {
+        scraper_restore_state(&self.opts, &self.visited_links, &self.handle).await?;
+        scraper_queue_replay(&self.opts, &self.handle).await
+    }
+
+    /// Warm up connections to hosts from `Opts::with_prefetch`.
+    /// Should be called before starting workers
+    pub async fn prefetch(&self) {
+        scraper_prefetch(&self.opts).await
+    }
+
+    async fn save_state(&self) -> Result<()> {
+        scraper_save_state(&self.opts, &self.visited_links, &self.handle).await
+    }
+
+    fn start_checkpoints(&self) -> Option<JoinHandle<()>> {
+        scraper_start_checkpoints(&self.opts, &self.visited_links, &self.handle)
+    }
+
+    /// Get handle that can be used to control this crawl
+    pub fn handle(&self) -> CrablerHandle {
+        self.handle.clone()
+    }
+
+    /// Restore state, schedule urls, sitemaps and downloads from opts and start
+    /// `Opts::with_threads` workers, as `run` of the derived scraper does before crawling
+    pub async fn start(&mut self) -> Result<()> {
+        let opts = self.opts.clone();
+
+        self.restore_state().await?;
+        self.prefetch().await;
+
+        for url in &opts.urls {
+            self.navigate(url).await?;
+        }
+
+        for url in &opts.sitemaps {
+            self.navigate_sitemap(url).await?;
+        }
+
+        for (url, destination) in &opts.download_urls {
+            self.download(url, destination).await?;
+        }
+
+        for pagination in &opts.ajax_pagination {
+            self.navigate(&pagination.page_url(1)).await?;
+        }
+
+        for _ in 0..opts.threads {
+            self.start_worker();
+        }
+
+        Ok(())
     }
 
     /// Run processing loop for the given MutableWebScraper
     pub async fn run(&mut self) -> Result<()> {
-        scraper_run_impl!(self)
+        self.run_until(None).await.map(|_| ())
     }
 
-    async fn event_loop(&mut self) -> Result<()> {
-        event_loop_impl!(self)
+    /// Process at most given number of work outputs and return, so the caller can interleave
+    /// crawling with other work, e.g. a GUI or game loop. Crawl continues where it stopped on
+    /// the next call, workers keep fetching in between. Once the crawl is done it is shut down
+    /// as after `run`, further calls return right away with `CrawlProgress::finished` set
+    pub async fn run_steps(&mut self, steps: usize) -> Result<CrawlProgress> {
+        self.run_until(Some(steps)).await
+    }
+
+    async fn run_until(&mut self, max_steps: Option<usize>) -> Result<CrawlProgress> {
+        scraper_run_impl!(self, max_steps)
+    }
+
+    async fn event_loop(&mut self, max_steps: Option<usize>) -> Result<CrawlProgress> {
+        event_loop_impl!(self, max_steps)
     }
 
     /// Create and start new worker tasks.
@@ -345,12 +1606,16 @@ where
 }
 
 pub struct ImmutableCrabler<'a, T: ImmutableWebScraper> {
-    visited_links: Arc<RwLock<HashSet<String>>>,
-    workinput_ch: Channels<WorkInput>,
+    visited_links: Arc<RwLock<VisitedLinks>>,
     workoutput_ch: Channels<WorkOutput>,
     scraper: &'a T,
-    counter: Arc<AtomicUsize>,
+    handle: CrablerHandle,
+    opts: Arc<Opts>,
     workers: Vec<async_std::task::JoinHandle<()>>,
+    warc: Option<WarcWriter>,
+    request_log: Option<RequestLog>,
+    html_url_patterns: Vec<(UrlPattern, String)>,
+    run: Option<RunState>,
 }
 
 impl<'a, T> ImmutableCrabler<'a, T>
@@ -359,26 +1624,121 @@ where
 {
     /// Create new ImmutableWebScraper out of given scraper struct
     pub fn new(scraper: &'a T) -> Self {
-        scraper_new_impl!(false, scraper)
+        Self::with_opts(scraper, Opts::new())
+    }
+
+    /// Create new ImmutableWebScraper out of given scraper struct and options
+    pub fn with_opts(scraper: &'a T, opts: Opts) -> Self {
+        scraper_new_impl!(false, scraper, opts)
     }
 
     async fn shutdown(&self) -> Result<()> {
-        scraper_shutdown(&self.workers, &self.workinput_ch, &self.workoutput_ch).await
+        scraper_shutdown(
+            &self.workers,
+            &self.handle.workinput_ch,
+            &self.workoutput_ch,
+        )
+        .await
     }
 
     /// Schedule scraper to visit given url,
     /// this will be executed on one of worker tasks
     pub async fn navigate(&self, url: &str) -> Result<()> {
-        scraper_navigate(&self.counter, &self.workinput_ch, url).await
+        scraper_navigate(&self.handle, url, None).await
+    }
+
+    /// Same as `navigate`, but given context is handed back as `Response::context`
+    pub async fn navigate_with_context(&self, url: &str, context: &str) -> Result<()> {
+        scraper_navigate(&self.handle, url, Some(context.to_string())).await
+    }
+
+    /// Schedule scraper to discover urls from given sitemap
+    pub async fn navigate_sitemap(&self, url: &str) -> Result<()> {
+        scraper_navigate_sitemap(&self.handle, url).await
+    }
+
+    /// Schedule scraper to download file from given url into destination path
+    pub async fn download(&self, url: &str, destination: &str) -> Result<()> {
+        scraper_download(&self.handle, url, destination).await
+    }
+
+    /// Restore visited links and pending work from the state file, if there is one,
+    /// and queue pages of `Opts::with_replay_from`.
+    /// Should be called before scheduling any navigation
+    pub async fn restore_state(&self) -> Result<()> {
+        scraper_restore_state(&self.opts, &self.visited_links, &self.handle).await?;
+        scraper_queue_replay(&self.opts, &self.handle).await
+    }
+
+    /// Warm up connections to hosts from `Opts::with_prefetch`.
+    /// Should be called before starting workers
+    pub async fn prefetch(&self) {
+        scraper_prefetch(&self.opts).await
+    }
+
+    async fn save_state(&self) -> Result<()> {
+        scraper_save_state(&self.opts, &self.visited_links, &self.handle).await
+    }
+
+    fn start_checkpoints(&self) -> Option<JoinHandle<()>> {
+        scraper_start_checkpoints(&self.opts, &self.visited_links, &self.handle)
+    }
+
+    /// Get handle that can be used to control this crawl
+    pub fn handle(&self) -> CrablerHandle {
+        self.handle.clone()
+    }
+
+    /// Restore state, schedule urls, sitemaps and downloads from opts and start
+    /// `Opts::with_threads` workers, as `run` of the derived scraper does before crawling
+    pub async fn start(&mut self) -> Result<()> {
+        let opts = self.opts.clone();
+
+        self.restore_state().await?;
+        self.prefetch().await;
+
+        for url in &opts.urls {
+            self.navigate(url).await?;
+        }
+
+        for url in &opts.sitemaps {
+            self.navigate_sitemap(url).await?;
+        }
+
+        for (url, destination) in &opts.download_urls {
+            self.download(url, destination).await?;
+        }
+
+        for pagination in &opts.ajax_pagination {
+            self.navigate(&pagination.page_url(1)).await?;
+        }
+
+        for _ in 0..opts.threads {
+            self.start_worker();
+        }
+
+        Ok(())
     }
 
     /// Run processing loop for the given MutableWebScraper
-    pub async fn run(&self) -> Result<()> {
-        scraper_run_impl!(self)
+    pub async fn run(&mut self) -> Result<()> {
+        self.run_until(None).await.map(|_| ())
+    }
+
+    /// Process at most given number of work outputs and return, so the caller can interleave
+    /// crawling with other work, e.g. a GUI or game loop. Crawl continues where it stopped on
+    /// the next call, workers keep fetching in between. Once the crawl is done it is shut down
+    /// as after `run`, further calls return right away with `CrawlProgress::finished` set
+    pub async fn run_steps(&mut self, steps: usize) -> Result<CrawlProgress> {
+        self.run_until(Some(steps)).await
     }
 
-    async fn event_loop(&self) -> Result<()> {
-        event_loop_impl!(self)
+    async fn run_until(&mut self, max_steps: Option<usize>) -> Result<CrawlProgress> {
+        scraper_run_impl!(self, max_steps)
+    }
+
+    async fn event_loop(&mut self, max_steps: Option<usize>) -> Result<CrawlProgress> {
+        event_loop_impl!(self, max_steps)
     }
 
     /// Create and start new worker tasks.
@@ -389,7 +1749,7 @@ where
 }
 
 async fn scraper_shutdown(
-    workers: &Vec<JoinHandle<()>>,
+    workers: &[JoinHandle<()>],
     input: &Channels<WorkInput>,
     output: &Channels<WorkOutput>,
 ) -> Result<()> {
@@ -404,32 +1764,274 @@ async fn scraper_shutdown(
 }
 
 async fn scraper_navigate(
-    counter: &Arc<AtomicUsize>,
-    input: &Channels<WorkInput>,
+    handle: &CrablerHandle,
     url: &str,
+    context: Option<String>,
+) -> Result<()> {
+    handle
+        .send_work(WorkInput::Navigate {
+            url: url.to_string(),
+            context,
+            expecting: None,
+        })
+        .await
+}
+
+async fn scraper_restore_state(
+    opts: &Opts,
+    visited_links: &RwLock<VisitedLinks>,
+    handle: &CrablerHandle,
+) -> Result<()> {
+    let path = match opts.state_file() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    if let Some(state) = CrawlState::load(&path).await? {
+        info!(
+            "Resuming from {} with {} visited and {} pending",
+            path,
+            state.visited.len(),
+            state.pending.len()
+        );
+        visited_links.write().await.extend(state.visited);
+
+        for workinput in state.pending {
+            handle.send_work(workinput).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn scraper_save_state(
+    opts: &Opts,
+    visited_links: &RwLock<VisitedLinks>,
+    handle: &CrablerHandle,
+) -> Result<()> {
+    if let Some(path) = opts.state_file() {
+        CrawlState::snapshot(visited_links, handle)
+            .await
+            .save(&path)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Queue logged pages of `Opts::with_replay_from` that have their content stored
+async fn scraper_queue_replay(opts: &Opts, handle: &CrablerHandle) -> Result<()> {
+    let (path, dir) = match (&opts.replay_from, &opts.cache_dir) {
+        (Some(path), Some(dir)) => (path, dir),
+        _ => return Ok(()),
+    };
+
+    let mut queued = 0;
+    for url in read_logged_urls(path).await? {
+        if CachedPage::load(dir, &url).await?.is_some() {
+            scraper_navigate(handle, &url, None).await?;
+            queued += 1;
+        }
+    }
+    info!("Replaying {} pages from {}", queued, path);
+
+    Ok(())
+}
+
+fn scraper_start_checkpoints(
+    opts: &Arc<Opts>,
+    visited_links: &Arc<RwLock<VisitedLinks>>,
+    handle: &CrablerHandle,
+) -> Option<JoinHandle<()>> {
+    let interval = opts.checkpoint_interval?;
+    let path = opts.state_file()?;
+    let visited_links = visited_links.clone();
+    let handle = handle.clone();
+
+    Some(async_std::task::spawn(async move {
+        loop {
+            async_std::task::sleep(interval).await;
+            debug!("Saving checkpoint to {}", path);
+
+            let state = CrawlState::snapshot(&visited_links, &handle).await;
+            if let Err(e) = state.save(&path).await {
+                warn!("Failed to save checkpoint to {}: {}", path, e);
+            }
+        }
+    }))
+}
+
+/// Report outcome of an url to `Opts::url_outcome_logger`, or log it as usual without one
+fn scraper_log_outcome(opts: &Opts, outcome: UrlOutcome) {
+    match &opts.url_outcome_logger {
+        Some(logger) => (logger.0)(&outcome),
+        None => outcome.log(),
+    }
+}
+
+/// Write request log entry for work output that was fetched or failed
+async fn scraper_log_request(
+    request_log: &RequestLog,
+    handle: &CrablerHandle,
+    output: &WorkOutput,
 ) -> Result<()> {
-    debug!("Increasing counter by 1");
-    counter.fetch_add(1, Ordering::SeqCst);
+    let (url, status, error) = match output {
+        WorkOutput::Markup { url, status, .. }
+        | WorkOutput::Headers { url, status, .. }
+        | WorkOutput::Lines { url, status, .. } => (url, Some(*status), None),
+        WorkOutput::Download { url, .. } => (url, Some(200), None),
+        WorkOutput::Error(url, e) => (url, None, Some(e.to_string())),
+        _ => return Ok(()),
+    };
+
+    let mut entry = RequestLogEntry::new(url);
+    entry.status = status;
+    entry.bytes = output.body_len();
+    entry.error = error;
+    if let Some(trace) = handle.take_request_trace(url) {
+        entry.duration_ms = Some(trace.duration.as_millis() as u64);
+        entry.from_cache = trace.from_cache;
+    }
+
+    request_log.write(&entry).await
+}
+
+/// Send `HEAD` request to first url of each host from `Opts::with_prefetch`, all at once
+async fn scraper_prefetch(opts: &Opts) {
+    if opts.offline {
+        return;
+    }
+
+    let mut hosts = HashSet::new();
+    let requests = opts.prefetch.iter().filter_map(|url| {
+        let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+        if !hosts.insert(host.clone()) {
+            return None;
+        }
+
+        let mut request = surf::head(url);
+        for (name, value) in opts.headers_for(&host) {
+            request = request.header(name.as_str(), value);
+        }
+
+        Some(async move {
+            match request.await {
+                Ok(response) => debug!("Prefetched {}: {}", url, response.status()),
+                Err(e) => warn!("Failed to prefetch {}: {}", url, e),
+            }
+        })
+    });
+
+    futures::future::join_all(requests).await;
+}
+
+fn scraper_start_progress_log(opts: &Opts, handle: &CrablerHandle) -> Option<JoinHandle<()>> {
+    let interval = opts.progress_log_interval?;
+    let handle = handle.clone();
+
+    Some(async_std::task::spawn(async move {
+        let mut last_pages = 0;
+
+        loop {
+            async_std::task::sleep(interval).await;
+
+            let report = handle.report();
+            let rate = (report.pages - last_pages) as f64 / interval.as_secs_f64();
+            last_pages = report.pages;
+
+            info!(
+                "Progress{}: {} pages ({:.1}/s), {} pending, {} errors, {} bytes",
+                report
+                    .crawl_id
+                    .as_ref()
+                    .map(|id| format!(" of {}", id))
+                    .unwrap_or_default(),
+                report.pages,
+                rate,
+                handle.counter.load(Ordering::SeqCst),
+                report.errors.len(),
+                report.total_bytes
+            );
+        }
+    }))
+}
+
+fn scraper_start_snapshots(opts: &Opts, handle: &CrablerHandle) -> Option<JoinHandle<()>> {
+    let (interval, callback) = opts.snapshot_callback.clone()?;
+    let handle = handle.clone();
+    let started = Instant::now();
+
+    Some(async_std::task::spawn(async move {
+        loop {
+            async_std::task::sleep(interval).await;
+
+            let report = handle.report();
+            let snapshot = CrawlSnapshot {
+                elapsed: started.elapsed(),
+                pending: handle.counter.load(Ordering::SeqCst),
+                visited: report.pages + report.errors.len(),
+                report,
+            };
+            (callback.0)(&snapshot);
+        }
+    }))
+}
+
+async fn scraper_navigate_sitemap(handle: &CrablerHandle, url: &str) -> Result<()> {
+    handle
+        .send_work(WorkInput::Sitemap {
+            url: url.to_string(),
+            depth: 0,
+        })
+        .await
+}
 
-    Ok(input.tx.send(WorkInput::Navigate(url.to_string())).await?)
+async fn scraper_download(handle: &CrablerHandle, url: &str, destination: &str) -> Result<()> {
+    handle
+        .send_work(WorkInput::Download {
+            url: url.to_string(),
+            destination: destination.to_string(),
+        })
+        .await
 }
 
 struct Worker {
-    visited_links: Arc<RwLock<HashSet<String>>>,
+    visited_links: Arc<RwLock<VisitedLinks>>,
     workinput_rx: Receiver<WorkInput>,
     workoutput_tx: Sender<WorkOutput>,
+    handle: CrablerHandle,
+    opts: Arc<Opts>,
+    /// Lowercase content types with `on_lines` handlers, their bodies are streamed
+    line_content_types: Arc<Vec<String>>,
+    /// Whether bodies of pages are handed to `on_stream` handler while downloading
+    stream_pages: bool,
 }
 
 impl Worker {
     fn new(
-        visited_links: Arc<RwLock<HashSet<String>>>,
+        visited_links: Arc<RwLock<VisitedLinks>>,
         workinput_rx: Receiver<WorkInput>,
         workoutput_tx: Sender<WorkOutput>,
+        handle: CrablerHandle,
+        opts: Arc<Opts>,
+        line_content_types: Arc<Vec<String>>,
+        stream_pages: bool,
     ) -> Self {
         Worker {
             visited_links,
             workinput_rx,
             workoutput_tx,
+            handle,
+            opts,
+            line_content_types,
+            stream_pages,
+        }
+    }
+
+    /// Stop restarting this worker, crawl fails once no workers are left
+    async fn give_up(&self) {
+        if self.handle.live_workers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _ = self.workoutput_tx.send(WorkOutput::WorkersDown).await;
         }
     }
 
@@ -437,25 +2039,72 @@ impl Worker {
         let workoutput_tx = self.workoutput_tx.clone();
 
         loop {
+            // turns of ordered hosts are taken in the order work is received, so receive one at a time
+            let dequeue = if self.opts.ordered_hosts.is_empty() {
+                None
+            } else {
+                Some(self.handle.dequeue.lock().await)
+            };
+
             let workinput = self.workinput_rx.recv().await;
             if let Err(RecvError) = workinput {
-                continue;
+                debug!("Work input channel closed, shutting down worker");
+                return Ok(());
             }
 
             let workinput = workinput?;
+            let turn = workinput
+                .url()
+                .and_then(|url| Url::parse(url).ok())
+                .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+                .filter(|host| self.opts.ordered_hosts.contains(host))
+                .map(|host| self.handle.take_host_turn(&host));
+            drop(dequeue);
+
+            if let Some(turn) = &turn {
+                turn.wait().await;
+            }
+
+            let _slot = match &self.opts.worker_slots {
+                Some(slots) if workinput.url().is_some() => Some(slots.acquire().await),
+                _ => None,
+            };
             let payload = self.process_message(workinput).await;
 
             match payload {
                 Ok(WorkOutput::Exit) => return Ok(()),
-                _ => workoutput_tx.send(payload?).await?,
+                // event loop is gone, e.g. it returned early with an error, there is no one to work for
+                _ => {
+                    if workoutput_tx.send(payload?).await.is_err() {
+                        debug!("Work output channel closed, shutting down worker");
+                        return Ok(());
+                    }
+                }
             }
         }
     }
 
     async fn process_message(&self, workinput: WorkInput) -> Result<WorkOutput> {
         match workinput {
-            WorkInput::Navigate(url) => {
-                let workoutput = self.navigate(url.clone()).await;
+            WorkInput::Navigate { url, .. }
+            | WorkInput::Download { url, .. }
+            | WorkInput::Sitemap { url, .. }
+            | WorkInput::Feed { url }
+                if self.handle.is_stopping() =>
+            {
+                Ok(WorkOutput::Drained(url))
+            }
+            WorkInput::Navigate { url, expecting, .. } => {
+                let document_slot = match &self.opts.document_slots {
+                    Some(slots) => Some(slots.acquire().await),
+                    None => None,
+                };
+                let workoutput = self.navigate(url.clone(), expecting).await;
+
+                // markup keeps its slot until event loop is done with it
+                if let (Some(slot), Ok(WorkOutput::Markup { .. })) = (document_slot, &workoutput) {
+                    slot.forget();
+                }
 
                 if let Err(e) = workoutput {
                     Ok(WorkOutput::Error(url, e))
@@ -464,7 +2113,15 @@ impl Worker {
                 }
             }
             WorkInput::Download { url, destination } => {
+                let started = Instant::now();
                 let workoutput = self.download(url.clone(), destination).await;
+                if self.opts.request_log.is_some() {
+                    let trace = RequestTrace {
+                        duration: started.elapsed(),
+                        from_cache: false,
+                    };
+                    self.handle.trace_request(&url, trace);
+                }
 
                 if let Err(e) = workoutput {
                     Ok(WorkOutput::Error(url, e))
@@ -472,37 +2129,776 @@ impl Worker {
                     workoutput
                 }
             }
+            WorkInput::Sitemap { url, depth } => {
+                let workoutput = self.sitemap(url.clone(), depth).await;
+
+                if let Err(e) = workoutput {
+                    warn!("Skipping sitemap {}: {}", url, e);
+                    Ok(WorkOutput::Sitemap { url, discovered: 0 })
+                } else {
+                    workoutput
+                }
+            }
+            WorkInput::Feed { url } => {
+                let workoutput = self.feed(url.clone()).await;
+
+                if let Err(e) = workoutput {
+                    warn!("Skipping feed {}: {}", url, e);
+                    Ok(WorkOutput::Feed { url, items: vec![] })
+                } else {
+                    workoutput
+                }
+            }
+            WorkInput::Wake => Ok(WorkOutput::Wake),
             WorkInput::Exit => Ok(WorkOutput::Exit),
         }
     }
 
-    async fn navigate(&self, url: String) -> Result<WorkOutput> {
-        let contains = self.visited_links.read().await.contains(&url.clone());
+    /// Wait until host of given url is no longer throttled
+    async fn wait_for_host(&self, url: &str) {
+        let host = match Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from))
+        {
+            Some(host) => host,
+            None => return,
+        };
+
+        if let Some(delay) = self.opts.initial_host_delay {
+            self.handle.delay_first_contact(&host, delay);
+        }
+
+        while let Some(pause) = self.handle.host_pause(&host) {
+            debug!("Waiting {:?} for throttled host {}", pause, host);
+            async_std::task::sleep(pause).await;
+        }
+    }
+
+    /// Check robots.txt rules for given url, fetching them for the host on first use.
+    /// Disallowed urls are recorded in the report
+    async fn allowed_by_robots(&self, url: &str) -> bool {
+        // cached pages were checked when they were fetched, robots.txt can not be fetched anyway
+        if !self.opts.respect_robots_txt || self.opts.offline {
+            return true;
+        }
+
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+        let host = match parsed.host_str() {
+            Some(host) => host.to_string(),
+            None => return true,
+        };
+
+        let known = self
+            .handle
+            .report
+            .lock()
+            .unwrap()
+            .robots
+            .get(&host)
+            .map(|audit| audit.rules.clone());
+
+        let rules = match known {
+            Some(rules) => rules,
+            None => {
+                let rules = self.fetch_robots(&parsed).await;
+                let mut report = self.handle.report.lock().unwrap();
+                let audit = report.robots.entry(host.clone()).or_default();
+                audit.rules = rules.clone();
+                rules
+            }
+        };
+
+        let mut path = parsed.path().to_string();
+        if let Some(query) = parsed.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+
+        let allowed = rules.is_allowed(&path);
+        if !allowed {
+            info!("Skipping {}, disallowed by robots.txt", url);
+            let mut report = self.handle.report.lock().unwrap();
+            let audit = report.robots.entry(host).or_default();
+            audit.skipped_urls.push(url.to_string());
+        }
+
+        allowed
+    }
+
+    /// Fetch url, following up to `Opts::with_max_redirects` redirects.
+    /// Requests of a redirected url are kept for `Response::redirect_chain`
+    async fn fetch_following_redirects(
+        &self,
+        url: &str,
+        expecting: Option<ContentKind>,
+    ) -> Result<surf::Response> {
+        let mut response = self.fetch(url, expecting).await?;
+        let mut current = url.to_string();
+        let mut chain = vec![];
+
+        while response.status().is_redirection() {
+            let location = match response.header("Location") {
+                Some(location) => location.as_str().to_string(),
+                None => break,
+            };
+            let next = match Url::parse(&current).and_then(|current| current.join(&location)) {
+                Ok(next) => String::from(next),
+                Err(_) => break,
+            };
+
+            if chain.len() >= self.opts.max_redirects {
+                if self.opts.max_redirects > 0 {
+                    warn!(
+                        "Not following redirect of {} to {}, {} redirects followed already",
+                        url,
+                        next,
+                        chain.len()
+                    );
+                }
+                break;
+            }
+
+            debug!("Following redirect of {} to {}", current, next);
+            chain.push((current, response.status() as u16));
+            self.wait_for_host(&next).await;
+            response = self.fetch(&next, expecting).await?;
+            current = next;
+        }
+
+        if !chain.is_empty() {
+            chain.push((current, response.status() as u16));
+            self.handle.record_redirect_chain(url, chain);
+        }
+
+        Ok(response)
+    }
+
+    /// Send request to given host with its client from `Opts::with_host_client` or the default
+    /// one, with the `metrics` feature connection usage of the default client is counted
+    /// in `CrawlReport::connections`. Waits for a slot first with `Opts::with_max_inflight`
+    async fn send(&self, host: &str, request: surf::RequestBuilder) -> Result<surf::Response> {
+        let _inflight_slot = match &self.opts.inflight_slots {
+            Some(slots) => Some(slots.acquire().await),
+            None => None,
+        };
+
+        // request was built by the host client and is sent with it
+        if self.opts.host_clients.contains_key(&host.to_lowercase()) {
+            return Ok(request.await?);
+        }
+
+        #[cfg(feature = "metrics")]
+        return self.handle.send_measured(request).await;
+
+        #[cfg(not(feature = "metrics"))]
+        Ok(request.await?)
+    }
+
+    /// Send GET request to given url with configured default and per-host headers.
+    /// With `cookie-store` feature stored cookies are sent along and new ones are saved.
+    /// A `401` challenge of a host with `Opts::with_credentials` is answered by one retry
+    async fn fetch(&self, url: &str, expecting: Option<ContentKind>) -> Result<surf::Response> {
+        if self.opts.offline {
+            return Err(CrablerError::Offline(url.to_string()));
+        }
+
+        let parsed = Url::parse(url).ok();
+        let host = parsed
+            .as_ref()
+            .and_then(|parsed| parsed.host_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let request = |authorization: Option<String>| {
+            let mut request = match self.opts.host_clients.get(&host.to_lowercase()) {
+                Some(client) => client.get(url),
+                None => surf::get(url),
+            };
+            for (name, value) in self.opts.headers_for(&host) {
+                request = request.header(name.as_str(), value);
+            }
+
+            if let Some(kind) = expecting {
+                request = request.header("Accept", kind.accept());
+            }
+
+            if let Some(authorization) = authorization {
+                request = request.header("Authorization", authorization);
+            }
+
+            #[cfg(feature = "cookie-store")]
+            {
+                let path = parsed.as_ref().map(|parsed| parsed.path()).unwrap_or("/");
+                if let Some(header) = self.handle.cookies.lock().unwrap().header_for(&host, path) {
+                    request = request.header("Cookie", header);
+                }
+            }
+
+            request
+        };
+
+        let credentials = self.opts.credentials.get(&host.to_lowercase());
+        let uri = parsed
+            .as_ref()
+            .map(|parsed| match parsed.query() {
+                Some(query) => format!("{}?{}", parsed.path(), query),
+                None => parsed.path().to_string(),
+            })
+            .unwrap_or_default();
+        let authorization = credentials
+            .and_then(|credentials| self.handle.authorization_for(&host, credentials, &uri));
+
+        let mut response = self.send(&host, request(authorization)).await?;
+
+        if let Some(credentials) = credentials {
+            let challenge = response
+                .header("WWW-Authenticate")
+                .filter(|_| response.status() == 401)
+                .and_then(|values| Challenge::parse(values.iter().map(|value| value.as_str())));
+
+            if let Some(challenge) = challenge {
+                debug!("Authenticating to {} after 401", url);
+                self.handle.store_challenge(&host, challenge);
+                let authorization = self.handle.authorization_for(&host, credentials, &uri);
+                response = self.send(&host, request(authorization)).await?;
+            }
+        }
+
+        #[cfg(feature = "cookie-store")]
+        if let Some(values) = response.header("Set-Cookie") {
+            self.handle
+                .cookies
+                .lock()
+                .unwrap()
+                .store(&host, values.iter().map(|value| value.as_str()));
+        }
+
+        Ok(response)
+    }
+
+    /// Fetch robots.txt for the host of given url.
+    /// Missing robots.txt allows everything, server or network errors disallow everything
+    async fn fetch_robots(&self, url: &Url) -> RobotsRules {
+        let mut robots_url = url.clone();
+        robots_url.set_path("/robots.txt");
+        robots_url.set_query(None);
+        robots_url.set_fragment(None);
+
+        self.wait_for_host(robots_url.as_str()).await;
+        let mut response = match self.fetch(robots_url.as_str(), None).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to fetch {}: {}", robots_url, e);
+                return RobotsRules::disallow_all();
+            }
+        };
+
+        if response.status().is_client_error() {
+            return RobotsRules::default();
+        }
+
+        if !response.status().is_success() {
+            warn!("Failed to fetch {}: {}", robots_url, response.status());
+            return RobotsRules::disallow_all();
+        }
+
+        match response.body_string().await {
+            Ok(text) => RobotsRules::parse(&text, ROBOTS_USER_AGENT),
+            Err(e) => {
+                warn!("Failed to read {}: {}", robots_url, e);
+                RobotsRules::disallow_all()
+            }
+        }
+    }
+
+    /// Inject configured chaos into the request for given url
+    #[cfg(feature = "testing")]
+    async fn chaos(&self, url: &str) -> Result<()> {
+        match &self.opts.chaos {
+            Some(config) => testing::inject_chaos(config, &self.handle.rng, url).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "testing"))]
+    async fn chaos(&self, _url: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn navigate(&self, url: String, expecting: Option<ContentKind>) -> Result<WorkOutput> {
+        if is_data_uri(&url) {
+            return self.data_uri_page(url);
+        }
+
+        if let Some(max_url_length) = self.opts.max_url_length {
+            if url.len() > max_url_length {
+                warn!(
+                    "Skipping url of {} bytes, longer than {}: {}...",
+                    url.len(),
+                    max_url_length,
+                    url.chars().take(200).collect::<String>()
+                );
+                return Ok(WorkOutput::Noop(url));
+            }
+        }
+
+        if !self.opts.in_seed_scope(&url) {
+            debug!("Skipping {}, it is outside of seed paths", url);
+            return Ok(WorkOutput::Noop(url));
+        }
+
+        // check and insert under one lock, so concurrent workers never both fetch the url
+        let contains = {
+            let key = self.opts.dedup_key(&url);
+            let mut visited_links = self.visited_links.write().await;
+            let contains = visited_links.contains(&key);
+            if !contains {
+                visited_links.insert(key);
+            }
+            contains
+        };
 
         if !contains {
-            self.visited_links.write().await.insert(url.clone());
-            let response = surf::get(&url).await?;
+            if let Some(config) = &self.opts.trap_detection {
+                if self.handle.is_trap(&url, config) {
+                    return Ok(WorkOutput::Noop(url));
+                }
+            }
+
+            if !self.allowed_by_robots(&url).await {
+                return Ok(WorkOutput::Noop(url));
+            }
+
+            if let Some(max_pages) = self.opts.max_pages_per_host {
+                if !self.handle.take_host_budget(&url, max_pages) {
+                    return Ok(WorkOutput::Noop(url));
+                }
+            }
+
+            let started = Instant::now();
+            let (workoutput, from_cache) = match self.cached_page(&url).await? {
+                Some(workoutput) => (Ok(workoutput), true),
+                None => (self.fetch_page_with_retries(&url, expecting).await, false),
+            };
+            if self.opts.request_log.is_some() {
+                let trace = RequestTrace {
+                    duration: started.elapsed(),
+                    from_cache,
+                };
+                self.handle.trace_request(&url, trace);
+            }
+            let workoutput = workoutput?;
+
+            if let (WorkOutput::Markup { text, .. }, true) =
+                (&workoutput, self.opts.skip_duplicate_content)
+            {
+                if self.handle.is_duplicate_content(&url, text) {
+                    return Ok(WorkOutput::Noop(url));
+                }
+            }
 
-            workoutput_from_response(response, url.clone()).await
+            match (&workoutput, self.opts.near_dup_distance) {
+                (WorkOutput::Markup { text, .. }, Some(distance))
+                    if self
+                        .handle
+                        .is_near_duplicate(&url, simhash::simhash(text), distance) =>
+                {
+                    Ok(WorkOutput::Noop(url))
+                }
+                _ => Ok(workoutput),
+            }
         } else {
             Ok(WorkOutput::Noop(url))
         }
     }
 
+    /// Fetch page, retrying retryable failures with backoff until retries run out
+    /// or the url's deadline would pass, see `Opts::with_retries` and `Opts::with_per_url_deadline`
+    async fn fetch_page_with_retries(
+        &self,
+        url: &str,
+        expecting: Option<ContentKind>,
+    ) -> Result<WorkOutput> {
+        let deadline = self
+            .opts
+            .per_url_deadline
+            .map(|deadline| Instant::now() + deadline);
+        let mut attempt = 0;
+
+        loop {
+            let result = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    async_std::future::timeout(remaining, self.fetch_page(url, expecting))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(CrablerError::Timeout(format!(
+                                "deadline for {} passed",
+                                url
+                            )))
+                        })
+                }
+                None => self.fetch_page(url, expecting).await,
+            };
+
+            match result {
+                Err(e) if self.opts.should_retry(url, &e, attempt) => {
+                    let delay = self.opts.retry_delay(attempt);
+                    if let Some(deadline) = deadline {
+                        if Instant::now() + delay >= deadline {
+                            warn!(
+                                "Giving up on {} after {} attempts, deadline would pass",
+                                url,
+                                attempt + 1
+                            );
+                            return Err(e);
+                        }
+                    }
+
+                    warn!("Retrying {} in {:?}: {}", url, delay, e);
+                    async_std::task::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fetch page from the network and store it in the cache directory when there is one,
+    /// and next to the request log with `Opts::with_request_log_bodies`
+    async fn fetch_page(&self, url: &str, expecting: Option<ContentKind>) -> Result<WorkOutput> {
+        self.wait_for_host(url).await;
+        self.chaos(url).await?;
+        let response = self.fetch_following_redirects(url, expecting).await?;
+
+        if let (Some(kind), true) = (expecting, self.opts.reject_unexpected_content) {
+            let content_type = response
+                .header("Content-Type")
+                .map(|values| values.as_str().to_string())
+                .unwrap_or_default();
+
+            if !kind.matches(&content_type) {
+                return Err(CrablerError::UnexpectedContent(format!(
+                    "expected {:?}, got {:?}",
+                    kind, content_type
+                )));
+            }
+        }
+
+        let workoutput = workoutput_from_response(
+            response,
+            url.to_string(),
+            &self.opts,
+            &self.line_content_types,
+            if self.stream_pages {
+                Some((&self.handle, &self.workoutput_tx))
+            } else {
+                None
+            },
+        )
+        .await?;
+
+        if let WorkOutput::Markup {
+            url,
+            text,
+            status,
+            headers,
+        } = &workoutput
+        {
+            let dirs = self
+                .opts
+                .cache_dir
+                .iter()
+                .cloned()
+                .chain(self.opts.request_log_pages())
+                .collect::<Vec<_>>();
+
+            if !dirs.is_empty() {
+                let page = CachedPage {
+                    url: url.clone(),
+                    status: *status,
+                    headers: headers.clone(),
+                    text: text.clone(),
+                };
+
+                for dir in dirs {
+                    if let Err(e) = page.save(&dir).await {
+                        warn!("Failed to cache {} in {}: {}", url, dir, e);
+                    }
+                }
+            }
+        }
+
+        Ok(workoutput)
+    }
+
+    /// Page for given url from the cache directory, if there is one and url was cached
+    async fn cached_page(&self, url: &str) -> Result<Option<WorkOutput>> {
+        let dir = match &self.opts.cache_dir {
+            Some(dir) => dir,
+            None => return Ok(None),
+        };
+
+        let page = CachedPage::load(dir, url).await?.map(|page| {
+            debug!("Serving {} from cache", url);
+            WorkOutput::Markup {
+                url: page.url,
+                text: page.text,
+                status: page.status,
+                headers: page.headers,
+            }
+        });
+
+        Ok(page)
+    }
+
+    /// Page decoded from an inline `data:` uri, skipped unless `Opts::with_handle_data_uris`
+    fn data_uri_page(&self, url: String) -> Result<WorkOutput> {
+        if !self.opts.handle_data_uris {
+            debug!("Skipping data uri {}", url);
+            return Ok(WorkOutput::Noop(url));
+        }
+
+        let uri = DataUri::parse(&url)?;
+        let mut headers = Headers::new();
+        headers.insert("content-type".to_string(), uri.media_type);
+
+        Ok(WorkOutput::Markup {
+            text: String::from_utf8_lossy(&uri.data).into_owned(),
+            url,
+            status: 200,
+            headers,
+        })
+    }
+
+    /// Write file decoded from an inline `data:` uri, skipped unless `Opts::with_handle_data_uris`
+    async fn data_uri_download(&self, url: String, destination: String) -> Result<WorkOutput> {
+        if !self.opts.handle_data_uris {
+            debug!("Skipping data uri {}", url);
+            return Ok(WorkOutput::Noop(url));
+        }
+
+        let uri = DataUri::parse(&url)?;
+        let destination = self.file_destination(&url, destination, None).await;
+        let sink = &self.opts.download_sink.0;
+        sink.write_chunk(&destination, &uri.data).await?;
+        sink.finalize(&destination).await?;
+
+        Ok(WorkOutput::Download {
+            url,
+            destination,
+            bytes: uri.data.len() as u64,
+        })
+    }
+
+    /// Path download is written to: a directory destination gets a file name from
+    /// `Content-Disposition` or url appended, then `Opts::with_destination_rewriter` is applied
+    async fn file_destination(
+        &self,
+        url: &str,
+        destination: String,
+        content_disposition: Option<&str>,
+    ) -> String {
+        let is_dir =
+            destination.ends_with('/') || async_std::path::Path::new(&destination).is_dir().await;
+        let destination = if is_dir {
+            join_dir(&destination, &download_file_name(url, content_disposition))
+        } else {
+            destination
+        };
+
+        match &self.opts.destination_rewriter {
+            Some(rewriter) => {
+                let rewritten = (rewriter.0)(url, &destination);
+                if rewritten != destination {
+                    debug!(
+                        "Rewrote destination of {}: {} -> {}",
+                        url, destination, rewritten
+                    );
+                }
+                rewritten
+            }
+            None => destination,
+        }
+    }
+
     async fn download(&self, url: String, destination: String) -> Result<WorkOutput> {
-        let contains = self.visited_links.read().await.contains(&url.clone());
+        if is_data_uri(&url) {
+            return self.data_uri_download(url, destination).await;
+        }
+
+        let key = self.opts.dedup_key(&url);
+        let contains = self.visited_links.read().await.contains(&key);
 
         if !contains {
+            if !self.allowed_by_robots(&url).await {
+                return Ok(WorkOutput::Noop(url));
+            }
+
             // need to notify parent about work being done
-            let response = surf::get(&*url).await?.body_bytes().await?;
-            let mut dest = File::create(destination.clone()).await?;
-            dest.write_all(&response).await?;
+            self.wait_for_host(&url).await;
+            self.chaos(&url).await?;
+            let mut response = self.fetch(&url, None).await?;
+            if !response.status().is_success() {
+                return Err(CrablerError::Http(response.status().into()));
+            }
 
-            Ok(WorkOutput::Download { url, destination })
+            let content_disposition = response
+                .header("Content-Disposition")
+                .map(|values| values.as_str().to_string());
+            let destination = self
+                .file_destination(&url, destination, content_disposition.as_deref())
+                .await;
+            let sink = &self.opts.download_sink.0;
+            let mut body = response.take_body();
+            let mut buf = vec![0u8; 64 * 1024];
+
+            let bytes = read_body_within(self.opts.body_read_timeout, &url, async {
+                let mut bytes = 0;
+                loop {
+                    let n = body
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| CrablerError::Network(e.to_string()))?;
+                    if n == 0 {
+                        break;
+                    }
+
+                    sink.write_chunk(&destination, &buf[..n]).await?;
+                    bytes += n as u64;
+                }
+
+                Ok(bytes)
+            })
+            .await?;
+            sink.finalize(&destination).await?;
+
+            Ok(WorkOutput::Download {
+                url,
+                destination,
+                bytes,
+            })
         } else {
             Ok(WorkOutput::Noop(url))
         }
     }
+
+    async fn sitemap(&self, url: String, depth: usize) -> Result<WorkOutput> {
+        let contains = self.visited_links.read().await.contains(&url);
+
+        if contains {
+            return Ok(WorkOutput::Sitemap { url, discovered: 0 });
+        }
+
+        self.visited_links.write().await.insert(url.clone());
+        self.wait_for_host(&url).await;
+        let mut response = self.fetch(&url, None).await?;
+
+        if !response.status().is_success() {
+            warn!("Skipping sitemap {} with status {}", url, response.status());
+            return Ok(WorkOutput::Sitemap { url, discovered: 0 });
+        }
+
+        let body = read_body_within(self.opts.body_read_timeout, &url, async {
+            Ok(response.body_bytes().await?)
+        })
+        .await?;
+
+        // gzip magic bytes, body was not decoded by the client
+        if body.starts_with(&[0x1f, 0x8b]) {
+            warn!(
+                "Skipping sitemap {}, it is gzip compressed without Content-Encoding",
+                url
+            );
+            return Ok(WorkOutput::Sitemap { url, discovered: 0 });
+        }
+
+        let discovered = match parse_sitemap(&String::from_utf8_lossy(&body), &url) {
+            Some(Sitemap::Index(sitemaps)) if depth >= self.opts.sitemap_max_depth => {
+                warn!(
+                    "Not following {} nested sitemaps of {}, max depth {} reached",
+                    sitemaps.len(),
+                    url,
+                    self.opts.sitemap_max_depth
+                );
+                0
+            }
+            Some(Sitemap::Index(sitemaps)) => {
+                for sitemap in &sitemaps {
+                    self.handle
+                        .send_work(WorkInput::Sitemap {
+                            url: sitemap.clone(),
+                            depth: depth + 1,
+                        })
+                        .await?;
+                }
+                sitemaps.len()
+            }
+            Some(Sitemap::UrlSet(urls)) => {
+                for url in &urls {
+                    self.handle
+                        .send_work(WorkInput::Navigate {
+                            url: url.clone(),
+                            context: None,
+                            expecting: None,
+                        })
+                        .await?;
+                }
+                urls.len()
+            }
+            None => {
+                warn!("Skipping malformed sitemap {}", url);
+                0
+            }
+        };
+
+        Ok(WorkOutput::Sitemap { url, discovered })
+    }
+
+    /// Fetch and parse RSS or Atom feed, links of its items are navigated to
+    async fn feed(&self, url: String) -> Result<WorkOutput> {
+        let contains = self.visited_links.read().await.contains(&url);
+
+        if contains {
+            return Ok(WorkOutput::Feed { url, items: vec![] });
+        }
+
+        self.visited_links.write().await.insert(url.clone());
+        self.wait_for_host(&url).await;
+        let mut response = self.fetch(&url, None).await?;
+
+        if !response.status().is_success() {
+            warn!("Skipping feed {} with status {}", url, response.status());
+            return Ok(WorkOutput::Feed { url, items: vec![] });
+        }
+
+        let body = read_body_within(self.opts.body_read_timeout, &url, async {
+            Ok(response.body_bytes().await?)
+        })
+        .await?;
+        let items = match parse_feed(&String::from_utf8_lossy(&body), &url) {
+            Some(items) => items,
+            None => {
+                warn!("Skipping malformed feed {}", url);
+                vec![]
+            }
+        };
+
+        for link in items.iter().filter_map(|item| item.link.as_ref()) {
+            self.handle
+                .send_work(WorkInput::Navigate {
+                    url: link.clone(),
+                    context: None,
+                    expecting: None,
+                })
+                .await?;
+        }
+
+        Ok(WorkOutput::Feed { url, items })
+    }
 }
 
 #[derive(Debug)]
@@ -511,23 +2907,352 @@ enum WorkOutput {
         url: String,
         text: String,
         status: u16,
+        headers: Headers,
+    },
+    Headers {
+        url: String,
+        status: u16,
+        headers: Headers,
+    },
+    Lines {
+        url: String,
+        status: u16,
+        headers: Headers,
+        content_type: String,
+        lines: Receiver<String>,
+    },
+    /// Body of a page about to be sent as `Markup`, handed over while downloading
+    Stream {
+        url: String,
+        status: u16,
+        headers: Headers,
+        chunks: Receiver<Vec<u8>>,
     },
     Download {
         url: String,
         destination: String,
+        bytes: u64,
     },
     Noop(String),
+    Drained(String),
+    Sitemap {
+        url: String,
+        discovered: usize,
+    },
+    Feed {
+        url: String,
+        items: Vec<FeedItem>,
+    },
     Error(String, CrablerError),
+    Wake,
+    WorkersDown,
     Exit,
 }
 
-async fn workoutput_from_response(mut response: surf::Response, url: String) -> Result<WorkOutput> {
+impl WorkOutput {
+    /// Size of downloaded page or file body
+    fn body_len(&self) -> u64 {
+        match self {
+            WorkOutput::Markup { text, .. } => text.len() as u64,
+            WorkOutput::Download { bytes, .. } => *bytes,
+            _ => 0,
+        }
+    }
+
+    fn url(&self) -> Option<&str> {
+        match self {
+            WorkOutput::Markup { url, .. }
+            | WorkOutput::Headers { url, .. }
+            | WorkOutput::Lines { url, .. }
+            | WorkOutput::Stream { url, .. }
+            | WorkOutput::Download { url, .. }
+            | WorkOutput::Noop(url)
+            | WorkOutput::Drained(url)
+            | WorkOutput::Sitemap { url, .. }
+            | WorkOutput::Feed { url, .. }
+            | WorkOutput::Error(url, _) => Some(url),
+            WorkOutput::Wake | WorkOutput::WorkersDown | WorkOutput::Exit => None,
+        }
+    }
+}
+
+/// Urls from given attribute of an element, `srcset` candidates are split into separate urls
+fn element_links(el: &Element, attr: &str) -> Vec<String> {
+    let value = match el.attr(attr) {
+        Some(value) => value,
+        None => return vec![],
+    };
+
+    if attr.eq_ignore_ascii_case("srcset") {
+        value
+            .split(',')
+            .filter_map(|candidate| candidate.split_whitespace().next())
+            .map(String::from)
+            .collect()
+    } else {
+        let value = value.trim();
+        if value.is_empty() {
+            vec![]
+        } else {
+            vec![value.to_string()]
+        }
+    }
+}
+
+/// Delay and target of the first `<meta http-equiv="refresh">` with a target url,
+/// refreshes that only reload the page itself are ignored
+fn meta_refresh(document: &Document) -> Option<(Duration, String)> {
+    document
+        .select("meta[http-equiv]")
+        .iter()
+        .filter(|meta| {
+            meta.attr("http-equiv")
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case("refresh"))
+        })
+        .find_map(|meta| {
+            let content = meta.attr("content")?;
+            let (delay, target) = content.split_once([';', ','])?;
+            let delay = delay.trim().split('.').next()?.parse().ok()?;
+
+            let target = target.trim();
+            let target = match (target.get(..3), target.get(3..)) {
+                (Some(name), Some(rest))
+                    if name.eq_ignore_ascii_case("url") && rest.trim_start().starts_with('=') =>
+                {
+                    &rest.trim_start()[1..]
+                }
+                _ => target,
+            };
+            let target = target.trim().trim_matches(|c| c == '\'' || c == '"').trim();
+            if target.is_empty() {
+                return None;
+            }
+
+            Some((Duration::from_secs(delay), target.to_string()))
+        })
+}
+
+/// Warn when parsed document came out empty even though markup has elements in it,
+/// crabquery does not report parse errors so this is the closest we get to detecting them
+fn warn_on_parse_anomaly(url: &str, text: &str, document: &Document) {
+    let skeleton = ["html", "head", "body", "!doctype"];
+    let tags = text
+        .split('<')
+        .skip(1)
+        .filter(|tag| tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '!'))
+        .map(|tag| {
+            tag.split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .filter(|tag| !tag.starts_with("!--") && !skeleton.contains(&tag.as_str()))
+        .count();
+
+    let elements = document
+        .select("head")
+        .iter()
+        .chain(document.select("body").iter())
+        .map(|el| el.children().len())
+        .sum::<usize>();
+
+    if tags > 0 && elements == 0 {
+        warn!(
+            "Markup from {} has {} tags but parsed document is empty, selectors will not match. \
+            Html is likely malformed, see Opts::with_html_preprocessor",
+            url, tags
+        );
+    }
+}
+
+async fn workoutput_from_response(
+    mut response: surf::Response,
+    url: String,
+    opts: &Opts,
+    line_content_types: &[String],
+    stream: Option<(&CrablerHandle, &Sender<WorkOutput>)>,
+) -> Result<WorkOutput> {
     let status = response.status().into();
-    let text = response.body_string().await?;
+    let headers = response
+        .iter()
+        .map(|(name, values)| {
+            let values = values.iter().map(|v| v.as_str()).collect::<Vec<_>>();
+            (name.as_str().to_lowercase(), values.join(", "))
+        })
+        .collect::<Headers>();
+
+    if let Some(should_download_body) = &opts.should_download_body {
+        // body is streamed, dropping response here means it is never downloaded
+        if !(should_download_body.0)(&url, &headers) {
+            return Ok(WorkOutput::Headers {
+                url,
+                status,
+                headers,
+            });
+        }
+    }
+
+    let content_type = headers
+        .get("content-type")
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| line_content_types.contains(value));
+
+    if let Some(content_type) = content_type {
+        // lines are handed over while the body is still downloading
+        let (lines_tx, lines) = bounded(LINES_BUFFER);
+        async_std::task::spawn(stream_lines(url.clone(), response.take_body(), lines_tx));
+
+        return Ok(WorkOutput::Lines {
+            url,
+            status,
+            headers,
+            content_type,
+            lines,
+        });
+    }
+
+    let text = read_body_within(opts.body_read_timeout, &url, async {
+        match stream {
+            Some((handle, workoutput_tx)) => {
+                tee_body(&mut response, &url, status, &headers, handle, workoutput_tx).await?;
+                body_text(&mut response, &url, opts.body_decode_policy, true).await
+            }
+            None => body_text(&mut response, &url, opts.body_decode_policy, false).await,
+        }
+    })
+    .await?;
+    let text = match text {
+        Some(text) => text,
+        None => return Ok(WorkOutput::Noop(url)),
+    };
+
+    Ok(WorkOutput::Markup {
+        status,
+        url,
+        text,
+        headers,
+    })
+}
+
+const LINES_BUFFER: usize = 1024;
+
+const STREAM_BUFFER: usize = 64;
+
+/// Decode body of a page into text using its charset, undecodable bodies are handled
+/// according to `Opts::with_body_decode_policy`, `None` means the page is skipped.
+/// `streamed` tells whether the body was already handed to the `on_stream` handler
+async fn body_text(
+    response: &mut surf::Response,
+    url: &str,
+    policy: BodyDecodePolicy,
+    streamed: bool,
+) -> Result<Option<String>> {
+    if policy == BodyDecodePolicy::Fail {
+        return Ok(Some(response.body_string().await?));
+    }
+
+    let bytes = response.body_bytes().await?;
+    response.set_body(bytes.clone());
+
+    let e = match response.body_string().await {
+        Ok(text) => return Ok(Some(text)),
+        Err(e) => e,
+    };
+
+    match policy {
+        BodyDecodePolicy::Lossy => {
+            warn!(
+                "Failed to decode {}, falling back to lossy UTF-8: {}",
+                url, e
+            );
+            Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+        BodyDecodePolicy::Binary if streamed => {
+            info!("Failed to decode {}, handled as binary: {}", url, e);
+            Ok(None)
+        }
+        _ => {
+            warn!("Failed to decode {}, skipping: {}", url, e);
+            Ok(None)
+        }
+    }
+}
 
-    if text.len() == 0 {
-        error!("body length is 0")
+/// Read body of response from url, failing with `CrablerError::BodyTimeout` when it takes
+/// longer than `Opts::with_body_read_timeout`
+async fn read_body_within<T>(
+    timeout: Option<Duration>,
+    url: &str,
+    read: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(timeout) => async_std::future::timeout(timeout, read)
+            .await
+            .unwrap_or_else(|_| Err(CrablerError::BodyTimeout(url.to_string()))),
+        None => read.await,
     }
+}
 
-    Ok(WorkOutput::Markup { status, url, text })
+/// Hand body chunks over to `on_stream` handler as they arrive, leaving the whole body
+/// in the response to be decoded as usual
+async fn tee_body(
+    response: &mut surf::Response,
+    url: &str,
+    status: u16,
+    headers: &Headers,
+    handle: &CrablerHandle,
+    workoutput_tx: &Sender<WorkOutput>,
+) -> Result<()> {
+    let (chunks_tx, chunks) = bounded(STREAM_BUFFER);
+
+    // stream is extra output for the same work, count it so crawl does not end before it is handled
+    handle.counter.fetch_add(1, Ordering::SeqCst);
+    workoutput_tx
+        .send(WorkOutput::Stream {
+            url: url.to_string(),
+            status,
+            headers: headers.clone(),
+            chunks,
+        })
+        .await?;
+
+    let mut body = response.take_body();
+    let mut bytes = vec![];
+    let mut buf = vec![0; 16 * 1024];
+    loop {
+        let n = body
+            .read(&mut buf)
+            .await
+            .map_err(|e| CrablerError::Network(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+
+        bytes.extend_from_slice(&buf[..n]);
+        // handler may stop reading early, the rest of the body is still needed for other handlers
+        let _ = chunks_tx.send(buf[..n].to_vec()).await;
+    }
+    drop(chunks_tx);
+
+    response.set_body(bytes);
+    Ok(())
+}
+
+async fn stream_lines(url: String, body: surf::Body, lines_tx: Sender<String>) {
+    let mut lines = body.lines();
+
+    while let Some(line) = lines.next().await {
+        match line {
+            Ok(line) => {
+                if lines_tx.send(line).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read lines of {}: {}", url, e);
+                break;
+            }
+        }
+    }
 }
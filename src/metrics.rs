@@ -0,0 +1,192 @@
+use crate::{CrablerHandle, Result};
+use http_client::isahc::IsahcClient;
+use isahc::config::Configurable;
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+/// Client shared by all crawls, like the default one of surf,
+/// but with curl metrics enabled to see how connections are used
+fn metrics_client() -> &'static surf::Client {
+    static CLIENT: OnceLock<surf::Client> = OnceLock::new();
+
+    CLIENT.get_or_init(|| {
+        let client = isahc::HttpClient::builder()
+            .metrics(true)
+            .build()
+            .expect("failed to build http client");
+
+        surf::Client::with_http_client(IsahcClient::from_client(client))
+    })
+}
+
+impl CrablerHandle {
+    /// Crawl metrics in Prometheus text exposition format, serve it from an http endpoint
+    /// for Prometheus to scrape. Counters start from zero for every crawl
+    pub fn metrics_prometheus(&self) -> String {
+        let report = self.report();
+        let mut out = Exposition {
+            text: String::new(),
+            labels: report
+                .crawl_id
+                .as_ref()
+                .map(|id| format!("crawl_id=\"{}\"", escape(id))),
+        };
+
+        out.metric(
+            "crabler_pages_total",
+            "counter",
+            "Pages and files fetched, including ones with error status",
+            report.pages,
+        );
+        out.labeled(
+            "crabler_responses_total",
+            "Pages fetched by response status code",
+            "status",
+            &report.statuses,
+        );
+        out.labeled(
+            "crabler_host_pages_total",
+            "Pages and files fetched by host",
+            "host",
+            &report.hosts,
+        );
+        out.metric(
+            "crabler_errors_total",
+            "counter",
+            "Requests that failed without a response",
+            report.errors.len(),
+        );
+        out.metric(
+            "crabler_received_bytes_total",
+            "counter",
+            "Bytes of page and file bodies received",
+            report.total_bytes,
+        );
+        out.metric(
+            "crabler_skipped_total",
+            "counter",
+            "Urls skipped by robots.txt, host budgets, trap and near-duplicate detection",
+            report.skipped_by_robots()
+                + report.over_budget.values().sum::<usize>()
+                + report.traps.len()
+                + report.near_duplicates.len(),
+        );
+        out.metric(
+            "crabler_queue_depth",
+            "gauge",
+            "Urls queued or in-flight",
+            self.frontier_len(),
+        );
+        out.metric(
+            "crabler_live_workers",
+            "gauge",
+            "Workers currently running",
+            self.live_workers.load(Ordering::SeqCst),
+        );
+        out.metric(
+            "crabler_requests_in_flight",
+            "gauge",
+            "Requests waiting for response headers, each holds a connection",
+            self.requests_in_flight.load(Ordering::SeqCst),
+        );
+        out.metric(
+            "crabler_connections_opened_total",
+            "counter",
+            "Requests that had to open a new connection",
+            report.connections.new_connections,
+        );
+        out.metric(
+            "crabler_connections_reused_total",
+            "counter",
+            "Requests sent over a kept-alive connection",
+            report.connections.reused_connections,
+        );
+        out.metric(
+            "crabler_tls_handshakes_total",
+            "counter",
+            "New connections that went through a TLS handshake",
+            report.connections.tls_handshakes,
+        );
+
+        out.text
+    }
+
+    /// Send request through the metrics enabled client and count how it got its connection.
+    /// Curl reports no connect time for requests over a reused connection
+    pub(crate) async fn send_measured(
+        &self,
+        request: surf::RequestBuilder,
+    ) -> Result<surf::Response> {
+        self.requests_in_flight.fetch_add(1, Ordering::SeqCst);
+        let response = metrics_client().send(request).await;
+        self.requests_in_flight.fetch_sub(1, Ordering::SeqCst);
+        let response = response?;
+
+        if let Some(metrics) = response.ext::<isahc::Metrics>() {
+            let connections = &mut self.report.lock().unwrap().connections;
+            if metrics.connect_time().is_zero() {
+                connections.reused_connections += 1;
+            } else {
+                connections.new_connections += 1;
+            }
+            if !metrics.secure_connect_time().is_zero() {
+                connections.tls_handshakes += 1;
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// Metrics text being built, with labels added to every sample
+struct Exposition {
+    text: String,
+    labels: Option<String>,
+}
+
+impl Exposition {
+    fn metric(&mut self, name: &str, kind: &str, help: &str, value: impl Display) {
+        self.header(name, kind, help);
+        let labels = match &self.labels {
+            Some(labels) => format!("{{{}}}", labels),
+            None => String::new(),
+        };
+        let _ = writeln!(self.text, "{}{} {}", name, labels, value);
+    }
+
+    fn labeled<K: Display + Ord + Hash>(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &HashMap<K, usize>,
+    ) {
+        self.header(name, "counter", help);
+
+        let mut values: Vec<_> = values.iter().collect();
+        values.sort();
+        for (key, value) in values {
+            let mut labels = format!("{}=\"{}\"", label, escape(&key.to_string()));
+            if let Some(common) = &self.labels {
+                labels = format!("{},{}", common, labels);
+            }
+            let _ = writeln!(self.text, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+    }
+}
+
+/// Escape label value as required by the text format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
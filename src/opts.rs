@@ -1,20 +1,241 @@
+#[cfg(feature = "testing")]
+use crate::ChaosConfig;
+use crate::{
+    AjaxPagination, BloomVisitedStore, BodyDecodePolicy, CrablerError, CrablerHandle,
+    CrawlSnapshot, Credentials, Document, DownloadSink, FileSink, Headers, JsonValue,
+    MemoryVisitedStore, TerminationCondition, TrapDetection, UrlOutcome, VisitedLinks,
+    VisitedStore,
+};
+use async_lock::Semaphore;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
 pub type Urls = Vec<String>;
 // pub type Proxies = Vec<String>;
 pub type Threads = usize;
 
+/// Decides from url and response headers whether body should be downloaded
+pub type ShouldDownloadBody = dyn Fn(&str, &Headers) -> bool + Send + Sync;
+
+/// Maps url and destination given to `Response::download_file` to the path file is written to
+pub type DestinationRewriter = dyn Fn(&str, &str) -> String + Send + Sync;
+
+/// Transforms raw markup before it is parsed
+pub type HtmlPreprocessor = dyn Fn(String) -> String + Send + Sync;
+
+/// Decides from url and parsed page whether it is a "not found" page served with success status
+pub type Soft404Detection = dyn Fn(&str, &Document) -> bool + Send + Sync;
+
+/// Computes key of a url in visited links, urls with the same key are visited only once
+pub type DedupKey = dyn Fn(&Url) -> String + Send + Sync;
+
+/// Receives periodic snapshots of crawl progress
+pub type SnapshotCallback = dyn Fn(&CrawlSnapshot) + Send + Sync;
+
+/// Receives outcome of every processed url in place of the default log line
+pub type UrlOutcomeLogger = dyn Fn(&UrlOutcome) + Send + Sync;
+
+/// Decides whether a page that failed with given error is retried, given how many times it already was
+pub type ShouldRetry = dyn Fn(&Url, &CrablerError, usize) -> bool + Send + Sync;
+
+/// User provided callback stored in `Opts`
+pub struct Hook<T: ?Sized>(pub Arc<T>);
+
+impl<T: ?Sized> Clone for Hook<T> {
+    fn clone(&self) -> Self {
+        Hook(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Hook<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// Order of handlers of an html page, see `Opts::with_dispatch_order`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DispatchOrder {
+    /// `on_html` and other handlers of the document run first, `on_response` last
+    #[default]
+    HtmlFirst,
+    /// `on_response` runs first and can skip the rest with `Response::skip_html`
+    ResponseFirst,
+}
+
 #[derive(Clone, Debug)]
 pub struct Opts {
     pub urls: Urls,
+    pub scope_to_seed_path: bool,
     // pub proxies: Proxies,
     pub threads: Threads,
+    pub handle: Option<CrablerHandle>,
+    pub seed: Option<u64>,
+    pub sitemaps: Urls,
+    pub download_urls: Vec<(String, String)>,
+    pub ajax_pagination: Vec<AjaxPagination>,
+    pub follow_feeds: bool,
+    pub follow_meta_refresh: bool,
+    pub meta_refresh_max_delay: Duration,
+    pub prefetch: Urls,
+    pub sitemap_max_depth: usize,
+    pub state_file: Option<String>,
+    pub id: Option<String>,
+    pub cache_dir: Option<String>,
+    pub warc_output: Option<String>,
+    pub request_log: Option<String>,
+    pub request_log_bodies: bool,
+    pub replay_from: Option<String>,
+    pub offline: bool,
+    pub checkpoint_interval: Option<Duration>,
+    pub revisit_after: Option<Duration>,
+    pub visited_store: Option<Hook<dyn VisitedStore>>,
+    pub keep_alive: bool,
+    pub final_retry_pass: bool,
+    pub fail_fast: bool,
+    pub abort_on_found: bool,
+    pub require_https: bool,
+    pub block_non_http_schemes: bool,
+    pub retries: usize,
+    pub retry_backoff: Duration,
+    pub should_retry: Option<Hook<ShouldRetry>>,
+    pub per_url_deadline: Option<Duration>,
+    pub body_read_timeout: Option<Duration>,
+    pub progress_log_interval: Option<Duration>,
+    pub snapshot_callback: Option<(Duration, Hook<SnapshotCallback>)>,
+    pub url_outcome_logger: Option<Hook<UrlOutcomeLogger>>,
+    pub respect_robots_txt: bool,
+    pub respect_meta_robots: bool,
+    pub reject_unexpected_content: bool,
+    pub handle_data_uris: bool,
+    pub link_attributes: Vec<(String, String)>,
+    pub should_download_body: Option<Hook<ShouldDownloadBody>>,
+    pub html_preprocessor: Option<Hook<HtmlPreprocessor>>,
+    pub destination_rewriter: Option<Hook<DestinationRewriter>>,
+    pub soft_404_detection: Option<Hook<Soft404Detection>>,
+    pub dedup_key: Option<Hook<DedupKey>>,
+    pub download_sink: Hook<dyn DownloadSink>,
+    pub near_dup_distance: Option<u32>,
+    pub skip_duplicate_content: bool,
+    pub body_decode_policy: BodyDecodePolicy,
+    pub dispatch_order: DispatchOrder,
+    pub follow_fragment_links: bool,
+    pub max_total_bytes: Option<u64>,
+    pub termination: TerminationCondition,
+    pub max_pages_per_host: Option<usize>,
+    pub max_links_per_page: Option<usize>,
+    pub max_redirects: usize,
+    pub initial_host_delay: Option<Duration>,
+    pub max_url_length: Option<usize>,
+    pub trap_detection: Option<TrapDetection>,
+    pub ordered_hosts: Vec<String>,
+    pub max_worker_restarts: Option<usize>,
+    pub default_headers: Headers,
+    pub accept_language: Option<String>,
+    pub host_headers: HashMap<String, Headers>,
+    pub host_overrides: HashMap<String, String>,
+    pub credentials: HashMap<String, Credentials>,
+    pub host_clients: HashMap<String, surf::Client>,
+    pub worker_restart_backoff: Duration,
+    /// Worker slots shared with other jobs of a `CrawlerPool`
+    pub(crate) worker_slots: Option<Arc<Semaphore>>,
+    pub max_concurrent_documents: Option<usize>,
+    /// Slots of markup responses fetched but not processed yet, created for every crawl
+    pub(crate) document_slots: Option<Arc<Semaphore>>,
+    pub max_inflight: Option<usize>,
+    /// Slots of requests currently sent, shared by all workers of a crawl
+    pub(crate) inflight_slots: Option<Arc<Semaphore>>,
+    #[cfg(feature = "testing")]
+    pub chaos: Option<ChaosConfig>,
 }
 
 impl Opts {
     pub fn new() -> Self {
         Opts {
             urls: vec![],
+            scope_to_seed_path: false,
             // proxies: vec![],
             threads: 1,
+            handle: None,
+            seed: None,
+            sitemaps: vec![],
+            download_urls: vec![],
+            ajax_pagination: vec![],
+            follow_feeds: false,
+            follow_meta_refresh: false,
+            meta_refresh_max_delay: Duration::ZERO,
+            prefetch: vec![],
+            sitemap_max_depth: 5,
+            state_file: None,
+            id: None,
+            cache_dir: None,
+            warc_output: None,
+            request_log: None,
+            request_log_bodies: false,
+            replay_from: None,
+            offline: false,
+            checkpoint_interval: None,
+            revisit_after: None,
+            visited_store: None,
+            keep_alive: false,
+            final_retry_pass: false,
+            fail_fast: false,
+            abort_on_found: false,
+            require_https: false,
+            block_non_http_schemes: false,
+            retries: 0,
+            retry_backoff: Duration::from_millis(500),
+            should_retry: None,
+            per_url_deadline: None,
+            body_read_timeout: None,
+            progress_log_interval: None,
+            snapshot_callback: None,
+            url_outcome_logger: None,
+            respect_robots_txt: false,
+            respect_meta_robots: false,
+            reject_unexpected_content: false,
+            handle_data_uris: false,
+            link_attributes: vec![],
+            should_download_body: None,
+            html_preprocessor: None,
+            destination_rewriter: None,
+            soft_404_detection: None,
+            dedup_key: None,
+            download_sink: Hook(Arc::new(FileSink::default())),
+            near_dup_distance: None,
+            skip_duplicate_content: false,
+            body_decode_policy: BodyDecodePolicy::Fail,
+            dispatch_order: DispatchOrder::HtmlFirst,
+            follow_fragment_links: false,
+            max_total_bytes: None,
+            termination: TerminationCondition::default(),
+            max_pages_per_host: None,
+            max_links_per_page: None,
+            max_redirects: 0,
+            initial_host_delay: None,
+            max_url_length: None,
+            trap_detection: None,
+            ordered_hosts: vec![],
+            max_worker_restarts: None,
+            default_headers: Headers::new(),
+            accept_language: None,
+            host_headers: HashMap::new(),
+            host_overrides: HashMap::new(),
+            credentials: HashMap::new(),
+            host_clients: HashMap::new(),
+            worker_restart_backoff: Duration::from_millis(100),
+            worker_slots: None,
+            max_concurrent_documents: None,
+            document_slots: None,
+            max_inflight: None,
+            inflight_slots: None,
+            #[cfg(feature = "testing")]
+            chaos: None,
         }
     }
 
@@ -25,6 +246,16 @@ impl Opts {
         new
     }
 
+    /// Only visit urls at or below directory of one of the seed urls from `with_urls`,
+    /// e.g. seed `http://x/docs/guide/intro.html` keeps the crawl within `http://x/docs/guide/`.
+    /// Other pages get a 304 response, downloads are not restricted
+    pub fn with_scope_to_seed_path(self, input: bool) -> Self {
+        let mut new = self;
+        new.scope_to_seed_path = input;
+
+        new
+    }
+
     //     pub fn with_proxies(self, input: Vec<&str>) -> Self {
     //         let mut new = self;
     //         new.proxies = input.iter().map(|s| s.to_string()).collect();
@@ -38,4 +269,925 @@ impl Opts {
 
         new
     }
+
+    /// Control the crawl through given handle, see `CrablerHandle`
+    pub fn with_handle(self, input: CrablerHandle) -> Self {
+        let mut new = self;
+        new.handle = Some(input);
+
+        new
+    }
+
+    /// Discover urls to crawl from given sitemaps, sitemap index files are followed recursively.
+    /// Gzip compressed sitemaps are supported when server marks them with `Content-Encoding`
+    pub fn with_sitemaps(self, input: Vec<&str>) -> Self {
+        let mut new = self;
+        new.sitemaps = input.iter().map(|s| s.to_string()).collect();
+
+        new
+    }
+
+    /// Download given urls into their destination paths when the crawl starts,
+    /// without navigating to them, e.g. to mirror a known list of files
+    pub fn with_download_urls(self, input: Vec<(&str, &str)>) -> Self {
+        let mut new = self;
+        new.download_urls = input
+            .iter()
+            .map(|(url, destination)| (url.to_string(), destination.to_string()))
+            .collect();
+
+        new
+    }
+
+    /// Crawl JSON endpoint paginated by page number, e.g. infinite scroll backends.
+    /// `{page}` in the template is replaced with 1 when the crawl starts, every fetched page is
+    /// parsed and the next one is requested until `stop` returns true for it, a page fails or
+    /// is not JSON. Pages are dispatched to `on_response` with the body in `Response::json`,
+    /// they are regular navigations, so throttling and page limits apply to them
+    pub fn with_ajax_pagination<F>(self, url_template: &str, stop: F) -> Self
+    where
+        F: Fn(&JsonValue) -> bool + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.ajax_pagination.push(AjaxPagination {
+            url_template: url_template.to_string(),
+            stop: Hook(Arc::new(stop)),
+        });
+
+        new
+    }
+
+    /// Warm up connections to hosts of given urls before the crawl starts, one `HEAD` request
+    /// per host is sent concurrently so the crawl does not start with cold DNS and connections.
+    /// Responses are not processed or cached, failures are only logged
+    pub fn with_prefetch(self, input: Vec<&str>) -> Self {
+        let mut new = self;
+        new.prefetch = input.iter().map(|s| s.to_string()).collect();
+
+        new
+    }
+
+    /// Fetch RSS and Atom feeds linked from pages with `<link rel="alternate">`, their items are
+    /// passed to `#[on_feed_item]` handler and links of the items are navigated to.
+    /// Malformed feeds are skipped with a warning
+    pub fn with_follow_feeds(self, input: bool) -> Self {
+        let mut new = self;
+        new.follow_feeds = input;
+
+        new
+    }
+
+    /// Navigate to the target of `<meta http-equiv="refresh" content="0; url=...">`,
+    /// like a browser would. Target goes through the same rules as any other link,
+    /// e.g. `nofollow` and `with_scope_to_seed_path`. Only immediate refreshes are followed,
+    /// see `with_meta_refresh_max_delay`
+    pub fn with_follow_meta_refresh(self, input: bool) -> Self {
+        let mut new = self;
+        new.follow_meta_refresh = input;
+
+        new
+    }
+
+    /// Also follow meta refreshes delayed by up to this long, defaults to zero.
+    /// Target is navigated to right away, crawl does not wait for the delay
+    pub fn with_meta_refresh_max_delay(self, input: Duration) -> Self {
+        let mut new = self;
+        new.meta_refresh_max_delay = input;
+
+        new
+    }
+
+    /// How deep nested sitemap index files are followed, defaults to 5
+    pub fn with_sitemap_max_depth(self, input: usize) -> Self {
+        let mut new = self;
+        new.sitemap_max_depth = input;
+
+        new
+    }
+
+    /// Persist visited links and pending work to given file when crawl finishes,
+    /// crawl is resumed from this file if it already exists on start.
+    /// `{id}` in the path is replaced with the crawl id, see `with_id`
+    pub fn with_state_file(self, input: &str) -> Self {
+        let mut new = self;
+        new.state_file = Some(input.to_string());
+
+        new
+    }
+
+    /// Identify the crawl with given id in logs, `CrawlReport::crawl_id`, metrics labels
+    /// and the state file path. With `crawl-id` feature a random uuid is used when not set
+    pub fn with_id(self, input: &str) -> Self {
+        let mut new = self;
+        new.id = Some(input.to_string());
+
+        new
+    }
+
+    /// Path of the state file with crawl id filled in
+    pub(crate) fn state_file(&self) -> Option<String> {
+        let path = self.state_file.as_ref()?;
+
+        Some(match &self.id {
+            Some(id) => path.replace("{id}", id),
+            None => path.clone(),
+        })
+    }
+
+    /// Directory with html pages of the request log, see `with_request_log_bodies`
+    pub(crate) fn request_log_pages(&self) -> Option<String> {
+        match (&self.request_log, self.request_log_bodies) {
+            (Some(path), true) => Some(pages_dir(path)),
+            _ => None,
+        }
+    }
+
+    /// Never fetch plain `http`: urls are upgraded to `https` when they are queued, counted
+    /// in `CrawlReport::https_upgrades`. There is no fallback to `http`, a host without https
+    /// fails like any other unreachable url
+    pub fn with_require_https(self, input: bool) -> Self {
+        let mut new = self;
+        new.require_https = input;
+
+        new
+    }
+
+    /// Skip urls with schemes other than `http`, `https` and `data` when they are queued,
+    /// e.g. `mailto:`, `tel:`, `javascript:` or `ftp:` links that would otherwise fail.
+    /// Skipped urls are counted by scheme in `CrawlReport::scheme_blocked`
+    pub fn with_block_non_http_schemes(self, input: bool) -> Self {
+        let mut new = self;
+        new.block_non_http_schemes = input;
+
+        new
+    }
+
+    /// Store fetched pages in given directory and serve later visits of the same url from it,
+    /// also across runs. Only pages handled as html are cached, not downloads or streamed lines
+    pub fn with_cache_dir(self, input: &str) -> Self {
+        let mut new = self;
+        new.cache_dir = Some(input.to_string());
+
+        new
+    }
+
+    /// Archive every fetched html page with its status and headers into a WARC 1.1 file
+    /// at given path, replacing the file if it exists. Downloads, streamed lines and pages
+    /// whose body was skipped are not archived. Bodies are stored decoded
+    pub fn with_warc_output(self, input: &str) -> Self {
+        let mut new = self;
+        new.warc_output = Some(input.to_string());
+
+        new
+    }
+
+    /// Append a JSON line for every request to the file at given path, with its timestamp, url,
+    /// method, status, bytes, duration in milliseconds, whether it was served from the cache
+    /// and the error if it failed. Skipped urls are not logged, the file is flushed once the crawl ends
+    pub fn with_request_log(self, input: &str) -> Self {
+        let mut new = self;
+        new.request_log = Some(input.to_string());
+
+        new
+    }
+
+    /// Also store html pages of the request log next to it, in `<path>.pages` directory
+    /// in the format of `with_cache_dir`, so the crawl can be replayed with `with_replay_from`
+    pub fn with_request_log_bodies(self, input: bool) -> Self {
+        let mut new = self;
+        new.request_log_bodies = input;
+
+        new
+    }
+
+    /// Replay a crawl captured with `with_request_log` and `with_request_log_bodies` at given
+    /// path: logged html pages are queued in the logged order and handlers run against their
+    /// stored content, e.g. to iterate on extraction logic. Crawl is offline and served from
+    /// `<path>.pages`, so urls that were not captured fail with `CrablerError::Offline`
+    pub fn with_replay_from(self, input: &str) -> Self {
+        let mut new = self;
+        new.replay_from = Some(input.to_string());
+        new.cache_dir = Some(pages_dir(input));
+        new.offline = true;
+
+        new
+    }
+
+    /// Never touch the network: pages are served only from `with_cache_dir`, anything else
+    /// fails with `CrablerError::Offline`, robots.txt is not checked. Unlike a dry run,
+    /// cached pages are processed by handlers as usual, so the crawl can be reproduced offline
+    pub fn with_offline(self, input: bool) -> Self {
+        let mut new = self;
+        new.offline = input;
+
+        new
+    }
+
+    /// Also save state file periodically while crawl is running,
+    /// so a crash loses at most one interval of progress. Requires `with_state_file`
+    pub fn with_checkpoint_interval(self, input: Duration) -> Self {
+        let mut new = self;
+        new.checkpoint_interval = Some(input);
+
+        new
+    }
+
+    /// Keep the crawl running when it runs out of work, waiting for urls added with
+    /// `CrablerHandle::enqueue` until `CrablerHandle::stop_after_current` is called
+    pub fn with_keep_alive(self, input: bool) -> Self {
+        let mut new = self;
+        new.keep_alive = input;
+
+        new
+    }
+
+    /// Once the crawl runs out of work, attempt every url that failed with an error once more,
+    /// e.g. to get past transient network failures. Urls failing again are not retried,
+    /// `CrawlReport::retried` and `CrawlReport::recovered` tell how it went
+    pub fn with_final_retry_pass(self, input: bool) -> Self {
+        let mut new = self;
+        new.final_retry_pass = input;
+
+        new
+    }
+
+    /// Stop the crawl on the first url failing with an error, `run` returns
+    /// `CrablerError::Failed` with the url and its error once workers are shut down.
+    /// Meant for validation crawls, e.g. catching broken links in CI. Takes precedence over
+    /// `with_final_retry_pass`, failed url is not attempted again. Retries of
+    /// `with_retries` still happen before the url counts as failed
+    pub fn with_fail_fast(self, input: bool) -> Self {
+        let mut new = self;
+        new.fail_fast = input;
+
+        new
+    }
+
+    /// Abort the crawl once a handler marks a page with `Response::found`, for search-style
+    /// crawls that stop at the first match. Handlers already running finish, like with
+    /// `CrablerHandle::abort`
+    pub fn with_abort_on_found(self, input: bool) -> Self {
+        let mut new = self;
+        new.abort_on_found = input;
+
+        new
+    }
+
+    /// Retry pages failing with a retryable error, see `CrablerError::is_retryable`,
+    /// up to given number of times right away. Defaults to 0
+    pub fn with_retries(self, input: usize) -> Self {
+        let mut new = self;
+        new.retries = input;
+
+        new
+    }
+
+    /// Delay before first retry of a failed page, doubled on every further retry
+    /// up to 256 times the initial delay. Defaults to 500ms
+    pub fn with_retry_backoff(self, input: Duration) -> Self {
+        let mut new = self;
+        new.retry_backoff = input;
+
+        new
+    }
+
+    /// Decide which failed pages are retried instead of retrying retryable errors,
+    /// see `CrablerError::is_retryable`. Called with url, error and number of retries so far,
+    /// pages are still retried at most `Opts::with_retries` times. Urls that fail to parse
+    /// are retried by the default rule
+    pub fn with_should_retry<F>(self, input: F) -> Self
+    where
+        F: Fn(&Url, &CrablerError, usize) -> bool + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.should_retry = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Bound total time spent on a single page across all attempts and backoffs.
+    /// Attempt running past the deadline fails with a timeout and no further retries are made
+    /// when the next one could not start before the deadline
+    pub fn with_per_url_deadline(self, input: Duration) -> Self {
+        let mut new = self;
+        new.per_url_deadline = Some(input);
+
+        new
+    }
+
+    /// Bound time spent reading body of a page, download, sitemap or feed once its headers
+    /// arrived, so servers stalling mid-body are cut off with `CrablerError::BodyTimeout`.
+    /// Streamed lines are not bound, their bodies may be endless
+    pub fn with_body_read_timeout(self, input: Duration) -> Self {
+        let mut new = self;
+        new.body_read_timeout = Some(input);
+
+        new
+    }
+
+    /// Whether page that failed with given error after given number of retries is retried again
+    pub(crate) fn should_retry(&self, url: &str, error: &CrablerError, retries: usize) -> bool {
+        if retries >= self.retries {
+            return false;
+        }
+
+        match (&self.should_retry, Url::parse(url)) {
+            (Some(should_retry), Ok(parsed)) => (should_retry.0)(&parsed, error, retries),
+            _ => error.is_retryable(),
+        }
+    }
+
+    /// Delay before retrying a page that was already retried given number of times
+    pub(crate) fn retry_delay(&self, retries: usize) -> Duration {
+        self.retry_backoff * 2u32.pow(retries.min(8) as u32)
+    }
+
+    /// Let visited urls be visited again once given time passed since the last visit,
+    /// for monitoring crawls that keep going. By default every url is visited only once.
+    /// Visits that expired are dropped from memory over time
+    pub fn with_revisit_after(self, input: Duration) -> Self {
+        let mut new = self;
+        new.revisit_after = Some(input);
+
+        new
+    }
+
+    /// Keep visited urls in given store instead of memory, e.g. `BloomVisitedStore` or
+    /// an implementation backed by a database for crawls too large for an in-memory set.
+    /// `Opts::with_revisit_after` only applies to the default `MemoryVisitedStore`
+    pub fn with_visited_store<S: VisitedStore + 'static>(self, input: S) -> Self {
+        let mut new = self;
+        new.visited_store = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Keep visited urls in a bloom filter sized for `expected_items` urls at given false
+    /// positive rate, e.g. `0.01`, same as `with_visited_store(BloomVisitedStore::new(..))`.
+    /// Memory is fixed up front, about 1.2 bytes per expected url at 1%, instead of growing
+    /// with every url. A false positive means an url that was never visited is skipped, more
+    /// so once the crawl goes past `expected_items`, lower the rate if that is too lossy
+    pub fn with_bloom_visited(self, expected_items: usize, false_positive_rate: f64) -> Self {
+        self.with_visited_store(BloomVisitedStore::new(expected_items, false_positive_rate))
+    }
+
+    /// Log a one-line summary of crawl progress every interval: pages per second,
+    /// pending work, errors and bytes received. Logged at info level like the rest of crabler logs
+    pub fn with_progress_log_interval(self, input: Duration) -> Self {
+        let mut new = self;
+        new.progress_log_interval = Some(input);
+
+        new
+    }
+
+    /// Call given callback every interval with a snapshot of crawl progress, e.g. to store it
+    /// in a database or push it to a monitoring system. Runs on a background task,
+    /// structured counterpart of `Opts::with_progress_log_interval`
+    pub fn with_snapshot_callback<F>(self, interval: Duration, callback: F) -> Self
+    where
+        F: Fn(&CrawlSnapshot) + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.snapshot_callback = Some((interval, Hook(Arc::new(callback))));
+
+        new
+    }
+
+    /// Call given logger with outcome of every url instead of logging it, e.g. to emit
+    /// structured logs, log failures only or feed outcomes into metrics.
+    /// `UrlOutcome::log` writes the default log line, so loggers can fall back to it
+    pub fn with_url_outcome_logger<F>(self, logger: F) -> Self
+    where
+        F: Fn(&UrlOutcome) + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.url_outcome_logger = Some(Hook(Arc::new(logger)));
+
+        new
+    }
+
+    /// Fetch robots.txt for every host and skip urls it disallows.
+    /// Applied rules and skipped urls are recorded in `CrawlReport::robots`
+    pub fn with_respect_robots_txt(self, input: bool) -> Self {
+        let mut new = self;
+        new.respect_robots_txt = input;
+
+        new
+    }
+
+    /// Automatically follow urls found in given attribute of elements matching a selector,
+    /// e.g. `("a", "href")`, `("img", "srcset")` or `("form", "action")`.
+    /// `srcset` is split into candidate urls, relative urls are resolved like `Response::navigate` does
+    /// and only http(s) urls are followed. Links are followed regardless of their host
+    pub fn with_link_attributes(self, input: Vec<(&str, &str)>) -> Self {
+        let mut new = self;
+        new.link_attributes = input
+            .iter()
+            .map(|(selector, attr)| (selector.to_string(), attr.to_string()))
+            .collect();
+
+        new
+    }
+
+    /// Honor `<meta name="robots">` and `X-Robots-Tag` header directives of fetched pages:
+    /// `Response::navigate` is a no-op for `nofollow` pages and `noindex` is exposed as
+    /// `Response::meta_robots` for handlers to check. Header directives scoped to other user
+    /// agents are ignored, pages where the header applied are counted in `CrawlReport::x_robots_tags`
+    pub fn with_respect_meta_robots(self, input: bool) -> Self {
+        let mut new = self;
+        new.respect_meta_robots = input;
+
+        new
+    }
+
+    /// Fail navigations made with `Response::navigate_expecting` when the response
+    /// `Content-Type` is not the expected kind, with `CrablerError::UnexpectedContent`
+    pub fn with_reject_unexpected_content(self, input: bool) -> Self {
+        let mut new = self;
+        new.reject_unexpected_content = input;
+
+        new
+    }
+
+    /// Decode inline `data:` uris instead of skipping them: navigating to one hands decoded
+    /// content to handlers as a page, downloading one writes decoded bytes to the download sink.
+    /// Either way `data:` uris are never fetched from the network
+    pub fn with_handle_data_uris(self, input: bool) -> Self {
+        let mut new = self;
+        new.handle_data_uris = input;
+
+        new
+    }
+
+    /// Decide whether to download page body once its headers arrive, e.g. by `content-type`
+    /// or `content-length`. When callback returns false body is never downloaded,
+    /// `on_html` handlers are skipped and `on_response` gets a response with headers only
+    pub fn with_should_download_body<F>(self, input: F) -> Self
+    where
+        F: Fn(&str, &Headers) -> bool + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.should_download_body = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Change destination of every download once the server responded, before anything
+    /// is written, e.g. to shard files into directories by hash or to sanitize names.
+    /// Callback gets url and destination the download was scheduled with, with file name
+    /// appended for `Response::download_to_dir`. Returned destination is passed to
+    /// the download sink and ends up in `Response::download_destination`
+    pub fn with_destination_rewriter<F>(self, input: F) -> Self
+    where
+        F: Fn(&str, &str) -> String + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.destination_rewriter = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Transform raw markup of every page before it is parsed, e.g. to strip scripts,
+    /// fix encoding artifacts or un-comment content that selectors should see
+    pub fn with_html_preprocessor<F>(self, input: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.html_preprocessor = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Classify pages as soft 404s, e.g. `|_, page| !page.select(".not-found").is_empty()`.
+    /// Soft 404s are not passed to `on_html` handlers nor followed, `on_response` gets them
+    /// with status 404 and they are counted in `CrawlReport::soft_404s`
+    pub fn with_soft_404_detection<F>(self, input: F) -> Self
+    where
+        F: Fn(&str, &Document) -> bool + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.soft_404_detection = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Compute key urls are deduplicated by instead of the url itself, e.g. to drop a session id
+    /// from the path or key product pages by product id. Urls that fail to parse use the url
+    pub fn with_dedup_key<F>(self, input: F) -> Self
+    where
+        F: Fn(&Url) -> String + Send + Sync + 'static,
+    {
+        let mut new = self;
+        new.dedup_key = Some(Hook(Arc::new(input)));
+
+        new
+    }
+
+    /// Stream downloaded files into given sink instead of local files,
+    /// e.g. to upload them to object storage or keep them in memory
+    pub fn with_download_sink<S: DownloadSink + 'static>(self, input: S) -> Self {
+        let mut new = self;
+        new.download_sink = Hook(Arc::new(input));
+
+        new
+    }
+
+    /// Give downloaded files these Unix permission bits, e.g. `0o644` for a mirror served
+    /// by a web server. Same as `with_download_sink(FileSink::default().with_permissions(mode))`,
+    /// so it replaces a sink set before. Ignored on other platforms
+    pub fn with_download_permissions(self, mode: u32) -> Self {
+        self.with_download_sink(FileSink::default().with_permissions(mode))
+    }
+
+    /// Skip pages whose text SimHash is within given Hamming distance of an already
+    /// processed page, catching templated pages that only differ in boilerplate.
+    /// Skipped pages get a 304 response and are listed in `CrawlReport::near_duplicates`
+    pub fn with_near_dup_detection(self, input: u32) -> Self {
+        let mut new = self;
+        new.near_dup_distance = Some(input);
+
+        new
+    }
+
+    /// Follow links like `#section` that only point into the page they are on.
+    /// Off by default, such links lead to the page itself and are skipped by `Response::navigate`
+    pub fn with_follow_fragment_links(self, input: bool) -> Self {
+        let mut new = self;
+        new.follow_fragment_links = input;
+
+        new
+    }
+
+    /// Decode pages that can not be decoded with their charset, e.g. invalid UTF-8 or
+    /// an unknown charset, as lossy UTF-8 instead of failing them with a parse error.
+    /// Undecodable bytes become U+FFFD replacement characters and a warning is logged.
+    /// Same as `with_body_decode_policy(BodyDecodePolicy::Lossy)`
+    pub fn with_lossy_decoding(self, input: bool) -> Self {
+        self.with_body_decode_policy(if input {
+            BodyDecodePolicy::Lossy
+        } else {
+            BodyDecodePolicy::Fail
+        })
+    }
+
+    /// Choose whether `on_response` of an html page runs before or after its `on_html`,
+    /// `on_html_all`, `on_html_for` and `on_json_ld` handlers. Running it first lets it
+    /// set up per-page state or skip the other handlers with `Response::skip_html`.
+    /// By default `on_response` runs last
+    pub fn with_dispatch_order(self, input: DispatchOrder) -> Self {
+        let mut new = self;
+        new.dispatch_order = input;
+
+        new
+    }
+
+    /// Choose what happens to pages that can not be decoded with their charset,
+    /// by default they fail with a parse error
+    pub fn with_body_decode_policy(self, input: BodyDecodePolicy) -> Self {
+        let mut new = self;
+        new.body_decode_policy = input;
+
+        new
+    }
+
+    /// Bound number of html pages held in memory at once, from fetching until their handlers
+    /// are done. Workers wait for a slot before fetching a page, which caps peak memory
+    /// when handlers are slower than fetching. Unlimited by default
+    pub fn with_max_concurrent_documents(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_concurrent_documents = Some(input);
+
+        new
+    }
+
+    /// Bound number of requests sent at once by all workers together, independent of
+    /// `Opts::with_threads`. Many workers can then run handlers in parallel while the number
+    /// of concurrent connections stays within server or NAT limits. Unlimited by default
+    pub fn with_max_inflight(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_inflight = Some(input);
+
+        new
+    }
+
+    /// Skip handlers of pages whose markup is byte-identical to an already processed page,
+    /// e.g. mirrors or urls differing only in ignored parameters. Cheaper and stricter than
+    /// `Opts::with_near_dup_detection`. Skipped pages get a 304 response and are listed
+    /// in `CrawlReport::duplicate_content`
+    pub fn with_skip_duplicate_content(self, input: bool) -> Self {
+        let mut new = self;
+        new.skip_duplicate_content = input;
+
+        new
+    }
+
+    /// Stop the crawl gracefully, like `CrablerHandle::stop_after_current`, once this many bytes were received.
+    /// Counts bodies of fetched pages and downloaded files after content decoding,
+    /// headers, robots.txt and sitemaps are not included. Work already in-flight
+    /// when the limit is reached still finishes, so total can go over the limit
+    pub fn with_max_total_bytes(self, input: u64) -> Self {
+        let mut new = self;
+        new.max_total_bytes = Some(input);
+
+        new
+    }
+
+    /// Stop the crawl gracefully once given condition is met, checked after every
+    /// processed response. By default crawl runs until it runs out of work
+    pub fn with_termination(self, input: TerminationCondition) -> Self {
+        let mut new = self;
+        new.termination = input;
+
+        new
+    }
+
+    /// Headers sent with every request.
+    /// Headers for a host from `with_host_headers` take precedence over these,
+    /// headers crabler sets for a single request (e.g. `Cookie` with `cookie-store` feature)
+    /// take precedence over both
+    pub fn with_default_headers(self, input: HashMap<&str, &str>) -> Self {
+        let mut new = self;
+        new.default_headers = lowercase_headers(input);
+
+        new
+    }
+
+    /// Ask every host for content in given languages by sending `Accept-Language` header,
+    /// e.g. `"de-DE, de;q=0.9, en;q=0.5"`. Takes precedence over `Accept-Language` from
+    /// `with_default_headers`, while headers from `with_host_headers` still take precedence
+    /// over it, e.g. to set a locale cookie or another language for a single host
+    pub fn with_accept_language(self, input: &str) -> Self {
+        let mut new = self;
+        new.accept_language = Some(input.to_string());
+
+        new
+    }
+
+    /// Headers sent only with requests to given host, e.g. credentials for an API.
+    /// Host has to match exactly, so following links to other domains never leaks them.
+    /// Can be called multiple times for different hosts, see `with_default_headers` for merge order
+    pub fn with_host_headers(self, host: &str, input: HashMap<&str, &str>) -> Self {
+        let mut new = self;
+        new.host_headers
+            .insert(host.to_lowercase(), lowercase_headers(input));
+
+        new
+    }
+
+    /// Send `Host: override_host` with requests to urls of `url_host`, while still connecting
+    /// to the address in the url, e.g. to crawl a vhost through a load balancer or a staging
+    /// server by its ip. Takes precedence over `Host` from any headers option.
+    /// TLS SNI and certificate verification keep using the url host, the http client
+    /// does not allow setting them separately.
+    ///
+    /// Server is told it serves `override_host` while crabler keeps tracking the url host,
+    /// so cookies, per-host headers, robots.txt and host budgets all belong to `url_host`.
+    /// Links on crawled pages usually point to `override_host` itself, so restrict crawl
+    /// (e.g. with `with_scope_to_seed_path`) unless crawling the real host is intended too
+    pub fn with_host_override(self, url_host: &str, override_host: &str) -> Self {
+        let mut new = self;
+        new.host_overrides
+            .insert(url_host.to_lowercase(), override_host.to_string());
+
+        new
+    }
+
+    /// Answer `401` responses of given host carrying a `WWW-Authenticate` challenge
+    /// with these credentials, the request is retried once with Basic or Digest
+    /// (`MD5`, `SHA-256` and their `-sess` variants with `qop=auth`) authorization.
+    /// Challenge is remembered, so later requests to the host authenticate up front,
+    /// reusing digest nonce until server rejects it. Host has to match exactly, like in
+    /// `with_host_headers`. Basic auth sends the password in plain text, use it over https
+    pub fn with_credentials(self, host: &str, username: &str, password: &str) -> Self {
+        let mut new = self;
+        new.credentials.insert(
+            host.to_lowercase(),
+            Credentials {
+                username: username.to_string(),
+                password: password.to_string(),
+            },
+        );
+
+        new
+    }
+
+    /// Send requests to given host with given client instead of the default one, e.g. one
+    /// built from `surf::Config` with a proxy, a different timeout or TLS settings. Host has
+    /// to match exactly, like in `with_host_headers`. Connection metrics of the `metrics`
+    /// feature are only collected for the default client
+    pub fn with_host_client(self, host: &str, client: surf::Client) -> Self {
+        let mut new = self;
+        new.host_clients.insert(host.to_lowercase(), client);
+
+        new
+    }
+
+    /// Check url against `with_scope_to_seed_path`, always true when scoping is off
+    pub(crate) fn in_seed_scope(&self, url: &str) -> bool {
+        if !self.scope_to_seed_path {
+            return true;
+        }
+
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+
+        self.urls
+            .iter()
+            .filter_map(|seed| Url::parse(seed).ok())
+            .any(|seed| {
+                let directory = &seed.path()[..seed.path().rfind('/').unwrap_or(0) + 1];
+
+                seed.scheme() == url.scheme()
+                    && seed.host_str() == url.host_str()
+                    && seed.port_or_known_default() == url.port_or_known_default()
+                    && url.path().starts_with(directory)
+            })
+    }
+
+    /// Visited links of a new crawl, kept in the configured store
+    pub(crate) fn visited_links(&self) -> VisitedLinks {
+        let store: Hook<dyn VisitedStore> = match &self.visited_store {
+            Some(store) => store.clone(),
+            None => Hook(Arc::new(MemoryVisitedStore::new(self.revisit_after))),
+        };
+
+        VisitedLinks::new(store)
+    }
+
+    /// Key of given url in visited links
+    pub(crate) fn dedup_key(&self, url: &str) -> String {
+        match (&self.dedup_key, Url::parse(url)) {
+            (Some(dedup_key), Ok(parsed)) => (dedup_key.0)(&parsed),
+            _ => url.to_string(),
+        }
+    }
+
+    /// Default headers merged with headers and `Host` override for given host
+    pub(crate) fn headers_for(&self, host: &str) -> Headers {
+        let mut headers = self.default_headers.clone();
+        if let Some(accept_language) = &self.accept_language {
+            headers.insert("accept-language".to_string(), accept_language.clone());
+        }
+        if let Some(host_headers) = self.host_headers.get(&host.to_lowercase()) {
+            headers.extend(host_headers.clone());
+        }
+        if let Some(host) = self.host_overrides.get(&host.to_lowercase()) {
+            headers.insert("host".to_string(), host.clone());
+        }
+
+        headers
+    }
+
+    /// Follow up to this many redirects of a page, hops are listed in
+    /// `Response::redirect_chain`. Off by default, so 3xx responses are handled as they are.
+    /// Once the limit is reached the last 3xx response is handled instead
+    pub fn with_max_redirects(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_redirects = input;
+
+        new
+    }
+
+    /// Fetch at most this many pages from a single host, so crawl samples many hosts
+    /// instead of getting stuck in one big site. Further urls of the host get a 304 response
+    /// and are counted in `CrawlReport::over_budget`. Downloads do not count against the budget
+    pub fn with_max_pages_per_host(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_pages_per_host = Some(input);
+
+        new
+    }
+
+    /// Follow at most this many links from a single html page, guarding against pages with
+    /// huge numbers of links. Further `Response::navigate` calls of the page are dropped,
+    /// the page is logged with a warning and recorded in `CrawlReport::link_capped`
+    pub fn with_max_links_per_page(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_links_per_page = Some(input);
+
+        new
+    }
+
+    /// Wait given delay before the first request to every host, including its robots.txt,
+    /// so a fragile server does not get hit by a burst of requests right away.
+    /// Further requests to the host are not delayed, hosts found later wait from the moment they are found
+    pub fn with_initial_host_delay(self, input: Duration) -> Self {
+        let mut new = self;
+        new.initial_host_delay = Some(input);
+
+        new
+    }
+
+    /// Skip navigation to urls longer than given number of bytes with a warning, such urls
+    /// are usually crawler traps or encoding bugs. Skipped urls get a 304 response. Unlimited by default
+    pub fn with_max_url_length(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_url_length = Some(input);
+
+        new
+    }
+
+    /// Skip urls that look like crawler traps, e.g. ever repeating path segments or
+    /// endless variations of the same query, using default `TrapDetection` heuristics.
+    /// Skipped urls get a 304 response and are listed in `CrawlReport::traps`
+    pub fn with_trap_detection(self, input: bool) -> Self {
+        let mut new = self;
+        new.trap_detection = if input {
+            Some(TrapDetection::default())
+        } else {
+            None
+        };
+
+        new
+    }
+
+    /// Enable crawler trap detection with given heuristics, see `Opts::with_trap_detection`
+    pub fn with_trap_heuristics(self, input: TrapDetection) -> Self {
+        let mut new = self;
+        new.trap_detection = Some(input);
+
+        new
+    }
+
+    /// Send requests to given hosts one at a time, in the order work was queued, e.g. for
+    /// stateful APIs that depend on request order. Throughput of these hosts drops to a single
+    /// request in flight, and a worker waiting for its turn does not pick up other work meanwhile
+    pub fn with_ordered_hosts(self, input: Vec<&str>) -> Self {
+        let mut new = self;
+        new.ordered_hosts = input.iter().map(|host| host.to_lowercase()).collect();
+
+        new
+    }
+
+    /// How many times a failing worker is restarted before it stays down,
+    /// crawl ends with `CrablerError::WorkersDown` once all workers are down. Unlimited by default
+    pub fn with_max_worker_restarts(self, input: usize) -> Self {
+        let mut new = self;
+        new.max_worker_restarts = Some(input);
+
+        new
+    }
+
+    /// Delay before first restart of a failing worker, doubled on every further restart
+    /// up to 256 times the initial delay. Defaults to 100ms
+    pub fn with_worker_restart_backoff(self, input: Duration) -> Self {
+        let mut new = self;
+        new.worker_restart_backoff = input;
+
+        new
+    }
+
+    /// Delay before restarting a worker that was already restarted given number of times
+    pub(crate) fn worker_restart_delay(&self, restarts: usize) -> Duration {
+        self.worker_restart_backoff * 2u32.pow(restarts.min(8) as u32)
+    }
+
+    /// Seed all random decisions made during the crawl,
+    /// given deterministic responses this makes crawl fully reproducible
+    pub fn with_seed(self, input: u64) -> Self {
+        let mut new = self;
+        new.seed = Some(input);
+
+        new
+    }
+
+    /// Inject random failures and latency into requests to test error handling,
+    /// combine with `with_seed` to make injected failures reproducible
+    #[cfg(feature = "testing")]
+    pub fn with_chaos(self, input: ChaosConfig) -> Self {
+        let mut new = self;
+        new.chaos = Some(input);
+
+        new
+    }
+
+    /// Create random number generator for the crawl,
+    /// seeded with value from `with_seed` or from entropy when no seed was given
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+fn lowercase_headers(input: HashMap<&str, &str>) -> Headers {
+    input
+        .into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect()
+}
+
+impl Default for Opts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Directory next to request log at given path holding its html pages
+fn pages_dir(request_log: &str) -> String {
+    format!("{}.pages", request_log)
 }
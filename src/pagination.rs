@@ -0,0 +1,30 @@
+use crate::{Hook, JsonValue};
+
+/// Placeholder for the page number in `AjaxPagination::url_template`
+pub const PAGE_PLACEHOLDER: &str = "{page}";
+
+/// Decides from parsed body of a page whether it was the last one, e.g. its results are empty
+pub type AjaxPaginationStop = dyn Fn(&JsonValue) -> bool + Send + Sync;
+
+/// Endpoint returning JSON pages numbered from 1 in the url, see `Opts::with_ajax_pagination`
+#[derive(Clone, Debug)]
+pub struct AjaxPagination {
+    /// Url with `{page}` in place of the page number, e.g. `https://example.com/api?page={page}`
+    pub url_template: String,
+    pub stop: Hook<AjaxPaginationStop>,
+}
+
+impl AjaxPagination {
+    /// Url of given page
+    pub(crate) fn page_url(&self, page: usize) -> String {
+        self.url_template
+            .replacen(PAGE_PLACEHOLDER, &page.to_string(), 1)
+    }
+
+    /// Number of the page fetched from url, if it is one of the pages of this endpoint
+    pub(crate) fn page_of(&self, url: &str) -> Option<usize> {
+        let (prefix, suffix) = self.url_template.split_once(PAGE_PLACEHOLDER)?;
+
+        url.strip_prefix(prefix)?.strip_suffix(suffix)?.parse().ok()
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{CrablerHandle, CrawlReport, ImmutableWebScraper, MutableWebScraper, Opts, Result};
+use async_lock::Semaphore;
+use futures::future::{join_all, LocalBoxFuture};
+use std::sync::Arc;
+
+/// Runs several independent crawls in one process.
+///
+/// Every job keeps its own scraper, options, visited links and report,
+/// but all jobs share `threads` worker slots: no more than `threads` requests
+/// are processed at once across the whole pool, regardless of `Opts::threads` of a job.
+pub struct CrawlerPool<'a> {
+    slots: Arc<Semaphore>,
+    jobs: Vec<LocalBoxFuture<'a, Result<CrawlReport>>>,
+}
+
+impl<'a> CrawlerPool<'a> {
+    pub fn new(threads: usize) -> Self {
+        CrawlerPool {
+            slots: Arc::new(Semaphore::new(threads)),
+            jobs: vec![],
+        }
+    }
+
+    /// Add job crawling with given mutable scraper
+    pub fn add_mutable_job<T: MutableWebScraper>(&mut self, scraper: &'a mut T, opts: Opts) {
+        let (handle, opts) = self.job_opts(opts);
+
+        self.jobs.push(Box::pin(async move {
+            scraper.run(opts).await?;
+            Ok(handle.report())
+        }));
+    }
+
+    /// Add job crawling with given immutable scraper
+    pub fn add_immutable_job<T: ImmutableWebScraper>(&mut self, scraper: &'a T, opts: Opts) {
+        let (handle, opts) = self.job_opts(opts);
+
+        self.jobs.push(Box::pin(async move {
+            scraper.run(opts).await?;
+            Ok(handle.report())
+        }));
+    }
+
+    /// Run all jobs to completion, returns report of every job in the order jobs were added
+    pub async fn run(self) -> Vec<Result<CrawlReport>> {
+        join_all(self.jobs).await
+    }
+
+    fn job_opts(&self, opts: Opts) -> (CrablerHandle, Opts) {
+        let handle = opts.handle.clone().unwrap_or_default();
+        let mut opts = opts.with_handle(handle.clone());
+        opts.worker_slots = Some(self.slots.clone());
+
+        (handle, opts)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{CrablerError, RobotsRules};
+use log::{error, info};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Summary of what happened during the crawl, see `CrablerHandle::report`
+#[derive(Clone, Debug, Default)]
+pub struct CrawlReport {
+    /// Id of the crawl, see `Opts::with_id`
+    pub crawl_id: Option<String>,
+    /// Reason given to `CrablerHandle::abort` when the crawl was aborted
+    pub aborted: Option<String>,
+    /// Whether robots.txt was respected during this crawl
+    pub honored_robots: bool,
+    /// Robots.txt audit by host
+    pub robots: HashMap<String, RobotsAudit>,
+    /// Pages skipped as near-duplicates of an already processed page
+    pub near_duplicates: Vec<String>,
+    /// Pages skipped because their markup was identical to an already processed page,
+    /// see `Opts::with_skip_duplicate_content`
+    pub duplicate_content: Vec<String>,
+    /// Urls skipped as likely crawler traps, see `Opts::with_trap_detection`
+    pub traps: Vec<String>,
+    /// Pages with more links than `Opts::with_max_links_per_page`, the rest were not followed
+    pub link_capped: Vec<String>,
+    /// Number of urls skipped by host after the host used up its page budget,
+    /// see `Opts::with_max_pages_per_host`
+    pub over_budget: HashMap<String, usize>,
+    /// Number of urls skipped by scheme, see `Opts::with_block_non_http_schemes`
+    pub scheme_blocked: HashMap<String, usize>,
+    /// Number of `http` urls upgraded to `https`, see `Opts::with_require_https`
+    pub https_upgrades: usize,
+    /// Bytes of page and downloaded file bodies received so far, see `Opts::with_max_total_bytes`
+    pub total_bytes: u64,
+    /// Pages whose `X-Robots-Tag` header applied directives, see `Opts::with_respect_meta_robots`
+    pub x_robots_tags: usize,
+    /// Pages classified as soft 404s, see `Opts::with_soft_404_detection`
+    pub soft_404s: usize,
+    /// Pages and files fetched so far, including ones with error status
+    pub pages: usize,
+    /// Urls of requests that failed without a response, with the error, in the order they
+    /// were processed. A crawl still succeeds when some of its requests fail, check this
+    /// to decide whether it went well enough
+    pub errors: Vec<(String, CrablerError)>,
+    /// Number of pages fetched by response status code, downloads have no status and are not counted
+    pub statuses: HashMap<u16, usize>,
+    /// Number of pages and files fetched by host
+    pub hosts: HashMap<String, usize>,
+    /// Pages marked as the target of a focused crawl with `Response::found`
+    pub found: Vec<String>,
+    /// Failed urls attempted again in the final retry pass, see `Opts::with_final_retry_pass`
+    pub retried: usize,
+    /// Urls from the final retry pass that succeeded the second time
+    pub recovered: usize,
+    /// How requests got their connections, tells whether keep-alive actually works
+    #[cfg(feature = "metrics")]
+    pub connections: ConnectionStats,
+    /// Urls of pages and files fetched, in the order they were processed
+    #[cfg(feature = "testing")]
+    pub visited: Vec<String>,
+    /// Urls of requests that failed without a response, with the error
+    #[cfg(feature = "testing")]
+    pub failed: Vec<(String, String)>,
+    /// Number of elements matched by every `on_html` selector, including selectors matching nothing
+    #[cfg(feature = "testing")]
+    pub selector_matches: HashMap<String, usize>,
+}
+
+/// Connection usage of requests sent during the crawl, with the `metrics` feature
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionStats {
+    /// Requests that had to open a new connection
+    pub new_connections: usize,
+    /// Requests sent over a pooled connection kept alive from an earlier request
+    pub reused_connections: usize,
+    /// New connections that went through a TLS handshake
+    pub tls_handshakes: usize,
+}
+
+#[cfg(feature = "metrics")]
+impl ConnectionStats {
+    /// Share of requests that reused a connection, 0 before the first request
+    pub fn reuse_rate(&self) -> f64 {
+        let total = self.new_connections + self.reused_connections;
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.reused_connections as f64 / total as f64
+    }
+}
+
+/// Progress of a running crawl handed to `Opts::with_snapshot_callback`
+#[derive(Clone, Debug)]
+pub struct CrawlSnapshot {
+    /// Time since the crawl started
+    pub elapsed: Duration,
+    /// Work queued or in-flight
+    pub pending: usize,
+    /// Urls processed so far, fetched or failed
+    pub visited: usize,
+    pub report: CrawlReport,
+}
+
+/// What happened to a single url, handed to `Opts::with_url_outcome_logger`
+#[derive(Debug)]
+pub enum UrlOutcome<'a> {
+    /// Page was fetched and its markup is about to be processed
+    Fetched { url: &'a str, status: u16 },
+    /// Page was detected as soft 404, see `Opts::with_soft_404_detection`
+    Soft404 { url: &'a str },
+    /// Html handlers were skipped by `Response::skip_html`
+    HtmlSkipped { url: &'a str },
+    /// Body is streamed line by line to `on_lines` handlers
+    Lines { url: &'a str, status: u16 },
+    /// Body is streamed in chunks to `on_stream` handlers
+    Streamed { url: &'a str, status: u16 },
+    /// Only headers were fetched, body was not downloaded
+    BodySkipped { url: &'a str, status: u16 },
+    /// Body was saved to destination
+    Downloaded { url: &'a str, destination: &'a str },
+    /// Work was skipped, e.g. page not modified
+    Noop { url: &'a str },
+    /// Work failed
+    Error {
+        url: &'a str,
+        error: &'a CrablerError,
+    },
+    /// Work was dropped from the queue when the crawl stopped
+    Drained { url: &'a str },
+    /// Sitemap was processed
+    Sitemap { url: &'a str, entries: usize },
+    /// Feed was processed
+    Feed { url: &'a str, items: usize },
+}
+
+impl UrlOutcome<'_> {
+    /// Url the outcome is about
+    pub fn url(&self) -> &str {
+        match self {
+            UrlOutcome::Fetched { url, .. }
+            | UrlOutcome::Soft404 { url }
+            | UrlOutcome::HtmlSkipped { url }
+            | UrlOutcome::Lines { url, .. }
+            | UrlOutcome::Streamed { url, .. }
+            | UrlOutcome::BodySkipped { url, .. }
+            | UrlOutcome::Downloaded { url, .. }
+            | UrlOutcome::Noop { url }
+            | UrlOutcome::Error { url, .. }
+            | UrlOutcome::Drained { url }
+            | UrlOutcome::Sitemap { url, .. }
+            | UrlOutcome::Feed { url, .. } => url,
+        }
+    }
+
+    /// Log the outcome the way crawler does without an outcome logger
+    pub fn log(&self) {
+        match self {
+            UrlOutcome::Fetched { url, .. } => info!("Fetched markup from: {}", url),
+            UrlOutcome::Soft404 { url } => info!("Soft 404: {}", url),
+            UrlOutcome::HtmlSkipped { url } => info!("Skipped html handlers of: {}", url),
+            UrlOutcome::Lines { url, .. } => info!("Streaming lines of: {}", url),
+            UrlOutcome::Streamed { url, .. } => info!("Streaming body of: {}", url),
+            UrlOutcome::BodySkipped { url, .. } => info!("Skipped body of: {}", url),
+            UrlOutcome::Downloaded { url, destination } => {
+                info!("Downloaded: {} -> {}", url, destination)
+            }
+            UrlOutcome::Noop { url } => info!("Noop: {}", url),
+            UrlOutcome::Error { url, error } => error!("Error from {}: {}", url, error),
+            UrlOutcome::Drained { url } => info!("Drained: {}", url),
+            UrlOutcome::Sitemap { url, entries } => {
+                info!("Processed sitemap {} with {} entries", url, entries)
+            }
+            UrlOutcome::Feed { url, items } => {
+                info!("Processed feed {} with {} items", url, items)
+            }
+        }
+    }
+}
+
+/// Progress of a crawl run in steps, returned by `MutableCrabler::run_steps`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CrawlProgress {
+    /// Work outputs processed by this call
+    pub processed: usize,
+    /// Work queued or in-flight
+    pub pending: usize,
+    /// Crawl is done, further steps process nothing
+    pub finished: bool,
+}
+
+/// Robots.txt rules that were applied to a host and urls skipped because of them
+#[derive(Clone, Debug, Default)]
+pub struct RobotsAudit {
+    pub rules: RobotsRules,
+    pub skipped_urls: Vec<String>,
+}
+
+impl CrawlReport {
+    /// Total number of urls skipped because of robots.txt
+    pub fn skipped_by_robots(&self) -> usize {
+        self.robots
+            .values()
+            .map(|audit| audit.skipped_urls.len())
+            .sum()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::warc::warc_date;
+use crate::{CrablerError, Result};
+use async_std::fs::{self, File, OpenOptions};
+use async_std::prelude::*;
+use async_std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Line of the request log, see `Opts::with_request_log`
+#[derive(Debug, Serialize)]
+pub(crate) struct RequestLogEntry<'a> {
+    pub(crate) timestamp: String,
+    pub(crate) url: &'a str,
+    pub(crate) method: &'a str,
+    pub(crate) status: Option<u16>,
+    pub(crate) bytes: u64,
+    pub(crate) duration_ms: Option<u64>,
+    pub(crate) from_cache: bool,
+    pub(crate) error: Option<String>,
+}
+
+impl<'a> RequestLogEntry<'a> {
+    pub(crate) fn new(url: &'a str) -> Self {
+        RequestLogEntry {
+            timestamp: warc_date(SystemTime::now()),
+            url,
+            method: "GET",
+            status: None,
+            bytes: 0,
+            duration_ms: None,
+            from_cache: false,
+            error: None,
+        }
+    }
+}
+
+/// Fields of a request log line needed to replay it, see `Opts::with_replay_from`
+#[derive(Debug, Deserialize)]
+struct LoggedRequest {
+    url: String,
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+/// Urls of requests that got a response, in the order they were logged
+pub(crate) async fn read_logged_urls(path: &str) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).await?;
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str::<LoggedRequest>(line)
+                .map_err(|e| CrablerError::Parse(format!("failed to parse {}: {}", path, e)))
+        })
+        .filter(|request| {
+            request.as_ref().map_or(true, |request| {
+                request.status.is_some() && request.error.is_none()
+            })
+        })
+        .map(|request| request.map(|request| request.url))
+        .collect()
+}
+
+/// Appends a JSON line per request to a file, see `Opts::with_request_log`
+#[derive(Debug)]
+pub(crate) struct RequestLog {
+    path: String,
+    file: Mutex<Option<File>>,
+}
+
+impl RequestLog {
+    /// File is opened for appending once the first entry is written
+    pub(crate) fn new(path: &str) -> Self {
+        RequestLog {
+            path: path.to_string(),
+            file: Mutex::new(None),
+        }
+    }
+
+    pub(crate) async fn write(&self, entry: &RequestLogEntry<'_>) -> Result<()> {
+        let mut file = self.file.lock().await;
+
+        if file.is_none() {
+            let opened = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            *file = Some(opened);
+        }
+
+        let mut line = serde_json::to_vec(entry).map_err(|e| CrablerError::Parse(e.to_string()))?;
+        line.push(b'\n');
+
+        if let Some(file) = file.as_mut() {
+            file.write_all(&line).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush written entries to disk
+    pub(crate) async fn flush(&self) -> Result<()> {
+        if let Some(file) = self.file.lock().await.as_mut() {
+            file.flush().await?;
+        }
+
+        Ok(())
+    }
+}
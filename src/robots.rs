@@ -0,0 +1,217 @@
+use crabquery::Document;
+
+/// User agent token used to pick a group from robots.txt
+pub const ROBOTS_USER_AGENT: &str = "crabler";
+
+/// Rules from robots.txt that apply to crabler
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RobotsRules {
+    pub allow: Vec<String>,
+    pub disallow: Vec<String>,
+}
+
+impl RobotsRules {
+    /// Rules that disallow everything, used when robots.txt could not be fetched
+    pub fn disallow_all() -> Self {
+        RobotsRules {
+            allow: vec![],
+            disallow: vec!["/".to_string()],
+        }
+    }
+
+    /// Parse robots.txt picking the group for given user agent,
+    /// falls back to the `*` group when there is no specific one
+    pub fn parse(text: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_lowercase();
+        let mut specific: Option<RobotsRules> = None;
+        let mut wildcard: Option<RobotsRules> = None;
+
+        let mut agents: Vec<String> = vec![];
+        let mut rules = RobotsRules::default();
+        let mut in_rules = false;
+
+        let mut finish_group = |agents: &[String], rules: &RobotsRules| {
+            for agent in agents {
+                let target = if agent == "*" {
+                    &mut wildcard
+                } else if user_agent.contains(agent.as_str()) {
+                    &mut specific
+                } else {
+                    continue;
+                };
+
+                let group = target.get_or_insert_with(RobotsRules::default);
+                group.allow.extend(rules.allow.iter().cloned());
+                group.disallow.extend(rules.disallow.iter().cloned());
+            }
+        };
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim().to_lowercase();
+            let value = parts.next().unwrap_or("").trim().to_string();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        finish_group(&agents, &rules);
+                        agents.clear();
+                        rules = RobotsRules::default();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_lowercase());
+                }
+                "allow" => {
+                    in_rules = true;
+                    if !value.is_empty() {
+                        rules.allow.push(value);
+                    }
+                }
+                "disallow" => {
+                    in_rules = true;
+                    if !value.is_empty() {
+                        rules.disallow.push(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        finish_group(&agents, &rules);
+
+        specific.or(wildcard).unwrap_or_default()
+    }
+
+    /// Check if given path (with query) may be crawled.
+    /// Longest matching rule wins, allow wins over disallow of the same length
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let longest = |patterns: &[String]| {
+            patterns
+                .iter()
+                .filter(|pattern| pattern_matches(pattern, path))
+                .map(|pattern| pattern.len())
+                .max()
+        };
+
+        match (longest(&self.allow), longest(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(allow), Some(disallow)) => allow >= disallow,
+        }
+    }
+}
+
+/// Match robots.txt path pattern supporting `*` wildcards and `$` end anchor
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &path[first.len()..];
+    let parts = parts.collect::<Vec<_>>();
+
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i == parts.len() - 1;
+
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+/// Directives from `<meta name="robots">` tags and the `X-Robots-Tag` header of a page
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MetaRobots {
+    /// Links on the page should not be followed
+    pub nofollow: bool,
+    /// Page should not be indexed, handlers storing items should skip it
+    pub noindex: bool,
+}
+
+impl MetaRobots {
+    /// Collect directives from robots meta tags and the ones addressed to crabler
+    pub(crate) fn from_document(document: &Document) -> Self {
+        let mut meta_robots = MetaRobots::default();
+
+        for meta in document.select("meta") {
+            let name = meta.attr("name").unwrap_or_default().to_lowercase();
+            if name != "robots" && name != ROBOTS_USER_AGENT {
+                continue;
+            }
+
+            let content = meta.attr("content").unwrap_or_default().to_lowercase();
+            for directive in content.split(',') {
+                meta_robots.apply(directive.trim());
+            }
+        }
+
+        meta_robots
+    }
+
+    /// Collect directives from `X-Robots-Tag` header value, repeated headers are joined with
+    /// commas. Directives after a `user-agent:` prefix apply only to that agent, until the next
+    /// prefix, so only unprefixed ones and the ones addressed to crabler are kept
+    pub(crate) fn from_x_robots_tag(value: &str) -> Self {
+        let mut meta_robots = MetaRobots::default();
+        let mut applies = true;
+
+        for token in value.to_lowercase().split(',') {
+            let mut directive = token.trim();
+            if let Some((name, rest)) = directive.split_once(':') {
+                let name = name.trim();
+                if !VALUED_DIRECTIVES.contains(&name) {
+                    applies = name == ROBOTS_USER_AGENT || name == "*";
+                    directive = rest.trim();
+                }
+            }
+
+            if applies {
+                meta_robots.apply(directive);
+            }
+        }
+
+        meta_robots
+    }
+
+    /// Directives set in either of the two
+    pub(crate) fn union(self, other: MetaRobots) -> Self {
+        MetaRobots {
+            nofollow: self.nofollow || other.nofollow,
+            noindex: self.noindex || other.noindex,
+        }
+    }
+
+    fn apply(&mut self, directive: &str) {
+        match directive {
+            "nofollow" => self.nofollow = true,
+            "noindex" => self.noindex = true,
+            "none" => {
+                self.nofollow = true;
+                self.noindex = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Directives written as `name: value`, not to be mistaken for a user agent prefix
+const VALUED_DIRECTIVES: &[&str] = &[
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
@@ -0,0 +1,91 @@
+use crate::Result;
+use async_std::fs::File;
+use async_std::prelude::*;
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Destination downloaded files are streamed into, see `Opts::with_download_sink`.
+///
+/// One sink is shared by all workers, so chunks of several downloads can arrive interleaved,
+/// they are told apart by destination passed to `Response::download_file`.
+#[async_trait]
+pub trait DownloadSink: Send + Sync {
+    /// Write next chunk of the file being downloaded into destination
+    async fn write_chunk(&self, destination: &str, chunk: &[u8]) -> Result<()>;
+
+    /// Called once the whole file was written, not called when download failed midway
+    async fn finalize(&self, destination: &str) -> Result<()>;
+}
+
+/// Default sink writing downloads into local files, destination is the file path.
+/// File is written next to destination with a `.part` suffix and renamed once complete,
+/// so destination never holds a partial download
+#[derive(Debug, Default)]
+pub struct FileSink {
+    files: Mutex<HashMap<String, File>>,
+    permissions: Option<u32>,
+}
+
+impl FileSink {
+    /// Set Unix permission bits of downloaded files, e.g. `0o644`, before they are moved
+    /// to their destination. Ignored on other platforms
+    pub fn with_permissions(self, mode: u32) -> Self {
+        let mut new = self;
+        new.permissions = Some(mode);
+
+        new
+    }
+
+    async fn set_permissions(&self, path: &str) -> Result<()> {
+        #[cfg(unix)]
+        if let Some(mode) = self.permissions {
+            use std::os::unix::fs::PermissionsExt;
+
+            async_std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await?;
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+
+        Ok(())
+    }
+}
+
+fn part_path(destination: &str) -> String {
+    format!("{}.part", destination)
+}
+
+#[async_trait]
+impl DownloadSink for FileSink {
+    async fn write_chunk(&self, destination: &str, chunk: &[u8]) -> Result<()> {
+        let file = self.files.lock().await.remove(destination);
+        let mut file = match file {
+            Some(file) => file,
+            None => File::create(part_path(destination)).await?,
+        };
+
+        file.write_all(chunk).await?;
+        self.files
+            .lock()
+            .await
+            .insert(destination.to_string(), file);
+
+        Ok(())
+    }
+
+    async fn finalize(&self, destination: &str) -> Result<()> {
+        let file = self.files.lock().await.remove(destination);
+        match file {
+            Some(mut file) => file.flush().await?,
+            // empty download never got a chunk, still create the file
+            None => {
+                File::create(part_path(destination)).await?;
+            }
+        }
+
+        self.set_permissions(&part_path(destination)).await?;
+        async_std::fs::rename(part_path(destination), destination).await?;
+
+        Ok(())
+    }
+}
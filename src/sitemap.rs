@@ -0,0 +1,49 @@
+use crabquery::Document;
+use url::Url;
+
+/// Contents of a sitemap file, see https://www.sitemaps.org/protocol.html
+#[derive(Debug, PartialEq)]
+pub(crate) enum Sitemap {
+    /// Sitemap index pointing to other sitemap files
+    Index(Vec<String>),
+    /// Regular sitemap listing page urls
+    UrlSet(Vec<String>),
+}
+
+/// Parse sitemap or sitemap index xml fetched from given url, returns `None` when input is not
+/// a sitemap. Locations are resolved against the sitemap url, e.g. protocol-relative
+/// `//example.com/page` takes the scheme of the sitemap
+pub(crate) fn parse_sitemap(text: &str, url: &str) -> Option<Sitemap> {
+    let document = Document::from(text);
+    let base = Url::parse(url).ok();
+
+    if !document.select("sitemapindex").is_empty() {
+        Some(Sitemap::Index(locations(
+            &document,
+            "sitemap loc",
+            base.as_ref(),
+        )))
+    } else if !document.select("urlset").is_empty() {
+        Some(Sitemap::UrlSet(locations(
+            &document,
+            "url loc",
+            base.as_ref(),
+        )))
+    } else {
+        None
+    }
+}
+
+fn locations(document: &Document, selector: &str, base: Option<&Url>) -> Vec<String> {
+    document
+        .select(selector)
+        .iter()
+        .filter_map(|loc| loc.text())
+        .map(|loc| loc.trim().to_string())
+        .filter(|loc| !loc.is_empty())
+        .map(|loc| match base.and_then(|base| base.join(&loc).ok()) {
+            Some(resolved) => resolved.to_string(),
+            None => loc,
+        })
+        .collect()
+}
@@ -0,0 +1,69 @@
+/// Content type of server-sent event streams, bodies of this type are passed to `on_sse` handler
+pub const SSE_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Event received from a server-sent event stream, see `on_sse`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SseEvent {
+    /// Type of event from `event:` field, `message` when not given
+    pub event: String,
+    /// Payload from `data:` fields, joined with newlines when there are several
+    pub data: String,
+    /// Last event id seen on the stream so far, from `id:` field
+    pub id: Option<String>,
+    /// Reconnection time in milliseconds from `retry:` field
+    pub retry: Option<u64>,
+}
+
+/// Incremental parser of server-sent event framing, fed one line at a time
+#[derive(Default)]
+pub(crate) struct SseParser {
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseParser {
+    /// Feed next line of the stream, returns complete event once a blank line ends it
+    pub(crate) fn push_line(&mut self, line: &str) -> Option<SseEvent> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if line.is_empty() {
+            let event = self.event.take();
+            let retry = self.retry.take();
+            if self.data.is_empty() {
+                return None;
+            }
+
+            return Some(SseEvent {
+                event: event.unwrap_or_else(|| "message".to_string()),
+                data: self.data.drain(..).collect::<Vec<_>>().join("\n"),
+                id: self.id.clone(),
+                retry,
+            });
+        }
+
+        // lines starting with a colon are comments, e.g. keep-alive pings
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.find(':') {
+            Some(i) => {
+                let value = &line[i + 1..];
+                (&line[..i], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => self.retry = value.parse().ok(),
+            _ => {}
+        }
+
+        None
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{CrablerError, CrablerHandle, Result, VisitedLinks, WorkInput};
+use async_std::fs;
+use async_std::path::Path;
+use async_std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// Snapshot of crawl progress that can be used to resume it later
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CrawlState {
+    pub visited: Vec<String>,
+    pub pending: Vec<WorkInput>,
+}
+
+impl CrawlState {
+    /// Take snapshot of visited links and work that is still queued or in-flight
+    pub(crate) async fn snapshot(
+        visited_links: &RwLock<VisitedLinks>,
+        handle: &CrablerHandle,
+    ) -> Self {
+        let visited = visited_links.read().await.urls();
+        let pending = handle.frontier();
+
+        CrawlState { visited, pending }
+    }
+
+    /// Load state from given path, returns `None` if file does not exist yet
+    pub(crate) async fn load(path: &str) -> Result<Option<Self>> {
+        if !Path::new(path).exists().await {
+            return Ok(None);
+        }
+
+        let content = fs::read(path).await?;
+        let state = serde_json::from_slice(&content)
+            .map_err(|e| CrablerError::StateFile(format!("failed to parse {}: {}", path, e)))?;
+
+        Ok(Some(state))
+    }
+
+    /// Save state to given path, file is replaced atomically so crash mid-write keeps old state
+    pub(crate) async fn save(&self, path: &str) -> Result<()> {
+        let content = serde_json::to_vec(self)
+            .map_err(|e| CrablerError::StateFile(format!("failed to serialize: {}", e)))?;
+        let tmp_path = format!("{}.tmp", path);
+
+        fs::write(&tmp_path, content).await?;
+        fs::rename(&tmp_path, path).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use async_std::channel::Receiver;
+use futures::io::AsyncBufRead;
+use futures::stream::{StreamExt, TryStreamExt};
+use std::io;
+
+/// Body of a page handed to `on_stream` handler while it is still downloading.
+/// Dropping it early does not stop the download, the whole page is still passed to other handlers
+pub struct BodyStream {
+    pub(crate) chunks: Receiver<Vec<u8>>,
+}
+
+impl BodyStream {
+    /// Next chunk of the raw body as it arrives, `None` once the whole body was received
+    pub async fn next_chunk(&mut self) -> Option<Vec<u8>> {
+        self.chunks.recv().await.ok()
+    }
+
+    /// Read the raw body through `AsyncBufRead`, e.g. with `lines()` or `read_until()`
+    pub fn into_reader(self) -> impl AsyncBufRead + Unpin {
+        self.chunks.map(Ok::<_, io::Error>).into_async_read()
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{CrawlReport, Hook};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callback deciding from the report so far whether the crawl should stop
+pub type TerminationCheck = dyn Fn(&CrawlReport) -> bool + Send + Sync;
+
+/// When the crawl stops, see `Opts::with_termination`.
+/// Crawl always ends once it runs out of work, other conditions stop it earlier
+/// like `CrablerHandle::stop_after_current`, so work already in-flight still finishes
+#[derive(Clone, Debug, Default)]
+pub enum TerminationCondition {
+    /// Run until there is no work left, the default
+    #[default]
+    QueueEmpty,
+    /// Stop once this many pages and files were fetched, see `CrawlReport::pages`
+    MaxPages(usize),
+    /// Stop once the crawl ran for this long
+    MaxTime(Duration),
+    /// Stop once the flag is set, e.g. from a Ctrl-C handler or another task
+    Signal(Arc<AtomicBool>),
+    /// Stop once callback returns true, see `TerminationCondition::custom`
+    Custom(Hook<TerminationCheck>),
+    /// Stop once any of the conditions is met
+    Any(Vec<TerminationCondition>),
+}
+
+impl TerminationCondition {
+    /// Stop once given callback returns true for the report so far
+    pub fn custom<F>(input: F) -> Self
+    where
+        F: Fn(&CrawlReport) -> bool + Send + Sync + 'static,
+    {
+        TerminationCondition::Custom(Hook(Arc::new(input)))
+    }
+
+    /// Check the condition against the report after the crawl ran for given time
+    pub fn is_met(&self, report: &CrawlReport, elapsed: Duration) -> bool {
+        match self {
+            TerminationCondition::QueueEmpty => false,
+            TerminationCondition::MaxPages(max_pages) => report.pages >= *max_pages,
+            TerminationCondition::MaxTime(max_time) => elapsed >= *max_time,
+            TerminationCondition::Signal(signal) => signal.load(Ordering::SeqCst),
+            TerminationCondition::Custom(check) => (check.0)(report),
+            TerminationCondition::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.is_met(report, elapsed)),
+        }
+    }
+}
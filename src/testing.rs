@@ -0,0 +1,221 @@
+//! Helpers for testing scrapers, enabled with the `testing` feature
+
+use crate::{CrablerError, CrablerHandle, CrawlReport, Document, Headers, Response, Result};
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Random failures and delays injected into workers, see `Opts::with_chaos`
+#[derive(Clone, Debug)]
+pub struct ChaosConfig {
+    /// Probability in range `0.0..=1.0` of a request failing before it is sent
+    pub fail_rate: f64,
+    /// Upper bound of random delay added to every request
+    pub extra_latency: Duration,
+}
+
+/// Apply chaos to a single request, sleeping and failing at random
+pub(crate) async fn inject_chaos(
+    config: &ChaosConfig,
+    rng: &Mutex<StdRng>,
+    url: &str,
+) -> Result<()> {
+    let (latency, fail) = {
+        let mut rng = rng.lock().unwrap();
+        let latency = rng.gen_range(0.0..=1.0) * config.extra_latency.as_secs_f64();
+        let fail = rng.gen_bool(config.fail_rate.clamp(0.0, 1.0));
+
+        (Duration::from_secs_f64(latency), fail)
+    };
+
+    debug!("Chaos: delaying {} by {:?}", url, latency);
+    async_std::task::sleep(latency).await;
+
+    if fail {
+        warn!("Chaos: failing {}", url);
+        return Err(CrablerError::Network("chaos: injected failure".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Assertions for scraper tests, chain them on a report from `CrablerHandle::report`:
+/// `report.assert_no_errors().assert_visited("http://localhost/")`
+impl CrawlReport {
+    /// Panic unless given url was fetched
+    #[track_caller]
+    pub fn assert_visited(&self, url: &str) -> &Self {
+        assert!(
+            self.visited.iter().any(|visited| visited == url),
+            "expected {} to be visited, visited: {:?}",
+            url,
+            self.visited
+        );
+        self
+    }
+
+    /// Panic if given url was fetched
+    #[track_caller]
+    pub fn assert_not_visited(&self, url: &str) -> &Self {
+        assert!(
+            !self.visited.iter().any(|visited| visited == url),
+            "expected {} not to be visited",
+            url
+        );
+        self
+    }
+
+    /// Panic if any request failed without a response
+    #[track_caller]
+    pub fn assert_no_errors(&self) -> &Self {
+        assert!(
+            self.failed.is_empty(),
+            "expected no errors, failed: {:?}",
+            self.failed
+        );
+        self
+    }
+
+    /// Panic unless given `on_html` selector matched at least one element during the crawl
+    #[track_caller]
+    pub fn assert_selector_matched(&self, selector: &str) -> &Self {
+        let matches = self.selector_matches.get(selector).copied().unwrap_or(0);
+        assert!(
+            matches > 0,
+            "expected selector {:?} to match, matches by selector: {:?}",
+            selector,
+            self.selector_matches
+        );
+        self
+    }
+}
+
+/// Builder of a `Response` for calling handlers directly in unit tests, without a crawl:
+///
+/// ```ignore
+/// let response = Response::test_builder()
+///     .with_url("http://localhost/")
+///     .with_body(r#"<a href="/next">next</a>"#)
+///     .build();
+/// let a = response.select_one("a").unwrap();
+/// scraper.link_handler(response.clone(), a).await?;
+/// assert_eq!(response.handle().take_queued_urls(), vec!["http://localhost/next"]);
+/// ```
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    url: String,
+    status: u16,
+    headers: Headers,
+    body: Option<String>,
+    context: Option<String>,
+    handle: CrablerHandle,
+}
+
+impl Response {
+    /// Start building a response for a unit test of a handler, defaults to
+    /// an empty 200 response of `http://localhost/`
+    pub fn test_builder() -> ResponseBuilder {
+        ResponseBuilder {
+            url: "http://localhost/".to_string(),
+            status: 200,
+            headers: Headers::new(),
+            body: None,
+            context: None,
+            handle: CrablerHandle::new(),
+        }
+    }
+}
+
+impl ResponseBuilder {
+    pub fn with_url(self, input: &str) -> Self {
+        let mut new = self;
+        new.url = input.to_string();
+
+        new
+    }
+
+    pub fn with_status(self, input: u16) -> Self {
+        let mut new = self;
+        new.status = input;
+
+        new
+    }
+
+    /// Header names are lowercased, like headers of fetched responses
+    pub fn with_header(self, name: &str, value: &str) -> Self {
+        let mut new = self;
+        new.headers.insert(name.to_lowercase(), value.to_string());
+
+        new
+    }
+
+    /// Html of the page, parsed for `Response::select_all` and `<base href>` of the page
+    pub fn with_body(self, input: &str) -> Self {
+        let mut new = self;
+        new.body = Some(input.to_string());
+
+        new
+    }
+
+    pub fn with_context(self, input: &str) -> Self {
+        let mut new = self;
+        new.context = Some(input.to_string());
+
+        new
+    }
+
+    /// Handle that built response queues work into, a fresh one by default
+    pub fn with_handle(self, input: CrablerHandle) -> Self {
+        let mut new = self;
+        new.handle = input;
+
+        new
+    }
+
+    pub fn build(self) -> Response {
+        let mut response = Response::new(
+            self.status,
+            self.url,
+            self.headers,
+            None,
+            self.context,
+            self.handle,
+        );
+
+        if let Some(body) = self.body {
+            let document = Document::from(body.as_str());
+            response.base_url = document
+                .select("base[href]")
+                .first()
+                .and_then(|base| base.attr("href"))
+                .map(|href| response.resolve_url(&href));
+            // same as documents of crawled pages, response never leaves the task it is used on
+            #[allow(clippy::arc_with_non_send_sync)]
+            let document = Arc::new(document);
+            response.document = Some(document);
+        }
+
+        response
+    }
+}
+
+impl CrablerHandle {
+    /// Urls of work queued through this handle and not picked up by a worker yet, in the order
+    /// it was queued, e.g. by `Response::navigate` of a response from `Response::test_builder`.
+    /// Returned work is removed from the queue
+    pub fn take_queued_urls(&self) -> Vec<String> {
+        let mut urls = vec![];
+        while let Ok(work) = self.workinput_ch.rx.try_recv() {
+            self.counter.fetch_sub(1, Ordering::SeqCst);
+            if let Some(url) = work.url() {
+                self.finish_work(url);
+                urls.push(url.to_string());
+            }
+        }
+
+        urls
+    }
+}
@@ -0,0 +1,125 @@
+use crabquery::Element;
+
+/// How whitespace of extracted text is handled, see `TextOptions`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Replace every run of whitespace with a single space and trim the ends,
+    /// text of adjacent elements like `<p>a</p><p>b</p>` is separated by a space
+    Collapse,
+    /// Keep whitespace exactly as it is in the document
+    Preserve,
+    /// Only remove whitespace at the start and the end
+    Trim,
+}
+
+/// Options of `ElementExt::normalized_text`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextOptions {
+    /// Defaults to `Whitespace::Collapse`
+    pub whitespace: Whitespace,
+    /// Decode entities left in the text after parsing, e.g. double-escaped `&amp;amp;` or
+    /// entities inside of `<script>`, parser already decodes regular ones. Off by default
+    pub decode_entities: bool,
+    /// Include text of `<script>`, `<style>`, `<noscript>` and `<template>` elements. Off by default
+    pub include_scripts: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            whitespace: Whitespace::Collapse,
+            decode_entities: false,
+            include_scripts: false,
+        }
+    }
+}
+
+const SCRIPT_TAGS: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Text extraction helpers for `Element`
+pub trait ElementExt {
+    /// Text of the element and all of its descendants, handled according to given options.
+    /// crabquery does not expose text nodes in document order, so text directly in the element
+    /// comes before text of its child elements, e.g. `<p>a <b>b</b> c</p>` gives `a c b`
+    fn normalized_text(&self, options: &TextOptions) -> String;
+}
+
+impl ElementExt for Element {
+    fn normalized_text(&self, options: &TextOptions) -> String {
+        let mut text = String::new();
+        collect_text(self, options, &mut text);
+
+        if options.decode_entities {
+            text = decode_entities(&text);
+        }
+
+        match options.whitespace {
+            Whitespace::Collapse => text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Whitespace::Preserve => text,
+            Whitespace::Trim => text.trim().to_string(),
+        }
+    }
+}
+
+fn collect_text(element: &Element, options: &TextOptions, out: &mut String) {
+    let tag = element.tag().unwrap_or_default();
+    if !options.include_scripts && SCRIPT_TAGS.contains(&tag.as_str()) {
+        return;
+    }
+
+    out.push_str(&element.text().unwrap_or_default());
+    for child in element.children() {
+        if options.whitespace == Whitespace::Collapse {
+            out.push(' ');
+        }
+        collect_text(&child, options, out);
+    }
+}
+
+/// Decode numeric entities and the most common named ones, unknown entities are kept as is
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let decoded = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix('#') {
+                        Some(hex) if hex.starts_with('x') || hex.starts_with('X') => {
+                            u32::from_str_radix(&hex[1..], 16).ok()?
+                        }
+                        Some(decimal) => decimal.parse().ok()?,
+                        None => return None,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+
+            Some((decoded, end))
+        });
+
+        match decoded {
+            Some((decoded, end)) => {
+                out.push(decoded);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
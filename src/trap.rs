@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use url::Url;
+
+/// Heuristics for spotting crawler traps like infinite calendars or pagination,
+/// see `Opts::with_trap_detection`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrapDetection {
+    /// Skip urls whose path repeats any single segment more than this many times,
+    /// e.g. `/a/b/a/b/a/b/a/b`
+    pub max_segment_repeats: usize,
+    /// Skip urls once this many urls with the same host, path and query parameter
+    /// names were seen, e.g. `/calendar?month=1`, `/calendar?month=2`, ...
+    pub max_query_variants: usize,
+}
+
+impl Default for TrapDetection {
+    fn default() -> Self {
+        TrapDetection {
+            max_segment_repeats: 3,
+            max_query_variants: 100,
+        }
+    }
+}
+
+/// Highest number of times a single non-empty path segment occurs in url
+pub(crate) fn segment_repeats(url: &Url) -> usize {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for segment in url.path().split('/').filter(|s| !s.is_empty()) {
+        *counts.entry(segment).or_insert(0) += 1;
+    }
+
+    counts.values().copied().max().unwrap_or(0)
+}
+
+/// Host, path and sorted query parameter names of url, `None` for urls without query
+pub(crate) fn query_pattern(url: &Url) -> Option<String> {
+    url.query()?;
+
+    let mut names: Vec<String> = url
+        .query_pairs()
+        .map(|(name, _)| name.into_owned())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    Some(format!(
+        "{}{}?{}",
+        url.host_str().unwrap_or(""),
+        url.path(),
+        names.join("&")
+    ))
+}
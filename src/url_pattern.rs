@@ -0,0 +1,57 @@
+use crate::{CrablerError, Result};
+use regex::Regex;
+use url::Url;
+
+/// Url pattern scoping `on_html_for` handlers to some pages
+#[derive(Debug, Clone)]
+pub enum UrlPattern {
+    /// Glob where `*` matches anything but `/`, `**` matches anything and `?` matches
+    /// a single character other than `/`. Globs starting with `/` are matched against
+    /// url path, others against the whole url
+    Glob(String),
+    /// Regular expression searched for in the whole url, anchor it with `^` and `$` as needed
+    Regex(Regex),
+}
+
+impl UrlPattern {
+    pub fn glob(pattern: &str) -> Self {
+        UrlPattern::Glob(pattern.to_string())
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(UrlPattern::Regex)
+            .map_err(|e| CrablerError::Parse(e.to_string()))
+    }
+
+    /// Check if given url matches the pattern
+    pub fn matches(&self, url: &str) -> bool {
+        match self {
+            UrlPattern::Glob(glob) if glob.starts_with('/') => match Url::parse(url) {
+                Ok(url) => glob_matches(glob.as_bytes(), url.path().as_bytes()),
+                Err(_) => false,
+            },
+            UrlPattern::Glob(glob) => glob_matches(glob.as_bytes(), url.as_bytes()),
+            UrlPattern::Regex(regex) => regex.is_match(url),
+        }
+    }
+}
+
+fn glob_matches(glob: &[u8], text: &[u8]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_matches(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|c| *c == b'/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob_matches(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => match text {
+            [c, text @ ..] if *c != b'/' => glob_matches(rest, text),
+            _ => false,
+        },
+        [g, rest @ ..] => match text {
+            [c, text @ ..] if c == g => glob_matches(rest, text),
+            _ => false,
+        },
+    }
+}
@@ -0,0 +1,216 @@
+use crate::Hook;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Storage of visited urls, see `Opts::with_visited_store`. Crawler serializes access to the
+/// store, so check and insert of one url never race, stores only need interior mutability
+/// to be shared by all workers. Implement it to keep the set in e.g. RocksDB or Redis
+/// when it does not fit in memory
+pub trait VisitedStore: Send + Sync {
+    /// Check if url was visited
+    fn contains(&self, url: &str) -> bool;
+
+    /// Record visit of url
+    fn insert(&self, url: String);
+
+    /// Urls saved into the state file by `Opts::with_state_file`, stores that can not list
+    /// their urls return none and the resumed crawl visits them again
+    fn urls(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Default store keeping visited urls with time of the last visit in memory.
+/// With `revisit_after` set, urls become eligible for another visit once it passes
+#[derive(Debug)]
+pub struct MemoryVisitedStore {
+    visits: Mutex<MemoryVisits>,
+    revisit_after: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct MemoryVisits {
+    visited_at: HashMap<String, Instant>,
+    prune_at: usize,
+}
+
+const MIN_PRUNE_AT: usize = 1024;
+
+impl MemoryVisitedStore {
+    pub(crate) fn new(revisit_after: Option<Duration>) -> Self {
+        MemoryVisitedStore {
+            visits: Mutex::new(MemoryVisits {
+                visited_at: HashMap::new(),
+                prune_at: MIN_PRUNE_AT,
+            }),
+            revisit_after,
+        }
+    }
+}
+
+impl Default for MemoryVisitedStore {
+    fn default() -> Self {
+        MemoryVisitedStore::new(None)
+    }
+}
+
+impl VisitedStore for MemoryVisitedStore {
+    fn contains(&self, url: &str) -> bool {
+        match self.visits.lock().unwrap().visited_at.get(url) {
+            Some(visited_at) => !expired_at(self.revisit_after, *visited_at, Instant::now()),
+            None => false,
+        }
+    }
+
+    fn insert(&self, url: String) {
+        let mut visits = self.visits.lock().unwrap();
+        visits.visited_at.insert(url, Instant::now());
+
+        // drop expired visits once in a while so monitoring crawls do not grow forever
+        if self.revisit_after.is_some() && visits.visited_at.len() >= visits.prune_at {
+            let now = Instant::now();
+            let revisit_after = self.revisit_after;
+            visits
+                .visited_at
+                .retain(|_, visited_at| !expired_at(revisit_after, *visited_at, now));
+            visits.prune_at = MIN_PRUNE_AT.max(visits.visited_at.len() * 2);
+        }
+    }
+
+    fn urls(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.visits
+            .lock()
+            .unwrap()
+            .visited_at
+            .iter()
+            .filter(|(_, visited_at)| !expired_at(self.revisit_after, **visited_at, now))
+            .map(|(url, _)| url.clone())
+            .collect()
+    }
+}
+
+/// Store remembering visited urls in a fixed size bloom filter, memory use does not grow
+/// with the crawl. A false positive makes the crawler skip an url that was not visited yet,
+/// they get more likely once more than `expected_items` urls were inserted.
+/// Urls can not be listed, so the state file does not include them
+#[derive(Debug)]
+pub struct BloomVisitedStore {
+    bits: Vec<AtomicU64>,
+    bit_count: u64,
+    hash_count: u32,
+}
+
+impl BloomVisitedStore {
+    /// Size the filter for given number of urls at given false positive rate, e.g. `0.01`
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let bit_count = (-items * rate.ln() / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hash_count = ((bit_count as f64 / items) * ln2).round().max(1.0) as u32;
+
+        BloomVisitedStore {
+            bits: (0..bit_count.div_ceil(64))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            bit_count,
+            hash_count,
+        }
+    }
+
+    /// Bit positions of url, from two hashes combined as in Kirsch-Mitzenmacher
+    fn positions(&self, url: &str) -> impl Iterator<Item = u64> {
+        let first = hash(url, 0);
+        let second = hash(url, 1) | 1;
+        let bit_count = self.bit_count;
+
+        (0..self.hash_count as u64)
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % bit_count)
+    }
+}
+
+impl VisitedStore for BloomVisitedStore {
+    fn contains(&self, url: &str) -> bool {
+        self.positions(url).all(|position| {
+            let word = self.bits[(position / 64) as usize].load(Ordering::Relaxed);
+            word & (1 << (position % 64)) != 0
+        })
+    }
+
+    fn insert(&self, url: String) {
+        for position in self.positions(&url) {
+            self.bits[(position / 64) as usize].fetch_or(1 << (position % 64), Ordering::Relaxed);
+        }
+    }
+}
+
+fn hash(url: &str, seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    url.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Links visited during the crawl, kept in the configured `VisitedStore`
+#[derive(Debug)]
+pub(crate) struct VisitedLinks {
+    store: Hook<dyn VisitedStore>,
+    /// Urls forgotten by `remove`, stores like bloom filter can not delete them themselves
+    forgotten: HashSet<String>,
+}
+
+impl VisitedLinks {
+    pub(crate) fn new(store: Hook<dyn VisitedStore>) -> Self {
+        VisitedLinks {
+            store,
+            forgotten: HashSet::new(),
+        }
+    }
+
+    /// Check if url was visited and is not due for a revisit yet
+    pub(crate) fn contains(&self, url: &str) -> bool {
+        !self.forgotten.contains(url) && self.store.0.contains(url)
+    }
+
+    /// Record visit of url happening now
+    pub(crate) fn insert(&mut self, url: String) {
+        self.forgotten.remove(&url);
+        self.store.0.insert(url);
+    }
+
+    /// Forget visit of url, so it can be visited again
+    pub(crate) fn remove(&mut self, url: &str) {
+        self.forgotten.insert(url.to_string());
+    }
+
+    /// Urls that count as visited right now
+    pub(crate) fn urls(&self) -> Vec<String> {
+        self.store
+            .0
+            .urls()
+            .into_iter()
+            .filter(|url| !self.forgotten.contains(url))
+            .collect()
+    }
+}
+
+impl Extend<String> for VisitedLinks {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, urls: I) {
+        for url in urls {
+            self.insert(url);
+        }
+    }
+}
+
+fn expired_at(revisit_after: Option<Duration>, visited_at: Instant, now: Instant) -> bool {
+    match revisit_after {
+        Some(revisit_after) => now.duration_since(visited_at) >= revisit_after,
+        None => false,
+    }
+}
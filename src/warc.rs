@@ -0,0 +1,169 @@
+use crate::{Headers, Result};
+use async_std::fs::File;
+use async_std::prelude::*;
+use async_std::sync::Mutex;
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+use surf::StatusCode;
+
+/// Writes fetched pages into a WARC 1.1 file, see `Opts::with_warc_output`
+#[derive(Debug)]
+pub(crate) struct WarcWriter {
+    path: String,
+    file: Mutex<Option<File>>,
+}
+
+impl WarcWriter {
+    /// File is created once the first record is written, starting with a `warcinfo` record
+    pub(crate) fn new(path: &str) -> Self {
+        WarcWriter {
+            path: path.to_string(),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Append `response` record of a fetched page
+    pub(crate) async fn write_response(
+        &self,
+        url: &str,
+        status: u16,
+        headers: &Headers,
+        body: &str,
+    ) -> Result<()> {
+        let reason = StatusCode::try_from(status)
+            .map(|status| status.canonical_reason())
+            .unwrap_or_default();
+        let mut block = format!("HTTP/1.1 {} {}\r\n", status, reason);
+
+        // body is stored decoded, so framing headers of the original transfer no longer apply
+        let mut names = headers.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            if !["content-length", "content-encoding", "transfer-encoding"].contains(&name.as_str())
+            {
+                block.push_str(&format!("{}: {}\r\n", name, headers[name]));
+            }
+        }
+        block.push_str(&format!("content-length: {}\r\n\r\n", body.len()));
+        block.push_str(body);
+
+        let fields = [
+            ("WARC-Type", "response".to_string()),
+            ("WARC-Record-ID", record_id()),
+            ("WARC-Date", warc_date(SystemTime::now())),
+            ("WARC-Target-URI", url.to_string()),
+            (
+                "Content-Type",
+                "application/http;msgtype=response".to_string(),
+            ),
+        ];
+
+        self.write_record(&fields, block.as_bytes()).await
+    }
+
+    /// Flush written records to disk
+    pub(crate) async fn flush(&self) -> Result<()> {
+        if let Some(file) = self.file.lock().await.as_mut() {
+            file.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_record(&self, fields: &[(&str, String)], block: &[u8]) -> Result<()> {
+        let mut file = self.file.lock().await;
+
+        if file.is_none() {
+            let mut created = File::create(&self.path).await?;
+            let info = format!(
+                "software: crabler/{}\r\nformat: WARC File Format 1.1\r\n",
+                env!("CARGO_PKG_VERSION")
+            );
+            let info_fields = [
+                ("WARC-Type", "warcinfo".to_string()),
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Date", warc_date(SystemTime::now())),
+                ("Content-Type", "application/warc-fields".to_string()),
+            ];
+
+            created
+                .write_all(&record(&info_fields, info.as_bytes()))
+                .await?;
+            *file = Some(created);
+        }
+
+        if let Some(file) = file.as_mut() {
+            file.write_all(&record(fields, block)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn record(fields: &[(&str, String)], block: &[u8]) -> Vec<u8> {
+    let mut record = b"WARC/1.1\r\n".to_vec();
+    for (name, value) in fields {
+        record.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+    record.extend_from_slice(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+    record.extend_from_slice(block);
+    record.extend_from_slice(b"\r\n\r\n");
+
+    record
+}
+
+/// Random version 4 uuid urn
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", uuid_v4())
+}
+
+/// Random version 4 uuid like `0b5e3f2a-4c1d-4e8f-9a7b-6c5d4e3f2a1b`
+pub(crate) fn uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// UTC timestamp like `2021-01-31T12:00:00Z`
+pub(crate) fn warc_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // days since epoch to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
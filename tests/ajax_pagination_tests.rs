@@ -0,0 +1,82 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    items: Vec<String>,
+}
+
+impl Scraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        if let Some(json) = response.json() {
+            for item in json["items"].as_array().into_iter().flatten() {
+                self.items
+                    .push(item.as_str().unwrap_or_default().to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn json(body: &str) -> common::Page {
+    common::Page::with_content_type("application/json", body.as_bytes())
+}
+
+#[async_std::test]
+async fn test_ajax_pagination() {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            ("/api?page=1".to_string(), json(r#"{"items": ["a", "b"]}"#)),
+            ("/api?page=2".to_string(), json(r#"{"items": ["c"]}"#)),
+            ("/api?page=3".to_string(), json(r#"{"items": []}"#)),
+            ("/api?page=4".to_string(), json(r#"{"items": ["never"]}"#)),
+        ]
+    })
+    .await;
+
+    let mut scraper = Scraper { items: vec![] };
+    scraper
+        .run(
+            Opts::new().with_ajax_pagination(&format!("{}/api?page={{page}}", base), |page| {
+                page["items"]
+                    .as_array()
+                    .is_none_or(|items| items.is_empty())
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(scraper.items, vec!["a", "b", "c"]);
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec!["/api?page=1", "/api?page=2", "/api?page=3"]
+    );
+}
+
+#[async_std::test]
+async fn test_ajax_pagination_stops_on_error() {
+    let base = common::serve(vec![
+        ("/api/1", json(r#"{"items": ["a"]}"#)),
+        ("/api/2", json(r#"{"items": ["b"]}"#).status(500)),
+        ("/api/3", json(r#"{"items": ["c"]}"#)),
+    ])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper { items: vec![] };
+    scraper
+        .run(
+            Opts::new()
+                .with_ajax_pagination(&format!("{}/api/{{page}}", base), |_| false)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(handle.report().pages, 2);
+}
@@ -0,0 +1,136 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("pre", echo_handler)]
+struct Scraper {
+    statuses: Arc<RwLock<Vec<(String, u16)>>>,
+    requests: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let path = Url::parse(&response.url).unwrap().path().to_string();
+        self.statuses.write().unwrap().push((path, response.status));
+
+        Ok(())
+    }
+
+    async fn echo_handler(&self, _response: Response, pre: Element) -> Result<()> {
+        let request = pre.text().unwrap_or_default();
+        let authorization = request
+            .lines()
+            .find(|line| line.to_lowercase().starts_with("authorization:"))
+            .map(|line| line["authorization:".len()..].trim().to_string())
+            .unwrap_or_default();
+        self.requests.write().unwrap().push(authorization);
+
+        Ok(())
+    }
+}
+
+async fn crawl(
+    challenge: &str,
+    paths: &[&str],
+    opts: Opts,
+) -> (Vec<(String, u16)>, Vec<String>, Vec<String>) {
+    let (base, log) = common::serve_logged(|_| {
+        paths
+            .iter()
+            .map(|path| {
+                (
+                    path.to_string(),
+                    common::Page::html("secret").challenge(challenge),
+                )
+            })
+            .collect()
+    })
+    .await;
+
+    let statuses = Arc::new(RwLock::new(vec![]));
+    let requests = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        statuses: statuses.clone(),
+        requests: requests.clone(),
+    };
+
+    let urls = paths
+        .iter()
+        .map(|path| format!("{}{}", base, path))
+        .collect::<Vec<_>>();
+    scraper
+        .run(
+            opts.with_urls(urls.iter().map(String::as_str).collect())
+                .with_threads(1),
+        )
+        .await
+        .unwrap();
+
+    let statuses = statuses.read().unwrap().clone();
+    let requests = requests.read().unwrap().clone();
+    let log = log.lock().unwrap().clone();
+    (statuses, requests, log)
+}
+
+#[async_std::test]
+async fn test_challenge_without_credentials() {
+    let (statuses, requests, log) =
+        crawl(r#"Basic realm="test""#, &["/private"], Opts::new()).await;
+
+    assert_eq!(statuses, vec![("/private".to_string(), 401)]);
+    assert!(requests.is_empty());
+    assert_eq!(log, vec!["/private"]);
+}
+
+#[async_std::test]
+async fn test_basic_auth() {
+    let (statuses, requests, log) = crawl(
+        r#"Basic realm="test""#,
+        &["/private"],
+        Opts::new().with_credentials("127.0.0.1", "user", "pass"),
+    )
+    .await;
+
+    assert_eq!(statuses, vec![("/private".to_string(), 200)]);
+    assert_eq!(requests, vec!["Basic dXNlcjpwYXNz"]);
+    assert_eq!(log, vec!["/private", "/private"]);
+}
+
+#[async_std::test]
+async fn test_digest_auth() {
+    let (statuses, requests, log) = crawl(
+        r#"Basic realm="test", Digest realm="test@host", qop="auth,auth-int", nonce="abc123", opaque="xyz""#,
+        &["/a", "/b?page=2"],
+        Opts::new().with_credentials("127.0.0.1", "user", "pass"),
+    )
+    .await;
+
+    assert_eq!(
+        statuses,
+        vec![("/a".to_string(), 200), ("/b".to_string(), 200)]
+    );
+    // challenge is remembered, so second page authenticates up front with the same nonce
+    assert_eq!(log, vec!["/a", "/a", "/b?page=2"]);
+
+    assert!(requests[0].starts_with("Digest "), "{:?}", requests);
+    for expected in &[
+        r#"username="user""#,
+        r#"realm="test@host""#,
+        r#"nonce="abc123""#,
+        r#"uri="/a""#,
+        r#"opaque="xyz""#,
+        "algorithm=MD5",
+        "qop=auth",
+        "nc=00000001",
+    ] {
+        assert!(requests[0].contains(expected), "{}", requests[0]);
+    }
+    assert!(requests[1].contains(r#"uri="/b?page=2""#));
+    assert!(requests[1].contains("nc=00000002"));
+}
@@ -0,0 +1,110 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_relative_links_resolve_against_base_href() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="docs/index">d</a><a href="blog/index">b</a>"#),
+        ),
+        (
+            "/docs/index",
+            common::Page::html(r#"<a href="page">p</a>"#),
+        ),
+        (
+            "/blog/index",
+            common::Page::html(
+                r#"<html><head><base href="/archive/"></head><body><a href="post">p</a></body></html>"#,
+            ),
+        ),
+        ("/docs/page", common::Page::html("docs")),
+        ("/archive/post", common::Page::html("post")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        vec![
+            format!("{}/", base),
+            format!("{}/archive/post", base),
+            format!("{}/blog/index", base),
+            format!("{}/docs/index", base),
+            format!("{}/docs/page", base),
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_protocol_relative_links_take_scheme_of_page() {
+    let base = common::serve_with(|base| {
+        let host = base.trim_start_matches("http:");
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(&format!(r#"<a href="{}/cdn/x">x</a>"#, host)),
+            ),
+            ("/cdn/x".to_string(), common::Page::html("x")),
+        ]
+    })
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        vec![format!("{}/", base), format!("{}/cdn/x", base)]
+    );
+}
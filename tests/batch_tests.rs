@@ -0,0 +1,75 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("li", item_handler)]
+#[on_html("a[href]", link_handler)]
+#[on_batch(2, batch_handler)]
+struct Scraper {
+    batches: Arc<RwLock<Vec<Vec<JsonValue>>>>,
+}
+
+impl Scraper {
+    async fn item_handler(&self, response: Response, li: Element) -> Result<()> {
+        if let Some(text) = li.text() {
+            response.emit(&text)?;
+        }
+
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn batch_handler(&self, items: Vec<JsonValue>) -> Result<()> {
+        self.batches.write().unwrap().push(items);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_batch() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<ul><li>a</li><li>b</li><li>c</li></ul><a href="/more">m</a>"#),
+        ),
+        ("/more", common::Page::html("<ul><li>d</li><li>e</li></ul>")),
+    ])
+    .await;
+
+    let batches = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        batches: batches.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let batches = batches.read().unwrap();
+    assert_eq!(
+        batches.iter().map(|batch| batch.len()).collect::<Vec<_>>(),
+        vec![2, 2, 1]
+    );
+
+    let mut items = batches
+        .iter()
+        .flatten()
+        .map(|item| item.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    items.sort();
+    assert_eq!(items, vec!["a", "b", "c", "d", "e"]);
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "blocking")]
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links.write().unwrap().push(response.url);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_blocking() {
+    let base = async_std::task::block_on(common::serve(vec![("/", common::Page::html("hello"))]));
+
+    let visited_links = Arc::new(RwLock::new(vec![]));
+    let scraper = Scraper {
+        visited_links: visited_links.clone(),
+    };
+
+    let report = scraper
+        .run_blocking(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .unwrap();
+
+    assert_eq!(report.total_bytes, 5);
+    assert_eq!(*visited_links.read().unwrap(), vec![format!("{}/", base)]);
+}
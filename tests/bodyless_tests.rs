@@ -0,0 +1,74 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("body", body_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+    bodies: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn body_handler(&self, response: Response, _body: Element) -> Result<()> {
+        self.bodies.write().unwrap().push(response.url);
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_bodyless_response_skips_html_handlers() {
+    let base = common::serve(vec![
+        ("/", common::Page::html(r#"<a href="/empty">e</a>"#)),
+        ("/empty", common::Page::html("").status(204)),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let bodies = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+        bodies: bodies.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/empty", base), 204)
+        ]
+    );
+    assert_eq!(*bodies.read().unwrap(), vec![format!("{}/", base)]);
+}
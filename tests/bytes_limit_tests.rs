@@ -0,0 +1,74 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_max_total_bytes() {
+    let links = (0..20)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a>", i, i))
+        .collect::<String>();
+    let leaf = "x".repeat(1000);
+    let paths = (0..20).map(|i| format!("/page/{}", i)).collect::<Vec<_>>();
+    let mut pages = vec![("/", common::Page::html(&links))];
+    for path in &paths {
+        pages.push((path.as_str(), common::Page::html(&leaf)));
+    }
+    let base = common::serve(pages).await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_max_total_bytes(2500)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let fetched = fetched.read().unwrap().len();
+    let total_bytes = handle.report().total_bytes;
+    assert!(handle.is_stopping());
+    assert!(fetched < 21);
+    assert!(total_bytes >= 2500);
+    assert_eq!(
+        total_bytes,
+        (links.len() + (fetched - 1) * leaf.len()) as u64
+    );
+}
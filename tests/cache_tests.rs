@@ -0,0 +1,92 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (Vec<(String, u16)>, CrawlReport) {
+    let responses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper.run(opts.with_handle(handle.clone())).await.unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    (responses, handle.report())
+}
+
+#[async_std::test]
+async fn test_offline_serves_only_cached_pages() {
+    let base = common::serve(vec![
+        ("/", common::Page::html(r#"<a href="/page">p</a>"#)),
+        ("/page", common::Page::html("page")),
+        ("/other", common::Page::html("other")),
+    ])
+    .await;
+    let cache_dir = std::env::temp_dir().join(format!("crabler-cache-{}", std::process::id()));
+    let cache_dir = cache_dir.to_str().unwrap();
+
+    let (online, _) = crawl(
+        Opts::new()
+            .with_urls(vec![&format!("{}/", base)])
+            .with_cache_dir(cache_dir),
+    )
+    .await;
+    assert_eq!(
+        online,
+        vec![(format!("{}/", base), 200), (format!("{}/page", base), 200)]
+    );
+
+    // /other is served by the server, but was never cached
+    let (offline, report) = crawl(
+        Opts::new()
+            .with_urls(vec![&format!("{}/", base), &format!("{}/other", base)])
+            .with_cache_dir(cache_dir)
+            .with_offline(true),
+    )
+    .await;
+    assert_eq!(
+        offline,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/other", base), 500),
+            (format!("{}/page", base), 200),
+        ]
+    );
+    assert_eq!(report.errors.len(), 1);
+
+    std::fs::remove_dir_all(cache_dir).unwrap();
+}
@@ -0,0 +1,70 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_pending_duplicates_are_coalesced() {
+    let links = r#"<a href="/shared">shared</a>"#.repeat(20);
+    let base = common::serve(vec![
+        ("/", common::Page::html(&links)),
+        ("/shared", common::Page::html("shared")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_threads(4),
+        )
+        .await
+        .unwrap();
+
+    // all links are followed while /shared is still pending, so no duplicate even reaches a worker
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/shared", base), 200)
+        ]
+    );
+}
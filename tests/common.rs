@@ -0,0 +1,173 @@
+#![allow(dead_code)]
+
+use async_std::io::{ReadExt, WriteExt};
+use async_std::net::{TcpListener, TcpStream};
+use async_std::task;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Canned response served by the test server
+#[derive(Clone)]
+pub struct Page {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// `WWW-Authenticate` sent with a 401 to requests without `Authorization`
+    pub challenge: Option<String>,
+}
+
+impl Page {
+    pub fn html(body: &str) -> Self {
+        Self::with_content_type("text/html; charset=utf-8", body.as_bytes())
+    }
+
+    pub fn with_content_type(content_type: &str, body: &[u8]) -> Self {
+        Page {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: body.to_vec(),
+            challenge: None,
+        }
+    }
+
+    pub fn status(self, status: u16) -> Self {
+        let mut new = self;
+        new.status = status;
+
+        new
+    }
+
+    pub fn header(self, name: &str, value: &str) -> Self {
+        let mut new = self;
+        new.headers.push((name.to_string(), value.to_string()));
+
+        new
+    }
+
+    /// Require authorization, authorized requests get their request head echoed back
+    pub fn challenge(self, www_authenticate: &str) -> Self {
+        let mut new = self;
+        new.challenge = Some(www_authenticate.to_string());
+
+        new
+    }
+}
+
+/// Start local http server serving given pages by path, returns base url like `http://127.0.0.1:1234`
+pub async fn serve(pages: Vec<(&str, Page)>) -> String {
+    let pages = pages
+        .into_iter()
+        .map(|(path, page)| (path.to_string(), page))
+        .collect();
+
+    serve_with(|_| pages).await
+}
+
+/// Same as `serve`, but pages are built from the base url of the server
+pub async fn serve_with<F>(pages: F) -> String
+where
+    F: FnOnce(&str) -> Vec<(String, Page)>,
+{
+    serve_logged(pages).await.0
+}
+
+/// Same as `serve_with`, also returns paths of requests in the order they were received
+pub async fn serve_logged<F>(pages: F) -> (String, Arc<Mutex<Vec<String>>>)
+where
+    F: FnOnce(&str) -> Vec<(String, Page)>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    let pages: HashMap<String, Page> = pages(&base).into_iter().collect();
+    let pages = Arc::new(pages);
+    let flaked = Arc::new(Mutex::new(HashSet::new()));
+    let log = Arc::new(Mutex::new(vec![]));
+    let requests = log.clone();
+
+    task::spawn(async move {
+        loop {
+            if let Ok((stream, _)) = listener.accept().await {
+                task::spawn(handle_connection(
+                    stream,
+                    pages.clone(),
+                    flaked.clone(),
+                    requests.clone(),
+                ));
+            }
+        }
+    });
+
+    (base, log)
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    pages: Arc<HashMap<String, Page>>,
+    flaked: Arc<Mutex<HashSet<String>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+) {
+    let mut request = vec![];
+    let mut buf = [0u8; 1024];
+
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+    requests.lock().unwrap().push(path.clone());
+
+    // flaky pages drop the connection the first time they are requested
+    if path.starts_with("/flaky") && flaked.lock().unwrap().insert(path.clone()) {
+        return;
+    }
+
+    // slow pages take a while to answer, e.g. to observe concurrent requests
+    if path.starts_with("/slow") {
+        task::sleep(std::time::Duration::from_millis(200)).await;
+    }
+
+    let page = match pages.get(&path) {
+        Some(page) => page.clone(),
+        // echo request head back so tests can check what was sent
+        None if path.starts_with("/echo") => Page::html(&format!("<pre>{}</pre>", request)),
+        None => Page::html("not found").status(404),
+    };
+
+    let page = match &page.challenge {
+        Some(challenge) if !request.to_lowercase().contains("\r\nauthorization:") => {
+            Page::html("unauthorized")
+                .status(401)
+                .header("WWW-Authenticate", challenge)
+        }
+        Some(_) => Page::html(&format!("<pre>{}</pre>", request)),
+        None => page,
+    };
+
+    let mut head = format!("HTTP/1.1 {} Test\r\n", page.status);
+    for (name, value) in &page.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        page.body.len()
+    ));
+
+    let _ = stream.write_all(head.as_bytes()).await;
+
+    // stalling pages send half of the body right away and the rest only after a pause
+    if path.starts_with("/stall") {
+        let (first, rest) = page.body.split_at(page.body.len() / 2);
+        let _ = stream.write_all(first).await;
+        let _ = stream.flush().await;
+        task::sleep(std::time::Duration::from_millis(500)).await;
+        let _ = stream.write_all(rest).await;
+        return;
+    }
+
+    let _ = stream.write_all(&page.body).await;
+}
@@ -0,0 +1,126 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a.api", api_link_handler)]
+#[on_html("pre", echo_handler)]
+struct Scraper {
+    responses: Seen,
+    echoed: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn api_link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate_expecting(href, ContentKind::Json).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn echo_handler(&self, _response: Response, pre: Element) -> Result<()> {
+        if let Some(text) = pre.text() {
+            self.echoed.write().unwrap().push(text);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_content_kind_matches() {
+    assert!(ContentKind::Json.matches("application/json; charset=utf-8"));
+    assert!(ContentKind::Json.matches("application/ld+json"));
+    assert!(ContentKind::Xml.matches("text/xml"));
+    assert!(ContentKind::Html.matches("TEXT/HTML"));
+    assert!(!ContentKind::Json.matches("text/html"));
+    assert!(!ContentKind::Xml.matches(""));
+}
+
+#[async_std::test]
+async fn test_navigate_expecting_sends_accept() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(r#"<a class="api" href="/echo/api">api</a>"#),
+    )])
+    .await;
+
+    let echoed = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: Arc::new(RwLock::new(vec![])),
+        echoed: echoed.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let echoed = echoed.read().unwrap();
+    assert_eq!(echoed.len(), 1);
+    assert!(echoed[0].to_lowercase().contains("accept:application/json"));
+}
+
+#[async_std::test]
+async fn test_reject_unexpected_content() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a class="api" href="/data">data</a><a class="api" href="/page">page</a>"#,
+            ),
+        ),
+        (
+            "/data",
+            common::Page::with_content_type("application/json", br#"{"crabs": 3}"#),
+        ),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+        echoed: Arc::new(RwLock::new(vec![])),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_reject_unexpected_content(true)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/data", base), 200),
+            (format!("{}/page", base), 500),
+        ]
+    );
+    assert_eq!(handle.report().errors.len(), 1);
+}
@@ -0,0 +1,75 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Vec<(String, Option<String>)>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    responses: Arc<RwLock<Seen>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url.clone(), response.context.clone()));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        let category = a.attr("data-category").unwrap();
+
+        response
+            .navigate_with_context(format!("{}{}", self.base, href), category)
+            .await
+    }
+}
+
+#[async_std::test]
+async fn test_context_reaches_handlers() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/books" data-category="books">b</a>
+                <a href="/music" data-category="music">m</a>"#,
+            ),
+        ),
+        ("/books", common::Page::html("books")),
+        ("/music", common::Page::html("music")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), None),
+            (format!("{}/books", base), Some("books".to_string())),
+            (format!("{}/music", base), Some("music".to_string())),
+        ]
+    );
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "cookie-store")]
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn response_handler(&self, _response: Response) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_cookies_for_host() {
+    let base = common::serve(vec![(
+        "/login",
+        common::Page::html("welcome")
+            .header("Set-Cookie", "session=abc; Path=/")
+            .header("Set-Cookie", "theme=dark; Domain=127.0.0.1")
+            .header("Set-Cookie", "gone=1; Max-Age=0"),
+    )])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/login", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut cookies = handle
+        .cookies_for("127.0.0.1")
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+        .collect::<Vec<_>>();
+    cookies.sort();
+
+    assert_eq!(cookies, vec!["session=abc", "theme=dark"]);
+    assert!(handle.cookies_for("example.com").is_empty());
+}
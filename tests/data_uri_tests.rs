@@ -0,0 +1,138 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+#[on_html("img[src]", image_handler)]
+#[on_html("p", paragraph_handler)]
+struct Scraper {
+    responses: Seen,
+    paragraphs: Arc<RwLock<Vec<String>>>,
+    destination: String,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let scheme = response
+            .url
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        self.responses
+            .write()
+            .unwrap()
+            .push((scheme, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn image_handler(&self, mut response: Response, img: Element) -> Result<()> {
+        if let Some(src) = img.attr("src") {
+            response
+                .download_file(src, self.destination.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, _response: Response, p: Element) -> Result<()> {
+        if let Some(text) = p.text() {
+            self.paragraphs.write().unwrap().push(text);
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts, destination: &str) -> (Vec<(String, u16)>, Vec<String>, CrawlReport) {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(
+            r#"<a href="data:text/html;base64,PHA+aW5saW5lPC9wPg==">page</a>
+            <img src="data:text/plain,hello%20crab">"#,
+        ),
+    )])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let paragraphs = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+        paragraphs: paragraphs.clone(),
+        destination: destination.to_string(),
+    };
+
+    scraper
+        .run(
+            opts.with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    let paragraphs = paragraphs.read().unwrap().clone();
+    (responses, paragraphs, handle.report())
+}
+
+#[async_std::test]
+async fn test_data_uris_skipped_by_default() {
+    let (responses, paragraphs, report) = crawl(Opts::new(), "unused").await;
+
+    assert_eq!(
+        responses,
+        vec![
+            ("data".to_string(), 304),
+            ("data".to_string(), 304),
+            ("http".to_string(), 200),
+        ]
+    );
+    assert!(paragraphs.is_empty());
+    assert_eq!(report.errors.len(), 0);
+}
+
+#[async_std::test]
+async fn test_data_uris_decoded() {
+    let destination = std::env::temp_dir()
+        .join(format!("crabler-data-uri-{}.txt", std::process::id()))
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let (responses, paragraphs, report) =
+        crawl(Opts::new().with_handle_data_uris(true), &destination).await;
+
+    assert_eq!(
+        responses,
+        vec![
+            ("data".to_string(), 200),
+            ("data".to_string(), 200),
+            ("http".to_string(), 200),
+        ]
+    );
+    assert_eq!(paragraphs, vec!["inline"]);
+    assert_eq!(report.errors.len(), 0);
+    assert_eq!(std::fs::read_to_string(&destination).unwrap(), "hello crab");
+
+    std::fs::remove_file(&destination).unwrap();
+}
@@ -0,0 +1,79 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_dedup_key() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/session-1/item">1</a>"#),
+        ),
+        (
+            "/session-1/item",
+            common::Page::html(r#"<a href="/session-2/item">2</a>"#),
+        ),
+        ("/session-2/item", common::Page::html("same item")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_dedup_key(|url| {
+                    let path = url.path().splitn(3, '/').nth(2).unwrap_or_default();
+                    format!("{}/{}", url.host_str().unwrap_or_default(), path)
+                }),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/session-1/item", base), 200),
+            (format!("{}/session-2/item", base), 304),
+        ]
+    );
+}
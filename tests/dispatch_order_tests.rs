@@ -0,0 +1,96 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<String>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+#[on_html("p", paragraph_handler)]
+struct Scraper {
+    seen: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.url.ends_with("/private") {
+            response.skip_html();
+        }
+        self.seen.write().unwrap().push(format!(
+            "response {}",
+            response.url.rsplit('/').next().unwrap()
+        ));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, _: Response, p: Element) -> Result<()> {
+        self.seen
+            .write()
+            .unwrap()
+            .push(format!("html {}", p.text().unwrap_or_default()));
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> Vec<String> {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<p>root</p><a href="/private">p</a>"#),
+        ),
+        (
+            "/private",
+            common::Page::html(r#"<p>private</p><a href="/hidden">h</a>"#),
+        ),
+        ("/hidden", common::Page::html("<p>hidden</p>")),
+    ])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper { seen: seen.clone() };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]).with_threads(1))
+        .await
+        .unwrap();
+
+    let seen = seen.read().unwrap().clone();
+    seen
+}
+
+#[async_std::test]
+async fn test_html_first() {
+    // skipping from on_response is too late, html handlers already ran
+    assert_eq!(
+        crawl(Opts::new()).await,
+        vec![
+            "html root",
+            "response ",
+            "html private",
+            "response private",
+            "html hidden",
+            "response hidden",
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_response_first() {
+    assert_eq!(
+        crawl(Opts::new().with_dispatch_order(DispatchOrder::ResponseFirst)).await,
+        vec!["response ", "html root", "response private"]
+    );
+}
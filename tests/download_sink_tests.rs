@@ -0,0 +1,282 @@
+extern crate crabler;
+
+use crabler::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod common;
+
+#[derive(Clone, Default)]
+struct MemorySink {
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    finalized: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl DownloadSink for MemorySink {
+    async fn write_chunk(&self, destination: &str, chunk: &[u8]) -> Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .entry(destination.to_string())
+            .or_default()
+            .extend_from_slice(chunk);
+        Ok(())
+    }
+
+    async fn finalize(&self, destination: &str) -> Result<()> {
+        self.finalized.lock().unwrap().push(destination.to_string());
+        Ok(())
+    }
+}
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", download_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn download_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            let destination = format!("memory:{}", href);
+            response.download_file(href, destination).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_download_sink() {
+    let large = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/small.txt">s</a><a href="/large.bin">l</a>"#),
+        ),
+        (
+            "/small.txt",
+            common::Page::with_content_type("text/plain", b"hello"),
+        ),
+        (
+            "/large.bin",
+            common::Page::with_content_type("application/octet-stream", &large),
+        ),
+    ])
+    .await;
+
+    let sink = MemorySink::default();
+    let mut scraper = Scraper {};
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_download_sink(sink.clone()),
+        )
+        .await
+        .unwrap();
+
+    let files = sink.files.lock().unwrap();
+    assert_eq!(files["memory:/small.txt"], b"hello");
+    assert_eq!(files["memory:/large.bin"], large);
+
+    let mut finalized = sink.finalized.lock().unwrap().clone();
+    finalized.sort();
+    assert_eq!(finalized, vec!["memory:/large.bin", "memory:/small.txt"]);
+}
+
+#[async_std::test]
+async fn test_download_urls() {
+    let base = common::serve(vec![
+        (
+            "/a.txt",
+            common::Page::with_content_type("text/plain", b"first"),
+        ),
+        (
+            "/b.txt",
+            common::Page::with_content_type("text/plain", b"second"),
+        ),
+    ])
+    .await;
+
+    let sink = MemorySink::default();
+    let mut scraper = Scraper {};
+    let a = format!("{}/a.txt", base);
+    let b = format!("{}/b.txt", base);
+
+    scraper
+        .run(
+            Opts::new()
+                .with_download_urls(vec![(&a, "memory:a"), (&b, "memory:b")])
+                .with_download_sink(sink.clone()),
+        )
+        .await
+        .unwrap();
+
+    let files = sink.files.lock().unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files["memory:a"], b"first");
+    assert_eq!(files["memory:b"], b"second");
+}
+
+#[async_std::test]
+async fn test_file_sink_permissions() {
+    let base = common::serve(vec![(
+        "/file.txt",
+        common::Page::with_content_type("text/plain", b"mirrored"),
+    )])
+    .await;
+
+    let destination = std::env::temp_dir()
+        .join(format!("crabler-permissions-{}.txt", std::process::id()))
+        .to_string_lossy()
+        .to_string();
+    let url = format!("{}/file.txt", base);
+
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_download_urls(vec![(&url, &destination)])
+                .with_download_permissions(0o640),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&destination).unwrap(), "mirrored");
+    assert!(!std::path::Path::new(&format!("{}.part", destination)).exists());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&destination)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    std::fs::remove_file(&destination).unwrap();
+}
+
+#[async_std::test]
+async fn test_destination_rewriter() {
+    let base = common::serve(vec![
+        (
+            "/files/a.txt",
+            common::Page::with_content_type("text/plain", b"first"),
+        ),
+        ("/missing.txt", common::Page::html("gone").status(404)),
+    ])
+    .await;
+
+    let sink = MemorySink::default();
+    let mut scraper = Scraper {};
+    let a = format!("{}/files/a.txt", base);
+    let missing = format!("{}/missing.txt", base);
+    let seen = Arc::new(Mutex::new(vec![]));
+    let calls = seen.clone();
+
+    scraper
+        .run(
+            Opts::new()
+                .with_download_urls(vec![(&a, "memory:a.txt"), (&missing, "memory:missing")])
+                .with_download_sink(sink.clone())
+                .with_destination_rewriter(move |url, destination| {
+                    calls.lock().unwrap().push(url.to_string());
+                    let path = url.rsplit('/').next().unwrap();
+                    destination.replace("memory:", &format!("memory:{}/", path.len()))
+                }),
+        )
+        .await
+        .unwrap();
+
+    let files = sink.files.lock().unwrap();
+    assert_eq!(files.keys().collect::<Vec<_>>(), vec!["memory:5/a.txt"]);
+    assert_eq!(files["memory:5/a.txt"], b"first");
+    // failed downloads never get to writing, so their destination is not rewritten
+    assert_eq!(*seen.lock().unwrap(), vec![a]);
+}
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", dir_handler)]
+struct DirScraper {}
+
+impl DirScraper {
+    async fn dir_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response
+                .download_to_dir(href, "memory:mirror".to_string())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_download_to_dir() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/get?id=1">1</a><a href="/get?id=2">2</a>
+                <a href="/get?id=3">3</a><a href="/files/report%20final.pdf">4</a>"#,
+            ),
+        ),
+        (
+            "/get?id=1",
+            common::Page::with_content_type("text/plain", b"1").header(
+                "Content-Disposition",
+                r#"attachment; filename="plain; name.txt""#,
+            ),
+        ),
+        (
+            "/get?id=2",
+            common::Page::with_content_type("text/plain", b"2").header(
+                "Content-Disposition",
+                "attachment; filename=fallback.txt; filename*=UTF-8''cr%C3%A4b%20list.csv",
+            ),
+        ),
+        (
+            "/get?id=3",
+            common::Page::with_content_type("text/plain", b"3").header(
+                "Content-Disposition",
+                r#"attachment; filename="../../etc/passwd""#,
+            ),
+        ),
+        (
+            "/files/report%20final.pdf",
+            common::Page::with_content_type("application/pdf", b"4"),
+        ),
+    ])
+    .await;
+
+    let sink = MemorySink::default();
+    let mut scraper = DirScraper {};
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_download_sink(sink.clone()),
+        )
+        .await
+        .unwrap();
+
+    let files = sink.files.lock().unwrap();
+    let mut names = files.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "memory:mirror/cräb list.csv",
+            "memory:mirror/passwd",
+            "memory:mirror/plain; name.txt",
+            "memory:mirror/report final.pdf",
+        ]
+    );
+    assert_eq!(files["memory:mirror/plain; name.txt"], b"1");
+}
@@ -0,0 +1,76 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.fetched.write().unwrap().push(response.url);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_enqueue_from_outside() {
+    let base = common::serve(vec![
+        ("/", common::Page::html("seed")),
+        ("/before", common::Page::html("before")),
+        ("/during", common::Page::html("during")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    handle.enqueue(&format!("{}/before", base)).await.unwrap();
+
+    let feeder = handle.clone();
+    let during = format!("{}/during", base);
+    let feed = async_std::task::spawn(async move { feeder.enqueue(&during).await });
+
+    let (crawl, fed) = futures::join!(
+        scraper.run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        ),
+        feed
+    );
+    crawl.unwrap();
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    match fed {
+        Ok(()) => assert_eq!(
+            fetched,
+            vec![
+                format!("{}/", base),
+                format!("{}/before", base),
+                format!("{}/during", base),
+            ]
+        ),
+        Err(CrablerError::CrawlFinished) => assert_eq!(
+            fetched,
+            vec![format!("{}/", base), format!("{}/before", base)]
+        ),
+        Err(e) => panic!("unexpected error {}", e),
+    }
+
+    assert!(matches!(
+        handle.enqueue(&format!("{}/after", base)).await,
+        Err(CrablerError::CrawlFinished)
+    ));
+}
@@ -0,0 +1,175 @@
+extern crate crabler;
+
+use crabler::*;
+use std::io;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", download_handler)]
+struct Scraper {
+    base: String,
+    destination: String,
+    statuses: Arc<RwLock<Vec<(String, u16)>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.statuses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn download_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response
+                .download_file(format!("{}{}", self.base, href), self.destination.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_retryable_errors() {
+    assert!(CrablerError::Network("connection refused".to_string()).is_retryable());
+    assert!(CrablerError::Timeout("read".to_string()).is_retryable());
+    assert!(CrablerError::BodyTimeout("http://example.com".to_string()).is_retryable());
+    assert!(CrablerError::Http(503).is_retryable());
+    assert!(CrablerError::Http(429).is_retryable());
+    assert!(!CrablerError::Http(404).is_retryable());
+    assert!(!CrablerError::Parse("invalid utf-8".to_string()).is_retryable());
+    assert!(!CrablerError::Io(io::Error::other("disk full")).is_retryable());
+    assert!(!CrablerError::BodyTooLarge(1024).is_retryable());
+}
+
+#[async_std::test]
+async fn test_failed_download_is_not_written() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(r#"<a href="/missing.zip">z</a>"#),
+    )])
+    .await;
+    let destination = std::env::temp_dir()
+        .join(format!("crabler-missing-{}.zip", std::process::id()))
+        .to_string_lossy()
+        .to_string();
+
+    let statuses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        destination: destination.clone(),
+        statuses: statuses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_progress_log_interval(std::time::Duration::from_millis(1))
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut statuses = statuses.read().unwrap().clone();
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/missing.zip", base), 500),
+        ]
+    );
+    assert!(!std::path::Path::new(&destination).exists());
+
+    let report = handle.report();
+    assert_eq!(report.pages, 1);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, format!("{}/missing.zip", base));
+    assert!(matches!(report.errors[0].1, CrablerError::Http(404)));
+}
+
+#[async_std::test]
+async fn test_fail_fast() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(r#"<a href="/missing.zip">z</a>"#),
+    )])
+    .await;
+    let destination = std::env::temp_dir()
+        .join(format!("crabler-fail-fast-{}.zip", std::process::id()))
+        .to_string_lossy()
+        .to_string();
+
+    let statuses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        destination,
+        statuses: statuses.clone(),
+    };
+
+    let result = scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_final_retry_pass(true)
+                .with_fail_fast(true),
+        )
+        .await;
+
+    match result {
+        Err(CrablerError::Failed { url, error }) => {
+            assert_eq!(url, format!("{}/missing.zip", base));
+            assert!(matches!(*error, CrablerError::Http(404)));
+        }
+        other => panic!("expected failure, got {:?}", other),
+    }
+}
+
+async fn crawl_stalling_page(body_read_timeout: std::time::Duration) -> CrawlReport {
+    let base = common::serve(vec![(
+        "/stall",
+        common::Page::html("<p>first half</p><p>second half</p>"),
+    )])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        destination: String::new(),
+        statuses: Arc::new(RwLock::new(vec![])),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/stall", base)])
+                .with_body_read_timeout(body_read_timeout)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    handle.report()
+}
+
+#[async_std::test]
+async fn test_body_read_timeout() {
+    let report = crawl_stalling_page(std::time::Duration::from_millis(100)).await;
+    assert_eq!(report.pages, 0);
+    assert_eq!(report.errors.len(), 1);
+    assert!(matches!(report.errors[0].1, CrablerError::BodyTimeout(_)));
+
+    let report = crawl_stalling_page(std::time::Duration::from_secs(5)).await;
+    assert_eq!(report.pages, 1);
+    assert!(report.errors.is_empty());
+}
@@ -0,0 +1,90 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(Debug, PartialEq)]
+enum Milestone {
+    Page(String),
+    Links(usize),
+}
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let path = response
+            .url
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        response.emit_event(Milestone::Page(path)).await;
+        // nobody receives these, they are dropped
+        response.emit_event(response.status).await;
+
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+            response.emit_event(Milestone::Links(1)).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_emit_event() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a>"#),
+        ),
+        ("/a", common::Page::html("a")),
+        ("/b", common::Page::html("b")),
+    ])
+    .await;
+
+    let handle = CrablerHandle::new();
+    // smaller than number of events, handlers wait for the consumer
+    let events = handle.events::<Milestone>(1);
+    let consumer = async_std::task::spawn(async move {
+        let mut received = vec![];
+        while let Ok(event) = events.recv().await {
+            received.push(event);
+        }
+        received
+    });
+
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle),
+        )
+        .await
+        .unwrap();
+
+    // channel is closed once the crawl is done
+    let received = consumer.await;
+    assert_eq!(received.len(), 5);
+    assert_eq!(
+        received
+            .iter()
+            .filter(|event| **event == Milestone::Links(1))
+            .count(),
+        2
+    );
+    for page in &["", "a", "b"] {
+        assert!(received.contains(&Milestone::Page(page.to_string())));
+    }
+}
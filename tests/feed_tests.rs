@@ -0,0 +1,147 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_feed_item(feed_item_handler)]
+struct Scraper {
+    visited: Arc<RwLock<Vec<String>>>,
+    items: Arc<RwLock<Vec<FeedItem>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited.write().unwrap().push(response.url);
+        Ok(())
+    }
+
+    async fn feed_item_handler(&self, _response: Response, item: FeedItem) -> Result<()> {
+        self.items.write().unwrap().push(item);
+        Ok(())
+    }
+}
+
+const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <title>Blog</title>
+  <link>/</link>
+  <item>
+    <title>First post</title>
+    <link>/posts/1</link>
+    <guid>post-1</guid>
+    <pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate>
+    <description><![CDATA[<p>Hello</p>]]></description>
+  </item>
+</channel></rss>"#;
+
+const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Blog</title>
+  <entry>
+    <title>Second post</title>
+    <link rel="edit" href="/edit/2"/>
+    <link href="/posts/2"/>
+    <id>urn:post:2</id>
+    <updated>2024-01-03T00:00:00Z</updated>
+    <published>2024-01-02T00:00:00Z</published>
+    <summary>World</summary>
+  </entry>
+</feed>"#;
+
+async fn crawl(opts: Opts) -> (String, Vec<String>, Vec<FeedItem>) {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<head>
+                <link rel="alternate" type="application/rss+xml" href="/rss.xml">
+                <link rel="alternate" type="application/atom+xml" href="/atom.xml">
+                <link rel="alternate" type="application/rss+xml" href="/broken.xml">
+                <link rel="alternate" hreflang="de" href="/de/">
+                </head>"#,
+            ),
+        ),
+        (
+            "/rss.xml",
+            common::Page::with_content_type("application/rss+xml", RSS.as_bytes()),
+        ),
+        (
+            "/atom.xml",
+            common::Page::with_content_type("application/atom+xml", ATOM.as_bytes()),
+        ),
+        (
+            "/broken.xml",
+            common::Page::with_content_type("application/rss+xml", b"<html>not a feed"),
+        ),
+        ("/posts/1", common::Page::html("first")),
+        ("/posts/2", common::Page::html("second")),
+    ])
+    .await;
+
+    let visited = Arc::new(RwLock::new(vec![]));
+    let items = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        visited: visited.clone(),
+        items: items.clone(),
+    };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut visited = visited.read().unwrap().clone();
+    visited.sort();
+    let mut items = items.read().unwrap().clone();
+    items.sort_by(|a, b| a.feed.cmp(&b.feed));
+
+    (base, visited, items)
+}
+
+#[async_std::test]
+async fn test_feeds_not_followed_by_default() {
+    let (base, visited, items) = crawl(Opts::new()).await;
+
+    assert_eq!(visited, vec![format!("{}/", base)]);
+    assert!(items.is_empty());
+}
+
+#[async_std::test]
+async fn test_follow_feeds() {
+    let (base, visited, items) = crawl(Opts::new().with_follow_feeds(true)).await;
+
+    assert_eq!(
+        visited,
+        vec![
+            format!("{}/", base),
+            format!("{}/posts/1", base),
+            format!("{}/posts/2", base),
+        ]
+    );
+    assert_eq!(
+        items,
+        vec![
+            FeedItem {
+                feed: format!("{}/atom.xml", base),
+                title: Some("Second post".to_string()),
+                link: Some(format!("{}/posts/2", base)),
+                id: Some("urn:post:2".to_string()),
+                published: Some("2024-01-02T00:00:00Z".to_string()),
+                summary: Some("World".to_string()),
+            },
+            FeedItem {
+                feed: format!("{}/rss.xml", base),
+                title: Some("First post".to_string()),
+                link: Some(format!("{}/posts/1", base)),
+                id: Some("post-1".to_string()),
+                published: Some("Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+                summary: Some("<p>Hello</p>".to_string()),
+            },
+        ]
+    );
+}
@@ -0,0 +1,84 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<String>>>;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html("p", paragraph_handler)]
+struct Scraper {
+    seen: Seen,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, response: Response, p: Element) -> Result<()> {
+        let text = p.text().unwrap_or_default();
+        if text == "needle" {
+            response.found();
+        }
+        self.seen.write().unwrap().push(text);
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (Vec<String>, CrawlReport) {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+        ),
+        ("/a", common::Page::html("<p>a</p>")),
+        ("/b", common::Page::html("<p>needle</p><p>after</p>")),
+        ("/c", common::Page::html("<p>c</p>")),
+    ])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper { seen: seen.clone() };
+
+    scraper
+        .run(
+            opts.with_urls(vec![&format!("{}/", base)])
+                .with_threads(1)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let seen = seen.read().unwrap().clone();
+    (seen, handle.report())
+}
+
+#[async_std::test]
+async fn test_found() {
+    let (seen, report) = crawl(Opts::new()).await;
+
+    // rest of the page is not dispatched once the target is found, other pages are
+    assert_eq!(seen, vec!["a", "needle", "c"]);
+    assert_eq!(report.found.len(), 1);
+    assert!(report.found[0].ends_with("/b"));
+    assert_eq!(report.aborted, None);
+}
+
+#[async_std::test]
+async fn test_abort_on_found() {
+    let (seen, report) = crawl(Opts::new().with_abort_on_found(true)).await;
+
+    assert_eq!(seen, vec!["a", "needle"]);
+    assert_eq!(report.found.len(), 1);
+    assert!(report.aborted.unwrap().starts_with("found target on"));
+}
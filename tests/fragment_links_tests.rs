@@ -0,0 +1,76 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    links: Arc<Mutex<usize>>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        // follow links only from the first page, revisits would loop forever
+        {
+            let mut links = self.links.lock().unwrap();
+            *links += 1;
+            if *links > 2 {
+                return Ok(());
+            }
+        }
+
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> Vec<String> {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(r##"<a href="#top">top</a><a href="/other">other</a>"##),
+            ),
+            ("/other".to_string(), common::Page::html("other")),
+        ]
+    })
+    .await;
+
+    let scraper = Scraper {
+        links: Arc::new(Mutex::new(0)),
+    };
+
+    // revisiting lets fragment links re-fetch the page they are on
+    scraper
+        .run(
+            opts.with_revisit_after(Duration::ZERO)
+                .with_urls(vec![&format!("{}/", base)]),
+        )
+        .await
+        .unwrap();
+
+    let mut requests = requests.lock().unwrap().clone();
+    requests.sort();
+    requests
+}
+
+#[async_std::test]
+async fn test_fragment_links_skipped_by_default() {
+    assert_eq!(crawl(Opts::new()).await, vec!["/", "/other"]);
+}
+
+#[async_std::test]
+async fn test_follow_fragment_links() {
+    assert_eq!(
+        crawl(Opts::new().with_follow_fragment_links(true)).await,
+        vec!["/", "/", "/other"]
+    );
+}
@@ -0,0 +1,132 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Vec<(String, Option<String>)>;
+type MimeSeen = Vec<(Option<String>, Option<String>)>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    responses: Arc<RwLock<Seen>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let content_type = response.headers.get("content-type").cloned();
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url.clone(), content_type));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_should_download_body_skips_unwanted_content() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/page">p</a><a href="/file.pdf">f</a>"#),
+        ),
+        ("/page", common::Page::html("page")),
+        (
+            "/file.pdf",
+            common::Page::with_content_type("application/pdf", br#"<a href="/hidden">h</a>"#),
+        ),
+        ("/hidden", common::Page::html("hidden")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_should_download_body(|_, headers| {
+                    headers
+                        .get("content-type")
+                        .map(|t| t.starts_with("text/html"))
+                        .unwrap_or(false)
+                }),
+        )
+        .await
+        .unwrap();
+
+    let html = Some("text/html; charset=utf-8".to_string());
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), html.clone()),
+            (
+                format!("{}/file.pdf", base),
+                Some("application/pdf".to_string())
+            ),
+            (format!("{}/page", base), html),
+        ]
+    );
+}
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct MimeScraper {
+    seen: Arc<RwLock<MimeSeen>>,
+}
+
+impl MimeScraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let content_type = response.content_type().map(String::from);
+        let charset = response
+            .mime()
+            .and_then(|mime| mime.param("charset").map(|charset| charset.to_string()));
+        self.seen.write().unwrap().push((content_type, charset));
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_content_type_and_mime() {
+    let base = common::serve(vec![(
+        "/data",
+        common::Page::with_content_type("application/json; charset=utf-8", b"{}"),
+    )])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = MimeScraper { seen: seen.clone() };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/data", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *seen.read().unwrap(),
+        vec![(
+            Some("application/json".to_string()),
+            Some("utf-8".to_string())
+        )]
+    );
+}
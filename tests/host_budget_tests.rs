@@ -0,0 +1,64 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_max_pages_per_host() {
+    let links = (0..20)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a>", i, i))
+        .collect::<String>();
+    let paths = (0..20).map(|i| format!("/page/{}", i)).collect::<Vec<_>>();
+    let mut pages = vec![("/", common::Page::html(&links))];
+    for path in &paths {
+        pages.push((path.as_str(), common::Page::html("leaf")));
+    }
+    let base = common::serve(pages).await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_max_pages_per_host(5)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.read().unwrap().len(), 5);
+    assert_eq!(handle.report().over_budget.get("127.0.0.1"), Some(&16));
+}
@@ -0,0 +1,194 @@
+extern crate crabler;
+
+use crabler::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("pre", echo_handler)]
+struct Scraper {
+    requests: Arc<RwLock<Vec<(String, String)>>>,
+}
+
+impl Scraper {
+    async fn echo_handler(&self, response: Response, pre: Element) -> Result<()> {
+        let request = pre.text().unwrap_or_default().to_lowercase();
+        self.requests.write().unwrap().push((response.url, request));
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_host_headers() {
+    let base = common::serve(vec![]).await;
+    let port = base.rsplit(':').next().unwrap();
+    let local = format!("http://127.0.0.1:{}/echo", port);
+    let other = format!("http://localhost:{}/echo", port);
+
+    let requests = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        requests: requests.clone(),
+    };
+
+    let mut defaults = HashMap::new();
+    defaults.insert("X-Client", "crabler");
+    defaults.insert("X-Tier", "free");
+    let mut auth = HashMap::new();
+    auth.insert("Authorization", "Bearer secret");
+    auth.insert("X-Tier", "paid");
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&local, &other])
+                .with_default_headers(defaults)
+                .with_host_headers("127.0.0.1", auth),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.read().unwrap().clone();
+    let request_to = |url: &str| {
+        requests
+            .iter()
+            .find(|(u, _)| u == url)
+            .map(|(_, request)| request.clone())
+            .unwrap()
+    };
+
+    let local = request_to(&local);
+    assert!(local.contains("authorization:bearer secret"));
+    assert!(local.contains("x-tier:paid"));
+    assert!(local.contains("x-client:crabler"));
+
+    let other = request_to(&other);
+    assert!(!other.contains("authorization"));
+    assert!(other.contains("x-tier:free"));
+    assert!(other.contains("x-client:crabler"));
+}
+
+#[async_std::test]
+async fn test_accept_language() {
+    let base = common::serve(vec![]).await;
+    let port = base.rsplit(':').next().unwrap();
+    let local = format!("http://127.0.0.1:{}/echo", port);
+    let other = format!("http://localhost:{}/echo", port);
+
+    let requests = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        requests: requests.clone(),
+    };
+
+    let mut defaults = HashMap::new();
+    defaults.insert("Accept-Language", "fr");
+    let mut english = HashMap::new();
+    english.insert("Accept-Language", "en-US");
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&local, &other])
+                .with_accept_language("de-DE, de;q=0.9")
+                .with_default_headers(defaults)
+                .with_host_headers("127.0.0.1", english),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.read().unwrap().clone();
+    let request_to = |url: &str| {
+        requests
+            .iter()
+            .find(|(u, _)| u == url)
+            .map(|(_, request)| request.clone())
+            .unwrap()
+    };
+
+    assert!(request_to(&local).contains("accept-language:en-us"));
+    assert!(request_to(&other).contains("accept-language:de-de, de;q=0.9"));
+}
+
+#[async_std::test]
+async fn test_host_override() {
+    let base = common::serve(vec![]).await;
+    let port = base.rsplit(':').next().unwrap();
+    let local = format!("http://127.0.0.1:{}/echo", port);
+    let other = format!("http://localhost:{}/echo", port);
+
+    let requests = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        requests: requests.clone(),
+    };
+
+    let mut defaults = HashMap::new();
+    defaults.insert("Host", "default.test");
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&local, &other])
+                .with_default_headers(defaults)
+                .with_host_override("127.0.0.1", "staging.example.com"),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.read().unwrap().clone();
+    let request_to = |url: &str| {
+        requests
+            .iter()
+            .find(|(u, _)| u == url)
+            .map(|(_, request)| request.clone())
+            .unwrap()
+    };
+
+    assert!(request_to(&local).contains("host:staging.example.com"));
+    assert!(request_to(&other).contains("host:default.test"));
+}
+
+#[async_std::test]
+async fn test_host_client() {
+    use std::convert::TryInto;
+
+    let base = common::serve(vec![]).await;
+    let port = base.rsplit(':').next().unwrap();
+    let local = format!("http://127.0.0.1:{}/echo", port);
+    let other = format!("http://localhost:{}/echo", port);
+
+    let requests = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        requests: requests.clone(),
+    };
+
+    let client: surf::Client = surf::Config::new()
+        .add_header("X-Routed", "custom")
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&local, &other])
+                .with_host_client("LocalHost", client),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.read().unwrap().clone();
+    let request_to = |url: &str| {
+        requests
+            .iter()
+            .find(|(u, _)| u == url)
+            .map(|(_, request)| request.clone())
+            .unwrap()
+    };
+
+    assert!(request_to(&other).contains("x-routed:custom"));
+    assert!(!request_to(&local).contains("x-routed"));
+}
@@ -0,0 +1,70 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[macro_use]
+mod common;
+
+type Tables = Arc<RwLock<Vec<Vec<String>>>>;
+
+#[derive(MutableWebScraper)]
+#[on_html("td", cell_handler)]
+#[on_html_all("td", table_handler)]
+#[on_html_all("li", list_handler)]
+struct Scraper {
+    cells: Arc<RwLock<usize>>,
+    tables: Tables,
+    lists: Arc<RwLock<Vec<usize>>>,
+}
+
+impl Scraper {
+    async fn cell_handler(&self, _response: Response, _td: Element) -> Result<()> {
+        *self.cells.write().unwrap() += 1;
+        Ok(())
+    }
+
+    async fn table_handler(&self, _response: Response, cells: Vec<Element>) -> Result<()> {
+        let cells = cells
+            .iter()
+            .map(|td| td.text().unwrap_or_default())
+            .collect();
+        self.tables.write().unwrap().push(cells);
+        Ok(())
+    }
+
+    async fn list_handler(&self, _response: Response, items: Vec<Element>) -> Result<()> {
+        self.lists.write().unwrap().push(items.len());
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_html_all_gets_all_matches_at_once() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(
+            "<table><tr><td>a</td></tr><tr><td>b</td></tr><tr><td>c</td></tr></table>",
+        ),
+    )])
+    .await;
+
+    let cells = Arc::new(RwLock::new(0));
+    let tables = Arc::new(RwLock::new(vec![]));
+    let lists = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        cells: cells.clone(),
+        tables: tables.clone(),
+        lists: lists.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(*cells.read().unwrap(), 3);
+    assert_eq!(*tables.read().unwrap(), vec![vec!["a", "b", "c"]]);
+    // handler runs even without matches
+    assert_eq!(*lists.read().unwrap(), vec![0]);
+}
@@ -0,0 +1,100 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, String)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html_for("/products/*", "span.price", product_price)]
+#[on_html_for(regex = r"/blog/\d+$", "span.price", blog_price)]
+struct Scraper {
+    prices: Seen,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn product_price(&self, response: Response, span: Element) -> Result<()> {
+        self.record("product", response, span)
+    }
+
+    async fn blog_price(&self, response: Response, span: Element) -> Result<()> {
+        self.record("blog", response, span)
+    }
+
+    fn record(&self, kind: &str, response: Response, span: Element) -> Result<()> {
+        let path = Url::parse(&response.url).unwrap().path().to_string();
+        self.prices.write().unwrap().push((
+            kind.to_string(),
+            format!("{} {}", path, span.text().unwrap_or_default()),
+        ));
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_html_for() {
+    let price =
+        |amount: &str| common::Page::html(&format!(r#"<span class="price">{}</span>"#, amount));
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<span class="price">0</span>
+                <a href="/products/1">p</a><a href="/products/1/reviews">r</a>
+                <a href="/blog/7">b</a><a href="/blog/about">a</a>"#,
+            ),
+        ),
+        ("/products/1", price("1")),
+        ("/products/1/reviews", price("2")),
+        ("/blog/7", price("3")),
+        ("/blog/about", price("4")),
+    ])
+    .await;
+
+    let prices = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        prices: prices.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let mut prices = prices.read().unwrap().clone();
+    prices.sort();
+    assert_eq!(
+        prices,
+        vec![
+            ("blog".to_string(), "/blog/7 3".to_string()),
+            ("product".to_string(), "/products/1 1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_url_pattern() {
+    assert!(UrlPattern::glob("/a/**/c").matches("http://x.com/a/b/b/c"));
+    assert!(UrlPattern::glob("/a/?/c").matches("http://x.com/a/b/c?q=1"));
+    assert!(!UrlPattern::glob("/a/*").matches("http://x.com/a/b/c"));
+    assert!(UrlPattern::glob("https://*.example.com/**").matches("https://www.example.com/a/b"));
+    assert!(!UrlPattern::glob("https://*.example.com/**").matches("http://www.example.com/a"));
+    assert!(UrlPattern::regex("^https?://")
+        .unwrap()
+        .matches("https://x.com/"));
+    assert!(UrlPattern::regex("(").is_err());
+}
@@ -0,0 +1,54 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (Duration, usize) {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a>"#),
+            ),
+            ("/a".to_string(), common::Page::html("a")),
+            ("/b".to_string(), common::Page::html("b")),
+        ]
+    })
+    .await;
+
+    let started = Instant::now();
+    Scraper {}
+        .run(opts.with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap().len();
+    (started.elapsed(), requests)
+}
+
+#[async_std::test]
+async fn test_initial_host_delay_only_delays_first_request() {
+    let delay = Duration::from_millis(300);
+    let (elapsed, requests) = crawl(Opts::new().with_initial_host_delay(delay)).await;
+
+    assert_eq!(requests, 3);
+    assert!(elapsed >= delay);
+    assert!(elapsed < delay * 2);
+}
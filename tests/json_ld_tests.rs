@@ -0,0 +1,52 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_json_ld(json_ld_handler)]
+struct Scraper {
+    names: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn json_ld_handler(&self, _response: Response, value: JsonValue) -> Result<()> {
+        if let Some(name) = value["name"].as_str() {
+            self.names.write().unwrap().push(name.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_json_ld_blocks() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(
+            r#"<html><head>
+            <script type="application/ld+json">{"@type": "Product", "name": "Crab"}</script>
+            <script type="application/ld+json">{"broken": </script>
+            <script type="application/ld+json">{"@type": "Article", "name": "Shell"}</script>
+            <script>{"name": "not json-ld"}</script>
+            </head><body></body></html>"#,
+        ),
+    )])
+    .await;
+
+    let names = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        names: names.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(*names.read().unwrap(), vec!["Crab", "Shell"]);
+}
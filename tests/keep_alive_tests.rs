@@ -0,0 +1,72 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.fetched.write().unwrap().push(response.url);
+        Ok(())
+    }
+}
+
+async fn wait_for_pages(handle: &CrablerHandle, pages: usize) {
+    while handle.report().pages < pages {
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[async_std::test]
+async fn test_keep_alive_waits_for_enqueued_work() {
+    let base = common::serve(vec![
+        ("/", common::Page::html("seed")),
+        ("/later", common::Page::html("later")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    let feeder = handle.clone();
+    let later = format!("{}/later", base);
+    let feed = async_std::task::spawn(async move {
+        // crawl is idle once the seed is done, it would have finished without keep-alive
+        wait_for_pages(&feeder, 1).await;
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        feeder.enqueue(&later).await.unwrap();
+
+        wait_for_pages(&feeder, 2).await;
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        feeder.stop_after_current();
+    });
+
+    let (crawl, ()) = futures::join!(
+        scraper.run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_keep_alive(true)
+                .with_handle(handle.clone()),
+        ),
+        feed
+    );
+    crawl.unwrap();
+
+    assert_eq!(
+        *fetched.read().unwrap(),
+        vec![format!("{}/", base), format!("{}/later", base)]
+    );
+}
@@ -0,0 +1,105 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_lines("text/csv", csv_handler)]
+struct Scraper {
+    lines: Arc<RwLock<Vec<(String, String)>>>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn csv_handler(&self, response: Response, line: String) -> Result<()> {
+        self.lines.write().unwrap().push((response.url, line));
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_lines_streams_csv() {
+    let base = common::serve(vec![
+        ("/", common::Page::html(r#"<a href="/crabs.csv">csv</a>"#)),
+        (
+            "/crabs.csv",
+            common::Page::with_content_type(
+                "text/csv; charset=utf-8",
+                b"name,legs\nhermit,10\n<a href=\"/nope\">x</a>\n",
+            ),
+        ),
+    ])
+    .await;
+
+    let lines = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        lines: lines.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let csv = format!("{}/crabs.csv", base);
+    assert_eq!(
+        *lines.read().unwrap(),
+        vec![
+            (csv.clone(), "name,legs".to_string()),
+            (csv.clone(), "hermit,10".to_string()),
+            (csv, "<a href=\"/nope\">x</a>".to_string()),
+        ]
+    );
+    assert_eq!(handle.report().pages, 2);
+}
+
+#[derive(ImmutableWebScraper)]
+#[on_lines("text/plain", line_handler)]
+struct ImmutableScraper {
+    lines: Arc<RwLock<Vec<String>>>,
+}
+
+impl ImmutableScraper {
+    async fn line_handler(&self, _response: Response, line: String) -> Result<()> {
+        self.lines.write().unwrap().push(line);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_lines_immutable() {
+    let base = common::serve(vec![(
+        "/log.txt",
+        common::Page::with_content_type("text/plain", b"first\nsecond"),
+    )])
+    .await;
+
+    let lines = Arc::new(RwLock::new(vec![]));
+    let scraper = ImmutableScraper {
+        lines: lines.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/log.txt", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(*lines.read().unwrap(), vec!["first", "second"]);
+}
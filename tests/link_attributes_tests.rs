@@ -0,0 +1,83 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_follow_link_attributes() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<img srcset="/small.png 1x, /large.png 2x">
+                <form action="/search"></form>
+                <div data-href="card"></div>
+                <a class="js" href="javascript:void(0)">js</a>
+                <a href="/ignored">not followed, a is not configured</a>"#,
+            ),
+        ),
+        (
+            "/small.png",
+            common::Page::with_content_type("image/png", b"s"),
+        ),
+        (
+            "/large.png",
+            common::Page::with_content_type("image/png", b"l"),
+        ),
+        ("/search", common::Page::html("search")),
+        ("/card", common::Page::html("card")),
+        ("/ignored", common::Page::html("ignored")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_link_attributes(vec![
+                    ("img", "srcset"),
+                    ("form", "action"),
+                    ("div", "data-href"),
+                    ("a.js", "href"),
+                ]),
+        )
+        .await
+        .unwrap();
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        vec![
+            format!("{}/", base),
+            format!("{}/card", base),
+            format!("{}/large.png", base),
+            format!("{}/search", base),
+            format!("{}/small.png", base),
+        ]
+    );
+}
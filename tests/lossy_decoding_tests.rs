@@ -0,0 +1,130 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<String>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("p", paragraph_handler)]
+struct Scraper {
+    seen: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.seen
+            .write()
+            .unwrap()
+            .push(format!("status {}", response.status));
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, _: Response, p: Element) -> Result<()> {
+        self.seen
+            .write()
+            .unwrap()
+            .push(p.text().unwrap_or_default());
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> Vec<String> {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::with_content_type("text/html; charset=utf-8", b"<p>caf\xe9</p>"),
+    )])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper { seen: seen.clone() };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let seen = seen.read().unwrap().clone();
+    seen
+}
+
+#[async_std::test]
+async fn test_invalid_body_fails() {
+    assert_eq!(crawl(Opts::new()).await, vec!["status 500"]);
+}
+
+#[async_std::test]
+async fn test_lossy_decoding() {
+    assert_eq!(
+        crawl(Opts::new().with_lossy_decoding(true)).await,
+        vec!["caf\u{fffd}", "status 200"]
+    );
+}
+
+#[async_std::test]
+async fn test_skip_undecodable_body() {
+    assert_eq!(
+        crawl(Opts::new().with_body_decode_policy(BodyDecodePolicy::Skip)).await,
+        vec!["status 304"]
+    );
+}
+
+#[derive(MutableWebScraper)]
+#[on_stream(stream_handler)]
+#[on_html("p", paragraph_handler)]
+struct BinaryScraper {
+    seen: Seen,
+}
+
+impl BinaryScraper {
+    async fn stream_handler(&self, _: Response, mut body: BodyStream) -> Result<()> {
+        let mut bytes = vec![];
+        while let Some(chunk) = body.next_chunk().await {
+            bytes.extend(chunk);
+        }
+        self.seen
+            .write()
+            .unwrap()
+            .push(format!("bytes {:?}", &bytes[..]));
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, _: Response, p: Element) -> Result<()> {
+        self.seen
+            .write()
+            .unwrap()
+            .push(p.text().unwrap_or_default());
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_binary_undecodable_body() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::with_content_type("text/html; charset=utf-8", b"<p>\xff</p>"),
+    )])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = BinaryScraper { seen: seen.clone() };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_body_decode_policy(BodyDecodePolicy::Binary),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *seen.read().unwrap(),
+        vec![format!("bytes {:?}", b"<p>\xff</p>")]
+    );
+}
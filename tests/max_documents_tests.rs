@@ -0,0 +1,74 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    requests: Arc<Mutex<Vec<String>>>,
+    seen: Arc<RwLock<Vec<usize>>>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        // give idle workers time to fetch whatever they are allowed to
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        self.seen
+            .write()
+            .unwrap()
+            .push(self.requests.lock().unwrap().len());
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> Vec<usize> {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+            ),
+            ("/a".to_string(), common::Page::html("a")),
+            ("/b".to_string(), common::Page::html("b")),
+            ("/c".to_string(), common::Page::html("c")),
+        ]
+    })
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        requests,
+        seen: seen.clone(),
+    };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]).with_threads(4))
+        .await
+        .unwrap();
+
+    let seen = seen.read().unwrap().clone();
+    seen
+}
+
+#[async_std::test]
+async fn test_max_concurrent_documents() {
+    // root page holds the only slot while its handlers run, nothing else is fetched meanwhile
+    let seen = crawl(Opts::new().with_max_concurrent_documents(1)).await;
+    assert_eq!(seen, vec![1, 1, 1]);
+}
+
+#[async_std::test]
+async fn test_unlimited_documents() {
+    let seen = crawl(Opts::new()).await;
+    assert_eq!(seen.last(), Some(&4));
+}
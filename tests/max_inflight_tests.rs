@@ -0,0 +1,60 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> Duration {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/slow/a">a</a><a href="/slow/b">b</a>
+                <a href="/slow/c">c</a><a href="/slow/d">d</a>"#,
+            ),
+        ),
+        ("/slow/a", common::Page::html("a")),
+        ("/slow/b", common::Page::html("b")),
+        ("/slow/c", common::Page::html("c")),
+        ("/slow/d", common::Page::html("d")),
+    ])
+    .await;
+
+    let mut scraper = Scraper {};
+    let started = Instant::now();
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]).with_threads(4))
+        .await
+        .unwrap();
+
+    started.elapsed()
+}
+
+#[async_std::test]
+async fn test_max_inflight() {
+    // one request at a time although four workers are idle
+    let elapsed = crawl(Opts::new().with_max_inflight(1)).await;
+    assert!(elapsed >= Duration::from_millis(800), "{:?}", elapsed);
+}
+
+#[async_std::test]
+async fn test_unlimited_inflight() {
+    let elapsed = crawl(Opts::new()).await;
+    assert!(elapsed < Duration::from_millis(800), "{:?}", elapsed);
+}
@@ -0,0 +1,62 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (String, usize, CrawlReport) {
+    let (base, requests) = common::serve_logged(|_| {
+        let links = (0..5)
+            .map(|n| format!(r#"<a href="/{}">{}</a>"#, n, n))
+            .collect::<String>();
+        let mut pages = vec![("/".to_string(), common::Page::html(&links))];
+        for n in 0..5 {
+            pages.push((format!("/{}", n), common::Page::html("page")));
+        }
+        pages
+    })
+    .await;
+
+    let handle = CrablerHandle::new();
+    Scraper {}
+        .run(
+            opts.with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap().len();
+    (base, requests, handle.report())
+}
+
+#[async_std::test]
+async fn test_all_links_followed_by_default() {
+    let (_, requests, report) = crawl(Opts::new()).await;
+
+    assert_eq!(requests, 6);
+    assert!(report.link_capped.is_empty());
+}
+
+#[async_std::test]
+async fn test_max_links_per_page() {
+    let (base, requests, report) = crawl(Opts::new().with_max_links_per_page(2)).await;
+
+    assert_eq!(requests, 3);
+    assert_eq!(report.link_capped, vec![format!("{}/", base)]);
+}
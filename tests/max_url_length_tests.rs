@@ -0,0 +1,77 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_max_url_length() {
+    let long = format!("/{}.html", "a".repeat(100));
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(&format!(
+                r#"<a href="/short.html">s</a><a href="{}">l</a>"#,
+                long
+            )),
+        ),
+        ("/short.html", common::Page::html("short")),
+        (&long, common::Page::html("long")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_max_url_length(base.len() + 50),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}{}", base, long), 304),
+            (format!("{}/short.html", base), 200),
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+struct Scraper {}
+
+async fn crawl(opts: Opts) -> Vec<String> {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(
+                    r#"<head><meta http-equiv="Refresh" content="0; URL='/moved'"></head>"#,
+                ),
+            ),
+            (
+                "/moved".to_string(),
+                common::Page::html(r#"<meta http-equiv="refresh" content="5;url=/later">"#),
+            ),
+            (
+                "/later".to_string(),
+                common::Page::html(r#"<meta http-equiv="refresh" content="0">"#),
+            ),
+        ]
+    })
+    .await;
+
+    Scraper {}
+        .run(opts.with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap().clone();
+    requests
+}
+
+#[async_std::test]
+async fn test_meta_refresh_not_followed_by_default() {
+    assert_eq!(crawl(Opts::new()).await, vec!["/"]);
+}
+
+#[async_std::test]
+async fn test_follow_meta_refresh() {
+    let requests = crawl(Opts::new().with_follow_meta_refresh(true)).await;
+
+    assert_eq!(requests, vec!["/", "/moved"]);
+}
+
+#[async_std::test]
+async fn test_meta_refresh_max_delay() {
+    let requests = crawl(
+        Opts::new()
+            .with_follow_meta_refresh(true)
+            .with_meta_refresh_max_delay(Duration::from_secs(5)),
+    )
+    .await;
+
+    assert_eq!(requests, vec!["/", "/moved", "/later"]);
+}
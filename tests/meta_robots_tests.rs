@@ -0,0 +1,148 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    indexed: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 && !response.meta_robots.noindex {
+            self.indexed.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_respect_meta_robots() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/hidden">h</a><a href="/closed">c</a>"#),
+        ),
+        (
+            "/hidden",
+            common::Page::html(
+                r#"<html><head><meta name="robots" content="noindex"></head>
+                <body><a href="/leaf">l</a></body></html>"#,
+            ),
+        ),
+        (
+            "/closed",
+            common::Page::html(
+                r#"<html><head><meta name="ROBOTS" content="index, nofollow"></head>
+                <body><a href="/secret">s</a></body></html>"#,
+            ),
+        ),
+        ("/leaf", common::Page::html("leaf")),
+        ("/secret", common::Page::html("secret")),
+    ])
+    .await;
+
+    let indexed = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        indexed: indexed.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_respect_meta_robots(true),
+        )
+        .await
+        .unwrap();
+
+    let mut indexed = indexed.read().unwrap().clone();
+    indexed.sort();
+    assert_eq!(
+        indexed,
+        vec![
+            format!("{}/", base),
+            format!("{}/closed", base),
+            format!("{}/leaf", base),
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_respect_x_robots_tag() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/hidden">h</a><a href="/closed">c</a><a href="/other">o</a>"#,
+            ),
+        ),
+        (
+            "/hidden",
+            common::Page::html(r#"<a href="/leaf">l</a>"#).header("X-Robots-Tag", "noindex"),
+        ),
+        (
+            "/closed",
+            common::Page::html(r#"<a href="/secret">s</a>"#)
+                .header("X-Robots-Tag", "max-snippet: 20")
+                .header("X-Robots-Tag", "crabler: nofollow"),
+        ),
+        (
+            "/other",
+            common::Page::html(r#"<a href="/open">o</a>"#)
+                .header("X-Robots-Tag", "googlebot: noindex, nofollow"),
+        ),
+        ("/leaf", common::Page::html("leaf")),
+        ("/secret", common::Page::html("secret")),
+        ("/open", common::Page::html("open")),
+    ])
+    .await;
+
+    let indexed = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        indexed: indexed.clone(),
+    };
+    let handle = CrablerHandle::new();
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_respect_meta_robots(true)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut indexed = indexed.read().unwrap().clone();
+    indexed.sort();
+    assert_eq!(
+        indexed,
+        vec![
+            format!("{}/", base),
+            format!("{}/closed", base),
+            format!("{}/leaf", base),
+            format!("{}/open", base),
+            format!("{}/other", base),
+        ]
+    );
+    assert_eq!(handle.report().x_robots_tags, 2);
+}
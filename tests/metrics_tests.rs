@@ -0,0 +1,140 @@
+#![cfg(feature = "metrics")]
+
+extern crate crabler;
+
+use async_std::io::{ReadExt, WriteExt};
+use async_std::net::{TcpListener, TcpStream};
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_metrics_prometheus() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/page">p</a><a href="/missing">m</a>"#),
+        ),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base), "http://127.0.0.1:1/"])
+                .with_id("nightly")
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let metrics = handle.metrics_prometheus();
+    let host = base
+        .trim_start_matches("http://")
+        .split(':')
+        .next()
+        .unwrap();
+
+    for line in &[
+        "# TYPE crabler_pages_total counter",
+        "crabler_pages_total{crawl_id=\"nightly\"} 3",
+        "crabler_responses_total{crawl_id=\"nightly\",status=\"200\"} 2",
+        "crabler_responses_total{crawl_id=\"nightly\",status=\"404\"} 1",
+        &format!(
+            "crabler_host_pages_total{{crawl_id=\"nightly\",host=\"{}\"}} 3",
+            host
+        ),
+        "crabler_errors_total{crawl_id=\"nightly\"} 1",
+        "crabler_queue_depth{crawl_id=\"nightly\"} 0",
+        "crabler_requests_in_flight{crawl_id=\"nightly\"} 0",
+        "crabler_connections_opened_total{crawl_id=\"nightly\"} 3",
+        "crabler_connections_reused_total{crawl_id=\"nightly\"} 0",
+        "crabler_tls_handshakes_total{crawl_id=\"nightly\"} 0",
+    ] {
+        assert!(
+            metrics.lines().any(|l| l == *line),
+            "missing {:?} in\n{}",
+            line,
+            metrics
+        );
+    }
+}
+
+/// Serve the same page over connections that are kept alive, unlike `common::serve`
+async fn serve_keep_alive(body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    async_std::task::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            async_std::task::spawn(keep_alive_connection(stream, body));
+        }
+    });
+
+    base
+}
+
+async fn keep_alive_connection(mut stream: TcpStream, body: &str) {
+    let mut request = vec![];
+    let mut buf = [0u8; 1024];
+
+    loop {
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+        request.clear();
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[async_std::test]
+async fn test_connection_reuse() {
+    let base = serve_keep_alive(r#"<a href="/1">1</a><a href="/2">2</a><a href="/3">3</a>"#).await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_threads(1)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let connections = handle.report().connections;
+    assert_eq!(connections.new_connections, 1);
+    assert_eq!(connections.reused_connections, 3);
+    assert_eq!(connections.tls_handshakes, 0);
+    assert_eq!(connections.reuse_rate(), 0.75);
+}
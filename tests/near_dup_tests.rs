@@ -0,0 +1,124 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn article(topic: &str, footer: &str) -> common::Page {
+    let text = (0..200)
+        .map(|i| format!("{}{} ", topic, i))
+        .collect::<String>();
+
+    common::Page::html(&format!(
+        "<html><body><p>{}</p><footer>{}</footer></body></html>",
+        text, footer
+    ))
+}
+
+#[async_std::test]
+async fn test_near_dup_detection() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+        ),
+        ("/a", article("crab", "Posted on Monday")),
+        ("/b", article("crab", "Posted on Tuesday")),
+        ("/c", article("lobster", "Posted on Monday")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_near_dup_detection(3)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let near_duplicates = handle.report().near_duplicates;
+    assert_eq!(near_duplicates.len(), 1);
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    assert_eq!(fetched.len(), 3);
+    assert!(fetched.contains(&format!("{}/c", base)));
+    assert!(!fetched.contains(&near_duplicates[0]));
+}
+
+#[async_std::test]
+async fn test_skip_duplicate_content() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+        ),
+        ("/a", article("crab", "Posted on Monday")),
+        ("/b", article("crab", "Posted on Monday")),
+        ("/c", article("crab", "Posted on Tuesday")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_skip_duplicate_content(true)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let duplicate_content = handle.report().duplicate_content;
+    assert_eq!(duplicate_content.len(), 1);
+    assert!(handle.report().near_duplicates.is_empty());
+
+    let fetched = fetched.read().unwrap().clone();
+    assert_eq!(fetched.len(), 3);
+    assert!(fetched.contains(&format!("{}/c", base)));
+    assert!(!fetched.contains(&duplicate_content[0]));
+}
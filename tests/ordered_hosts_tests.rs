@@ -0,0 +1,68 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses.write().unwrap().push(response.url);
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_ordered_hosts_keep_submission_order() {
+    let paths = (1..=8).map(|i| format!("/{}", i)).collect::<Vec<_>>();
+    let links = paths
+        .iter()
+        .map(|path| format!(r#"<a href="{}">{}</a>"#, path, path))
+        .collect::<String>();
+
+    let mut pages = vec![("/".to_string(), common::Page::html(&links))];
+    // later pages are smaller, so they would tend to finish first without ordering
+    for (i, path) in paths.iter().enumerate() {
+        pages.push((
+            path.clone(),
+            common::Page::html(&"crab ".repeat(20000 / (i + 1))),
+        ));
+    }
+    let base = common::serve_with(move |_| pages).await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_threads(4)
+                .with_ordered_hosts(vec!["127.0.0.1"]),
+        )
+        .await
+        .unwrap();
+
+    let mut expected = vec![format!("{}/", base)];
+    expected.extend(paths.iter().map(|path| format!("{}{}", base, path)));
+    assert_eq!(*responses.read().unwrap(), expected);
+}
@@ -0,0 +1,88 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links.write().unwrap().push(response.url);
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn serve_site(robots: &str) -> String {
+    common::serve(vec![
+        (
+            "/robots.txt",
+            common::Page::with_content_type("text/plain", robots.as_bytes()),
+        ),
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a>"#),
+        ),
+        ("/a", common::Page::html("a")),
+        ("/b", common::Page::html("b")),
+    ])
+    .await
+}
+
+#[async_std::test]
+async fn test_pool_runs_isolated_jobs() {
+    let first_base = serve_site("User-agent: *\nDisallow: /b\n").await;
+    let second_base = serve_site("").await;
+
+    let first_links = Arc::new(RwLock::new(vec![]));
+    let second_links = Arc::new(RwLock::new(vec![]));
+    let mut first = Scraper {
+        base: first_base.clone(),
+        visited_links: first_links.clone(),
+    };
+    let mut second = Scraper {
+        base: second_base.clone(),
+        visited_links: second_links.clone(),
+    };
+
+    let mut pool = CrawlerPool::new(2);
+    pool.add_mutable_job(
+        &mut first,
+        Opts::new()
+            .with_urls(vec![&format!("{}/", first_base)])
+            .with_respect_robots_txt(true)
+            .with_threads(2),
+    );
+    pool.add_mutable_job(
+        &mut second,
+        Opts::new().with_urls(vec![&format!("{}/", second_base)]),
+    );
+
+    let reports = pool.run().await;
+    let reports = reports
+        .into_iter()
+        .map(|report| report.unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(reports[0].skipped_by_robots(), 1);
+    assert!(reports[1].robots.is_empty());
+    assert_eq!(first_links.read().unwrap().len(), 3);
+    assert_eq!(second_links.read().unwrap().len(), 3);
+}
@@ -0,0 +1,39 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn response_handler(&self, _response: Response) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_prefetch_once_per_host_before_crawl() {
+    let (base, requests) =
+        common::serve_logged(|_| vec![("/".to_string(), common::Page::html("seed"))]).await;
+
+    let scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_prefetch(vec![
+                    &format!("{}/warm", base),
+                    &format!("{}/warm-again", base),
+                    // unreachable hosts do not fail the crawl
+                    "http://127.0.0.1:1/",
+                ]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(*requests.lock().unwrap(), vec!["/warm", "/"]);
+}
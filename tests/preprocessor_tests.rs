@@ -0,0 +1,49 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, _response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            self.links.write().unwrap().push(href);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_html_preprocessor() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(r#"<a href="/visible">v</a><!-- <a href="/commented">c</a> -->"#),
+    )])
+    .await;
+
+    let links = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        links: links.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_html_preprocessor(|html| html.replace("<!--", "").replace("-->", "")),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(*links.read().unwrap(), vec!["/visible", "/commented"]);
+}
@@ -0,0 +1,110 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Arc<RwLock<Vec<(u16, RedirectChain)>>>,
+    links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.status, response.redirect_chain));
+
+        Ok(())
+    }
+
+    async fn link_handler(&self, response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        self.links
+            .write()
+            .unwrap()
+            .push(response.resolve_url(&href));
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (String, Vec<(u16, RedirectChain)>, Vec<String>) {
+    let base = common::serve(vec![
+        (
+            "/short",
+            common::Page::html("")
+                .status(301)
+                .header("Location", "/hop"),
+        ),
+        (
+            "/hop",
+            common::Page::html("")
+                .status(302)
+                .header("Location", "/articles/final"),
+        ),
+        (
+            "/articles/final",
+            common::Page::html(r#"<a href="next">next</a>"#),
+        ),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let links = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+        links: links.clone(),
+    };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/short", base)]))
+        .await
+        .unwrap();
+
+    let responses = responses.read().unwrap().clone();
+    let links = links.read().unwrap().clone();
+    (base, responses, links)
+}
+
+#[async_std::test]
+async fn test_redirects_not_followed_by_default() {
+    let (_, responses, links) = crawl(Opts::new()).await;
+
+    assert_eq!(responses, vec![(301, vec![])]);
+    assert!(links.is_empty());
+}
+
+#[async_std::test]
+async fn test_redirect_chain() {
+    let (base, responses, links) = crawl(Opts::new().with_max_redirects(5)).await;
+
+    assert_eq!(
+        responses,
+        vec![(
+            200,
+            vec![
+                (format!("{}/short", base), 301),
+                (format!("{}/hop", base), 302),
+                (format!("{}/articles/final", base), 200),
+            ]
+        )]
+    );
+    assert_eq!(links, vec![format!("{}/articles/next", base)]);
+}
+
+#[async_std::test]
+async fn test_max_redirects() {
+    let (_, responses, _) = crawl(Opts::new().with_max_redirects(1)).await;
+
+    let (status, chain) = &responses[0];
+    assert_eq!(responses.len(), 1);
+    assert_eq!(*status, 302);
+    assert_eq!(chain.len(), 2);
+}
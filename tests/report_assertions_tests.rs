@@ -0,0 +1,76 @@
+#![cfg(feature = "testing")]
+
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html("table", table_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn table_handler(&self, _response: Response, _table: Element) -> Result<()> {
+        Ok(())
+    }
+}
+
+async fn crawl(links: &str) -> (String, CrawlReport) {
+    let base = common::serve(vec![
+        ("/", common::Page::html(links)),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    (base, handle.report())
+}
+
+#[async_std::test]
+async fn test_report_assertions() {
+    let (base, report) = crawl(r#"<a href="/page">p</a>"#).await;
+
+    report
+        .assert_no_errors()
+        .assert_visited(&format!("{}/", base))
+        .assert_visited(&format!("{}/page", base))
+        .assert_not_visited(&format!("{}/other", base))
+        .assert_selector_matched("a[href]");
+}
+
+#[async_std::test]
+#[should_panic(expected = "expected selector \"table\" to match")]
+async fn test_assert_selector_matched_fails() {
+    let (_, report) = crawl(r#"<a href="/page">p</a>"#).await;
+
+    report.assert_selector_matched("table");
+}
+
+#[async_std::test]
+#[should_panic(expected = "expected no errors")]
+async fn test_assert_no_errors_fails() {
+    let (_, report) = crawl(r#"<a href="http://127.0.0.1:1/">down</a>"#).await;
+
+    report.assert_no_errors();
+}
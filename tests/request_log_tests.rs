@@ -0,0 +1,155 @@
+extern crate crabler;
+
+use crabler::*;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(ImmutableWebScraper)]
+#[on_html("p", paragraph_handler)]
+struct Extractor {
+    paragraphs: Arc<Mutex<Vec<String>>>,
+}
+
+impl Extractor {
+    async fn paragraph_handler(&self, response: Response, p: Element) -> Result<()> {
+        self.paragraphs.lock().unwrap().push(format!(
+            "{} {}",
+            response.url.rsplit('/').next().unwrap_or_default(),
+            p.text().unwrap_or_default()
+        ));
+
+        Ok(())
+    }
+}
+
+fn read_log(path: &str) -> Vec<Value> {
+    let mut entries = std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry["url"].as_str().unwrap().to_string());
+
+    entries
+}
+
+#[async_std::test]
+async fn test_request_log() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/page">p</a><a href="http://127.0.0.1:1/">x</a>"#),
+        ),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+    let dir = std::env::temp_dir().join(format!("crabler-request-log-{}", std::process::id()));
+    let cache_dir = dir.join("cache");
+    let log = dir.join("requests.jsonl");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let opts = Opts::new()
+        .with_urls(vec![&format!("{}/", base)])
+        .with_cache_dir(cache_dir.to_str().unwrap())
+        .with_request_log(log.to_str().unwrap());
+    Scraper {}.run(opts.clone()).await.unwrap();
+
+    let entries = read_log(log.to_str().unwrap());
+    assert_eq!(entries.len(), 3);
+
+    assert_eq!(entries[0]["url"], "http://127.0.0.1:1/");
+    assert_eq!(entries[0]["status"], Value::Null);
+    assert!(entries[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("network error"));
+
+    assert_eq!(entries[1]["url"], format!("{}/", base));
+    assert_eq!(entries[1]["method"], "GET");
+    assert_eq!(entries[1]["status"], 200);
+    assert_eq!(entries[1]["from_cache"], false);
+    assert!(entries[1]["duration_ms"].is_u64());
+    assert!(entries[1]["timestamp"].as_str().unwrap().ends_with('Z'));
+
+    assert_eq!(entries[2]["url"], format!("{}/page", base));
+    assert_eq!(entries[2]["bytes"], 4);
+    assert_eq!(entries[2]["error"], Value::Null);
+
+    // second run is served from the cache and appended to the same log
+    Scraper {}.run(opts).await.unwrap();
+
+    let entries = read_log(log.to_str().unwrap());
+    assert_eq!(entries.len(), 6);
+    assert_eq!(
+        entries
+            .iter()
+            .filter(|entry| entry["from_cache"] == true)
+            .count(),
+        2
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[async_std::test]
+async fn test_replay_from_request_log() {
+    let (base, requests) = common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(r#"<p>root</p><a href="/page">p</a>"#),
+            ),
+            ("/page".to_string(), common::Page::html("<p>page</p>")),
+        ]
+    })
+    .await;
+    let dir = std::env::temp_dir().join(format!("crabler-replay-{}", std::process::id()));
+    let log = dir.join("requests.jsonl");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    Scraper {}
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_request_log(log.to_str().unwrap())
+                .with_request_log_bodies(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    // different extraction logic runs against the capture, without following links
+    let paragraphs = Arc::new(Mutex::new(vec![]));
+    Extractor {
+        paragraphs: paragraphs.clone(),
+    }
+    .run(Opts::new().with_replay_from(log.to_str().unwrap()))
+    .await
+    .unwrap();
+
+    let mut paragraphs = paragraphs.lock().unwrap().clone();
+    paragraphs.sort();
+    assert_eq!(paragraphs, vec![" root", "page page"]);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "testing")]
+
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html("h1", title_handler)]
+struct Scraper {
+    titles: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn title_handler(&self, response: Response, h1: Element) -> Result<()> {
+        if response.status == 200 {
+            let title = h1.text().unwrap_or_default();
+            let context = response.context.unwrap_or_default();
+            self.titles
+                .write()
+                .unwrap()
+                .push(format!("{} in {}", title, context));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_link_handler() {
+    let scraper = Scraper {
+        titles: Arc::default(),
+    };
+    let response = Response::test_builder()
+        .with_url("http://example.com/docs/")
+        .with_body(r#"<a href="intro">intro</a><a href="http://other.com/">other</a>"#)
+        .build();
+
+    for a in response.select_all("a") {
+        scraper.link_handler(response.clone(), a).await.unwrap();
+    }
+
+    assert_eq!(
+        response.handle().take_queued_urls(),
+        vec!["http://example.com/docs/intro", "http://other.com/"]
+    );
+    assert!(response.handle().take_queued_urls().is_empty());
+}
+
+#[async_std::test]
+async fn test_title_handler() {
+    let titles = Arc::new(RwLock::new(vec![]));
+    let scraper = Scraper {
+        titles: titles.clone(),
+    };
+
+    let response = Response::test_builder()
+        .with_header("Content-Type", "text/html; charset=utf-8")
+        .with_body("<h1>Crabs</h1>")
+        .with_context("listing")
+        .build();
+    assert_eq!(response.content_type(), Some("text/html"));
+    let h1 = response.select_one("h1").unwrap();
+    scraper.title_handler(response, h1).await.unwrap();
+
+    let missing = Response::test_builder()
+        .with_status(404)
+        .with_body("<h1>Not found</h1>")
+        .build();
+    let h1 = missing.select_one("h1").unwrap();
+    scraper.title_handler(missing, h1).await.unwrap();
+
+    assert_eq!(*titles.read().unwrap(), vec!["Crabs in listing"]);
+}
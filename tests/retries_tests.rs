@@ -0,0 +1,133 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_retries() {
+    let base = common::serve(vec![("/flaky", common::Page::html("back"))]).await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/flaky", base)])
+                .with_handle(handle.clone())
+                .with_retries(1)
+                .with_retry_backoff(Duration::from_millis(10)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        responses.read().unwrap().clone(),
+        vec![(format!("{}/flaky", base), 200)]
+    );
+    assert_eq!(handle.report().errors.len(), 0);
+}
+
+#[async_std::test]
+async fn test_per_url_deadline() {
+    let responses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    // nothing listens on port 1, every attempt fails right away
+    let started = Instant::now();
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec!["http://127.0.0.1:1/"])
+                .with_handle(handle.clone())
+                .with_retries(5)
+                .with_retry_backoff(Duration::from_millis(200))
+                .with_per_url_deadline(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(
+        responses.read().unwrap().clone(),
+        vec![("http://127.0.0.1:1/".to_string(), 500)]
+    );
+    assert_eq!(handle.report().errors.len(), 1);
+}
+
+#[async_std::test]
+async fn test_should_retry() {
+    let base = common::serve(vec![("/flaky", common::Page::html("back"))]).await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let calls = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    let seen_calls = calls.clone();
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/flaky", base)])
+                .with_retries(3)
+                .with_retry_backoff(Duration::from_millis(10))
+                .with_should_retry(move |url, error, retries| {
+                    seen_calls.write().unwrap().push((
+                        url.path().to_string(),
+                        error.is_retryable(),
+                        retries,
+                    ));
+                    false
+                }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        responses.read().unwrap().clone(),
+        vec![(format!("{}/flaky", base), 500)]
+    );
+    assert_eq!(
+        calls.read().unwrap().clone(),
+        vec![("/flaky".to_string(), true, 0)]
+    );
+}
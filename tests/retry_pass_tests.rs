@@ -0,0 +1,92 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> (String, Vec<(String, u16)>, CrawlReport) {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/flaky">f</a><a href="/page">p</a>"#),
+        ),
+        ("/flaky", common::Page::html("back")),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            opts.with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    (base, responses, handle.report())
+}
+
+#[async_std::test]
+async fn test_no_retry_by_default() {
+    let (base, responses, report) = crawl(Opts::new()).await;
+
+    assert!(responses.contains(&(format!("{}/flaky", base), 500)));
+    assert!(!responses.contains(&(format!("{}/flaky", base), 200)));
+    assert_eq!(report.retried, 0);
+}
+
+#[async_std::test]
+async fn test_final_retry_pass_recovers() {
+    let (base, responses, report) = crawl(Opts::new().with_final_retry_pass(true)).await;
+
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/flaky", base), 200),
+            (format!("{}/flaky", base), 500),
+            (format!("{}/page", base), 200),
+        ]
+    );
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.retried, 1);
+    assert_eq!(report.recovered, 1);
+}
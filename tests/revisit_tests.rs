@@ -0,0 +1,73 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    visits: Arc<RwLock<usize>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            *self.visits.write().unwrap() += 1;
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        // html handlers run before the response handler counts the page,
+        // so the third page sees two visits and ends the crawl
+        if *self.visits.read().unwrap() >= 2 {
+            return Ok(());
+        }
+
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> usize {
+    let base = common::serve(vec![
+        ("/", common::Page::html(r#"<a href="/back">back</a>"#)),
+        ("/back", common::Page::html(r#"<a href="/">again</a>"#)),
+    ])
+    .await;
+
+    let visits = Arc::new(RwLock::new(0));
+    let mut scraper = Scraper {
+        visits: visits.clone(),
+    };
+
+    scraper
+        .run(opts.with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    let visits = *visits.read().unwrap();
+    visits
+}
+
+#[async_std::test]
+async fn test_visited_only_once_by_default() {
+    assert_eq!(crawl(Opts::new()).await, 2);
+}
+
+#[async_std::test]
+async fn test_revisit_after() {
+    assert_eq!(
+        crawl(Opts::new().with_revisit_after(Duration::ZERO)).await,
+        3
+    );
+}
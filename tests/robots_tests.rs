@@ -0,0 +1,96 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    fetched: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.fetched.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_robots_rules() {
+    let rules = RobotsRules::parse(
+        "User-agent: *\nDisallow: /\n\nUser-agent: crabler\nDisallow: /private\nAllow: /private/ok\nDisallow: /*.pdf$\n",
+        ROBOTS_USER_AGENT,
+    );
+
+    assert!(rules.is_allowed("/public"));
+    assert!(!rules.is_allowed("/private/secret"));
+    assert!(rules.is_allowed("/private/ok/page"));
+    assert!(!rules.is_allowed("/files/report.pdf"));
+    assert!(rules.is_allowed("/files/report.pdf?download=1"));
+}
+
+#[async_std::test]
+async fn test_respect_robots_txt() {
+    let base = common::serve(vec![
+        (
+            "/robots.txt",
+            common::Page::with_content_type("text/plain", b"User-agent: *\nDisallow: /private\n"),
+        ),
+        (
+            "/",
+            common::Page::html(r#"<a href="/public">a</a><a href="/private">b</a>"#),
+        ),
+        ("/public", common::Page::html("public")),
+        ("/private", common::Page::html("private")),
+    ])
+    .await;
+
+    let fetched = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        fetched: fetched.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_respect_robots_txt(true)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut fetched = fetched.read().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        vec![format!("{}/", base), format!("{}/public", base)]
+    );
+
+    let report = handle.report();
+    assert!(report.honored_robots);
+    assert_eq!(report.skipped_by_robots(), 1);
+    assert_eq!(
+        report.robots["127.0.0.1"].skipped_urls,
+        vec![format!("{}/private", base)]
+    );
+}
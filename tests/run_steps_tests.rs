@@ -0,0 +1,65 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    visited: Vec<String>,
+}
+
+impl Scraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.visited.push(response.url);
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_run_steps() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+        ),
+        ("/a", common::Page::html("a")),
+        ("/b", common::Page::html("b")),
+        ("/c", common::Page::html("c")),
+    ])
+    .await;
+
+    let mut scraper = Scraper { visited: vec![] };
+    let mut crabler = MutableCrabler::with_opts(
+        &mut scraper,
+        Opts::new().with_urls(vec![&format!("{}/", base)]),
+    );
+    crabler.start().await.unwrap();
+
+    let mut steps = vec![];
+    loop {
+        let progress = crabler.run_steps(1).await.unwrap();
+        steps.push(progress.processed);
+        if progress.finished {
+            break;
+        }
+    }
+    assert_eq!(steps, vec![1, 1, 1, 1]);
+
+    let progress = crabler.run_steps(1).await.unwrap();
+    assert_eq!(progress.processed, 0);
+    assert!(progress.finished);
+
+    drop(crabler);
+    assert_eq!(scraper.visited.len(), 4);
+}
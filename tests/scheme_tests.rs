@@ -0,0 +1,86 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn crawl(opts: Opts) -> CrawlReport {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/page">page</a><a href="mailto:a@example.com">mail</a>
+                <a href="mailto:b@example.com">mail</a><a href="tel:+123">tel</a>
+                <a href="ftp://example.com/file">ftp</a>"#,
+            ),
+        ),
+        ("/page", common::Page::html("page")),
+    ])
+    .await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            opts.with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    handle.report()
+}
+
+#[async_std::test]
+async fn test_other_schemes_fail() {
+    let report = crawl(Opts::new()).await;
+    assert_eq!(report.pages, 2);
+    assert_eq!(report.errors.len(), 4);
+}
+
+#[async_std::test]
+async fn test_block_non_http_schemes() {
+    let report = crawl(Opts::new().with_block_non_http_schemes(true)).await;
+    assert_eq!(report.pages, 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(report.scheme_blocked["mailto"], 2);
+    assert_eq!(report.scheme_blocked["tel"], 1);
+    assert_eq!(report.scheme_blocked["ftp"], 1);
+}
+
+#[async_std::test]
+async fn test_require_https() {
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec!["http://example.com/"])
+                .with_require_https(true)
+                .with_offline(true)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    // offline crawl fails every fetch, error shows which url was requested
+    let report = handle.report();
+    assert_eq!(report.https_upgrades, 1);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, "https://example.com/");
+}
@@ -0,0 +1,77 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_scope_to_seed_path() {
+    let base = common::serve(vec![
+        (
+            "/docs/guide/intro.html",
+            common::Page::html(
+                r#"<a href="setup.html">s</a><a href="/docs/api.html">a</a><a href="/">h</a>"#,
+            ),
+        ),
+        ("/docs/guide/setup.html", common::Page::html("setup")),
+        ("/docs/api.html", common::Page::html("api")),
+        ("/", common::Page::html("home")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/docs/guide/intro.html", base)])
+                .with_scope_to_seed_path(true),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 304),
+            (format!("{}/docs/api.html", base), 304),
+            (format!("{}/docs/guide/intro.html", base), 200),
+            (format!("{}/docs/guide/setup.html", base), 200),
+        ]
+    );
+}
@@ -0,0 +1,68 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(Option<String>, Vec<String>)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    seen: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        let title = response.select_one("h1").and_then(|h1| h1.text());
+        let links = response
+            .select_all(r#"ul.links > li a[href^="/item"]"#)
+            .iter()
+            .filter_map(|a| a.attr("href"))
+            .collect();
+
+        self.seen.write().unwrap().push((title, links));
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_select_on_response() {
+    let base = common::serve(vec![(
+        "/",
+        common::Page::html(
+            r#"<h1>Title</h1>
+                <ul class="links">
+                    <li><a href="/item/1">1</a></li>
+                    <li><a href="/other">o</a></li>
+                    <li><span><a href="/item/2">2</a></span></li>
+                </ul>
+                <a href="/item/3">outside</a>"#,
+        ),
+    )])
+    .await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper { seen: seen.clone() };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base), &format!("{}/missing", base)]))
+        .await
+        .unwrap();
+
+    let mut seen = seen.read().unwrap().clone();
+    seen.sort();
+    assert_eq!(
+        seen,
+        vec![
+            (None, vec![]),
+            (
+                Some("Title".to_string()),
+                vec!["/item/1".to_string(), "/item/2".to_string()]
+            ),
+        ]
+    );
+}
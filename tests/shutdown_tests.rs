@@ -0,0 +1,61 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        if response.url.ends_with("/0") {
+            return Err(CrablerError::Parse("handler failed".to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_workers_stop_when_crawl_fails() {
+    let (base, requests) = common::serve_logged(|_| {
+        let links = (0..20)
+            .map(|n| format!(r#"<a href="/{}">{}</a>"#, n, n))
+            .collect::<String>();
+        let mut pages = vec![("/".to_string(), common::Page::html(&links))];
+        for n in 0..20 {
+            pages.push((format!("/{}", n), common::Page::html("page")));
+        }
+        pages
+    })
+    .await;
+
+    let scraper = Scraper {};
+    let result = scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_threads(1)
+                .with_worker_restart_backoff(Duration::ZERO),
+        )
+        .await;
+    assert!(result.is_err());
+
+    // work already in-flight may finish, queued work is not fetched for a crawl that is over
+    let fetched = requests.lock().unwrap().len();
+    async_std::task::sleep(Duration::from_millis(200)).await;
+    assert!(requests.lock().unwrap().len() <= fetched + 1);
+}
@@ -0,0 +1,118 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct Scraper {
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links.write().unwrap().push(response.url);
+        Ok(())
+    }
+}
+
+fn xml(body: String) -> common::Page {
+    common::Page::with_content_type("application/xml", body.as_bytes())
+}
+
+#[async_std::test]
+async fn test_nested_sitemaps() {
+    let base = common::serve_with(|base| {
+        let index = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>{base}/pages.xml</loc></sitemap>
+                <sitemap><loc>{base}/broken.xml</loc></sitemap>
+                <sitemap><loc>{base}/nested.xml</loc></sitemap>
+            </sitemapindex>"#,
+            base = base
+        );
+        let nested = format!(
+            r#"<sitemapindex><sitemap><loc>{base}/deep.xml</loc></sitemap></sitemapindex>"#,
+            base = base
+        );
+        let pages = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <url><loc>{base}/a</loc></url>
+                <url><loc>{base}/b</loc></url>
+            </urlset>"#,
+            base = base
+        );
+        let deep = format!(
+            r#"<urlset><url><loc>{base}/c</loc></url></urlset>"#,
+            base = base
+        );
+
+        vec![
+            ("/sitemap.xml".to_string(), xml(index)),
+            ("/nested.xml".to_string(), xml(nested)),
+            ("/pages.xml".to_string(), xml(pages)),
+            ("/deep.xml".to_string(), xml(deep)),
+            ("/broken.xml".to_string(), common::Page::html("<p>nope</p>")),
+            ("/a".to_string(), common::Page::html("a")),
+            ("/b".to_string(), common::Page::html("b")),
+            ("/c".to_string(), common::Page::html("c")),
+        ]
+    })
+    .await;
+
+    let visited_links = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        visited_links: visited_links.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_sitemaps(vec![&format!("{}/sitemap.xml", base)])
+                .with_sitemap_max_depth(1),
+        )
+        .await
+        .unwrap();
+
+    let mut visited = visited_links.read().unwrap().clone();
+    visited.sort();
+    assert_eq!(visited, vec![format!("{}/a", base), format!("{}/b", base)]);
+}
+
+#[async_std::test]
+async fn test_protocol_relative_sitemap_locations() {
+    let base = common::serve_with(|base| {
+        let host = base.trim_start_matches("http:");
+        let pages = format!(
+            r#"<urlset><url><loc>{host}/a</loc></url><url><loc>/b</loc></url></urlset>"#,
+            host = host
+        );
+
+        vec![
+            ("/sitemap.xml".to_string(), xml(pages)),
+            ("/a".to_string(), common::Page::html("a")),
+            ("/b".to_string(), common::Page::html("b")),
+        ]
+    })
+    .await;
+
+    let visited_links = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        visited_links: visited_links.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_sitemaps(vec![&format!("{}/sitemap.xml", base)]))
+        .await
+        .unwrap();
+
+    let mut visited = visited_links.read().unwrap().clone();
+    visited.sort();
+    assert_eq!(visited, vec![format!("{}/a", base), format!("{}/b", base)]);
+}
@@ -0,0 +1,68 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_snapshot_callback() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/slow/a">a</a><a href="/slow/b">b</a><a href="/slow/c">c</a>"#,
+            ),
+        ),
+        ("/slow/a", common::Page::html("a")),
+        ("/slow/b", common::Page::html("b")),
+        ("/slow/c", common::Page::html("c")),
+    ])
+    .await;
+
+    let snapshots = Arc::new(Mutex::new(vec![]));
+    let received = snapshots.clone();
+    let mut scraper = Scraper {};
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_threads(1)
+                .with_snapshot_callback(Duration::from_millis(100), move |snapshot| {
+                    received.lock().unwrap().push((
+                        snapshot.elapsed,
+                        snapshot.pending,
+                        snapshot.visited,
+                        snapshot.report.pages,
+                    ));
+                }),
+        )
+        .await
+        .unwrap();
+
+    // three slow pages fetched one by one take 600ms
+    let snapshots = snapshots.lock().unwrap().clone();
+    assert!(snapshots.len() >= 3, "{:?}", snapshots);
+    assert!(snapshots
+        .windows(2)
+        .all(|w| w[0].0 < w[1].0 && w[0].2 <= w[1].2));
+    assert!(snapshots[0].1 > 0);
+    assert!(snapshots.iter().all(|s| s.2 == s.3 && s.2 <= 4));
+}
@@ -0,0 +1,80 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    statuses: Arc<RwLock<Vec<(String, u16)>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.statuses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_soft_404_detection() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/product">p</a><a href="/gone">g</a>"#),
+        ),
+        ("/product", common::Page::html("<h1>Crab plushie</h1>")),
+        (
+            "/gone",
+            common::Page::html(
+                r#"<h1 class="not-found">Page not found</h1><a href="/hidden">h</a>"#,
+            ),
+        ),
+        ("/hidden", common::Page::html("hidden")),
+    ])
+    .await;
+
+    let statuses = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        statuses: statuses.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_soft_404_detection(|_, page| !page.select(".not-found").is_empty())
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut statuses = statuses.read().unwrap().clone();
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/gone", base), 404),
+            (format!("{}/product", base), 200),
+        ]
+    );
+    assert_eq!(handle.report().soft_404s, 1);
+}
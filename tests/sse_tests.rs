@@ -0,0 +1,79 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<SseEvent>>>;
+
+#[derive(MutableWebScraper)]
+#[on_sse(sse_handler)]
+struct Scraper {
+    events: Seen,
+}
+
+impl Scraper {
+    async fn sse_handler(&self, _: Response, event: SseEvent) -> Result<()> {
+        self.events.write().unwrap().push(event);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_sse() {
+    let body = ": keep-alive\n\
+                \n\
+                data: first\n\
+                \n\
+                event: update\r\n\
+                id: 7\r\n\
+                data: line one\r\n\
+                data:line two\r\n\
+                \r\n\
+                retry: 3000\n\
+                data: {\"n\": 3}\n\
+                \n\
+                data: never finished\n";
+    let base = common::serve(vec![(
+        "/events",
+        common::Page::with_content_type("text/event-stream", body.as_bytes()),
+    )])
+    .await;
+
+    let events = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        events: events.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/events", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        events.read().unwrap().clone(),
+        vec![
+            SseEvent {
+                event: "message".to_string(),
+                data: "first".to_string(),
+                id: None,
+                retry: None,
+            },
+            SseEvent {
+                event: "update".to_string(),
+                data: "line one\nline two".to_string(),
+                id: Some("7".to_string()),
+                retry: None,
+            },
+            SseEvent {
+                event: "message".to_string(),
+                data: "{\"n\": 3}".to_string(),
+                id: Some("7".to_string()),
+                retry: Some(3000),
+            },
+        ]
+    );
+}
@@ -0,0 +1,163 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    stop: bool,
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links
+            .write()
+            .unwrap()
+            .push(response.url.clone());
+
+        if self.stop {
+            response.handle().stop_after_current();
+        }
+
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_resume_from_state_file() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+        ),
+        ("/a", common::Page::html("a")),
+        ("/b", common::Page::html("b")),
+        ("/c", common::Page::html("c")),
+    ])
+    .await;
+
+    let state_file =
+        std::env::temp_dir().join(format!("crabler-state-{}.json", std::process::id()));
+    let state_file = state_file.to_string_lossy().to_string();
+    let _ = std::fs::remove_file(&state_file);
+    let seed = format!("{}/", base);
+
+    let first_run = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop: true,
+        visited_links: first_run.clone(),
+    };
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&seed])
+                .with_state_file(&state_file),
+        )
+        .await
+        .unwrap();
+
+    let second_run = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop: false,
+        visited_links: second_run.clone(),
+    };
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&seed])
+                .with_state_file(&state_file),
+        )
+        .await
+        .unwrap();
+
+    let _ = std::fs::remove_file(&state_file);
+
+    let mut fetched = first_run.read().unwrap().clone();
+    fetched.extend(second_run.read().unwrap().iter().cloned());
+    fetched.retain(|url| url != &seed);
+    fetched.sort();
+    fetched.dedup();
+
+    assert_eq!(
+        fetched,
+        vec![
+            format!("{}/a", base),
+            format!("{}/b", base),
+            format!("{}/c", base)
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_crawl_id_in_state_file_and_report() {
+    let base = common::serve(vec![("/", common::Page::html("home"))]).await;
+
+    let id = format!("run-{}", std::process::id());
+    let state_file = std::env::temp_dir().join("crabler-state-{id}.json");
+    let expected_file = std::env::temp_dir().join(format!("crabler-state-{}.json", id));
+    let _ = std::fs::remove_file(&expected_file);
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop: false,
+        visited_links: Arc::new(RwLock::new(vec![])),
+    };
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_id(&id)
+                .with_state_file(&state_file.to_string_lossy())
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(handle.report().crawl_id, Some(id));
+    assert!(expected_file.exists());
+    let _ = std::fs::remove_file(&expected_file);
+}
+
+#[cfg(feature = "crawl-id")]
+#[async_std::test]
+async fn test_generated_crawl_id() {
+    let base = common::serve(vec![("/", common::Page::html("home"))]).await;
+
+    let handle = CrablerHandle::new();
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop: false,
+        visited_links: Arc::new(RwLock::new(vec![])),
+    };
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    let id = handle.report().crawl_id.unwrap();
+    assert_eq!(id.len(), 36);
+    assert_eq!(id.matches('-').count(), 4);
+}
@@ -0,0 +1,143 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    base: String,
+    stop_before_navigate: bool,
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links
+            .write()
+            .unwrap()
+            .push(response.url.clone());
+        response.handle().stop_after_current();
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if self.stop_before_navigate {
+            response.handle().stop_after_current();
+        }
+
+        if let Some(href) = a.attr("href") {
+            response.navigate(format!("{}{}", self.base, href)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn serve_links() -> String {
+    let links = (0..20)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a>", i, i))
+        .collect::<String>();
+    let mut pages = vec![("/", common::Page::html(&links))];
+    let paths = (0..20).map(|i| format!("/page/{}", i)).collect::<Vec<_>>();
+    for path in &paths {
+        pages.push((path.as_str(), common::Page::html("leaf")));
+    }
+
+    common::serve(pages).await
+}
+
+#[async_std::test]
+async fn test_stop_makes_navigate_noop() {
+    let base = serve_links().await;
+    let visited_links = Arc::new(RwLock::new(vec![]));
+
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop_before_navigate: true,
+        visited_links: visited_links.clone(),
+    };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(*visited_links.read().unwrap(), vec![format!("{}/", base)]);
+}
+
+#[async_std::test]
+async fn test_stop_drains_queued_work() {
+    let base = serve_links().await;
+    let visited_links = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+
+    let mut scraper = Scraper {
+        base: base.clone(),
+        stop_before_navigate: false,
+        visited_links: visited_links.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert!(handle.is_stopping());
+    assert!(visited_links.read().unwrap().len() < 21);
+}
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+struct AbortingScraper {
+    visited_links: Arc<RwLock<Vec<String>>>,
+}
+
+impl AbortingScraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.visited_links
+            .write()
+            .unwrap()
+            .push(response.url.clone());
+        response.abort_crawl("found it");
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_abort_skips_in_flight_work() {
+    let base = serve_links().await;
+    let visited_links = Arc::new(RwLock::new(vec![]));
+    let handle = CrablerHandle::new();
+    let urls = (0..20)
+        .map(|i| format!("{}/page/{}", base, i))
+        .collect::<Vec<_>>();
+
+    let mut scraper = AbortingScraper {
+        visited_links: visited_links.clone(),
+    };
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(urls.iter().map(String::as_str).collect())
+                .with_threads(4)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    // in-flight pages finish downloading, but only the first one is handled
+    assert_eq!(visited_links.read().unwrap().len(), 1);
+    assert!(handle.is_aborted());
+    assert_eq!(handle.report().aborted, Some("found it".to_string()));
+}
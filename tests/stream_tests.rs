@@ -0,0 +1,111 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<String>>>;
+
+#[derive(MutableWebScraper)]
+#[on_stream(stream_handler)]
+#[on_html("p", paragraph_handler)]
+struct Scraper {
+    seen: Seen,
+}
+
+impl Scraper {
+    async fn stream_handler(&self, response: Response, mut body: BodyStream) -> Result<()> {
+        // stop reading once the title is there, rest of the page keeps downloading
+        let mut head = String::new();
+        while !head.contains("</title>") {
+            match body.next_chunk().await {
+                Some(chunk) => head.push_str(&String::from_utf8_lossy(&chunk)),
+                None => break,
+            }
+        }
+
+        let title = head
+            .split("<title>")
+            .nth(1)
+            .and_then(|rest| rest.split("</title>").next())
+            .unwrap_or_default();
+        self.seen
+            .write()
+            .unwrap()
+            .push(format!("stream {} {}", response.status, title));
+        Ok(())
+    }
+
+    async fn paragraph_handler(&self, _: Response, p: Element) -> Result<()> {
+        self.seen
+            .write()
+            .unwrap()
+            .push(format!("html {}", p.text().unwrap_or_default().len()));
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_stream() {
+    let body = format!(
+        "<html><head><title>Big page</title></head><body><p>{}</p></body></html>",
+        "x".repeat(2_000_000)
+    );
+    let base = common::serve(vec![("/", common::Page::html(&body))]).await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper { seen: seen.clone() };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        seen.read().unwrap().clone(),
+        vec![
+            "stream 200 Big page".to_string(),
+            "html 2000000".to_string()
+        ]
+    );
+}
+
+#[derive(MutableWebScraper)]
+#[on_stream(reader_handler)]
+struct ReaderScraper {
+    seen: Seen,
+}
+
+impl ReaderScraper {
+    async fn reader_handler(&self, _: Response, body: BodyStream) -> Result<()> {
+        use futures::io::AsyncBufReadExt;
+        use futures::stream::StreamExt;
+
+        let lines: Vec<String> = body
+            .into_reader()
+            .lines()
+            .filter_map(|line| async { line.ok() })
+            .collect()
+            .await;
+        self.seen.write().unwrap().extend(lines);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_stream_reader() {
+    let base = common::serve(vec![("/", common::Page::html("one\ntwo\nthree"))]).await;
+
+    let seen = Arc::new(RwLock::new(vec![]));
+    let mut scraper = ReaderScraper { seen: seen.clone() };
+
+    scraper
+        .run(Opts::new().with_urls(vec![&format!("{}/", base)]))
+        .await
+        .unwrap();
+
+    assert_eq!(seen.read().unwrap().clone(), vec!["one", "two", "three"]);
+}
@@ -0,0 +1,90 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[macro_use]
+mod common;
+
+#[derive(ImmutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    signal: Arc<AtomicBool>,
+}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if response.url.ends_with("/3") {
+            self.signal.store(true, Ordering::SeqCst);
+        }
+
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Crawl a chain of pages `/0` -> `/1` -> ... -> `/9`, returns pages fetched
+async fn crawl(termination: TerminationCondition, signal: Arc<AtomicBool>) -> usize {
+    let base = common::serve_with(|_| {
+        (0..10)
+            .map(|n| {
+                (
+                    format!("/{}", n),
+                    common::Page::html(&format!(r#"<a href="/{}">next</a>"#, n + 1)),
+                )
+            })
+            .collect()
+    })
+    .await;
+
+    let handle = CrablerHandle::new();
+    Scraper { signal }
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/0", base)])
+                .with_threads(1)
+                .with_termination(termination)
+                .with_handle(handle.clone()),
+        )
+        .await
+        .unwrap();
+
+    handle.report().pages
+}
+
+#[async_std::test]
+async fn test_queue_empty_by_default() {
+    let pages = crawl(TerminationCondition::default(), Arc::default()).await;
+
+    // the last page links to a missing one
+    assert_eq!(pages, 11);
+}
+
+#[async_std::test]
+async fn test_max_pages() {
+    let pages = crawl(TerminationCondition::MaxPages(3), Arc::default()).await;
+
+    assert_eq!(pages, 3);
+}
+
+#[async_std::test]
+async fn test_signal() {
+    let signal = Arc::new(AtomicBool::new(false));
+    let pages = crawl(TerminationCondition::Signal(signal.clone()), signal).await;
+
+    // signal is set by handler of `/3`, which already navigated to `/4` by then
+    assert_eq!(pages, 5);
+}
+
+#[async_std::test]
+async fn test_custom_and_any() {
+    let custom = TerminationCondition::custom(|report| report.statuses.get(&200) == Some(&5));
+    let termination = TerminationCondition::Any(vec![TerminationCondition::MaxPages(8), custom]);
+    let pages = crawl(termination, Arc::default()).await;
+
+    assert_eq!(pages, 5);
+}
@@ -0,0 +1,57 @@
+extern crate crabler;
+
+use crabler::*;
+
+fn text(html: &str, options: &TextOptions) -> String {
+    let document = Document::from(html);
+    let elements = document.select("div");
+
+    elements[0].normalized_text(options)
+}
+
+const HTML: &str =
+    "<div>\n  Hello,\n  <p>big   world</p>\n  <script>track()</script><style>p {}</style>\n</div>";
+
+#[test]
+fn test_collapse_whitespace_by_default() {
+    assert_eq!(text(HTML, &TextOptions::default()), "Hello, big world");
+}
+
+#[test]
+fn test_preserve_and_trim_whitespace() {
+    let preserve = TextOptions {
+        whitespace: Whitespace::Preserve,
+        ..TextOptions::default()
+    };
+    assert_eq!(text(HTML, &preserve), "\n  Hello,\n  \n  \nbig   world");
+
+    let trim = TextOptions {
+        whitespace: Whitespace::Trim,
+        ..TextOptions::default()
+    };
+    assert_eq!(text(HTML, &trim), "Hello,\n  \n  \nbig   world");
+}
+
+#[test]
+fn test_include_scripts() {
+    let options = TextOptions {
+        include_scripts: true,
+        ..TextOptions::default()
+    };
+    assert_eq!(text(HTML, &options), "Hello, big world track() p {}");
+}
+
+#[test]
+fn test_decode_entities() {
+    let html = "<div>Tom &amp;amp; Jerry &amp;#169; &amp;#x263A; &amp;bogus; &amp; more</div>";
+    assert_eq!(
+        text(html, &TextOptions::default()),
+        "Tom &amp; Jerry &#169; &#x263A; &bogus; & more"
+    );
+
+    let options = TextOptions {
+        decode_entities: true,
+        ..TextOptions::default()
+    };
+    assert_eq!(text(html, &options), "Tom & Jerry © ☺ &bogus; & more");
+}
@@ -0,0 +1,135 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+#[macro_use]
+mod common;
+
+type Seen = Arc<RwLock<Vec<(String, u16)>>>;
+
+#[derive(MutableWebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {
+    responses: Seen,
+}
+
+impl Scraper {
+    async fn response_handler(&self, response: Response) -> Result<()> {
+        self.responses
+            .write()
+            .unwrap()
+            .push((response.url, response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_trap_detection_repeated_segments() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(
+                r#"<a href="/a/a/x.html">ok</a><a href="/a/b/a/b/a/b/a/x.html">trap</a>"#,
+            ),
+        ),
+        ("/a/a/x.html", common::Page::html("ok")),
+        ("/a/b/a/b/a/b/a/x.html", common::Page::html("trap")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    let handle = CrablerHandle::new();
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone())
+                .with_trap_detection(true),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/a/a/x.html", base), 200),
+            (format!("{}/a/b/a/b/a/b/a/x.html", base), 304),
+        ]
+    );
+    assert_eq!(
+        handle.report().traps,
+        vec![format!("{}/a/b/a/b/a/b/a/x.html", base)]
+    );
+}
+
+#[async_std::test]
+async fn test_trap_detection_query_variants() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/cal?m=1">1</a><a href="/cal?m=2&y=1">2</a>"#),
+        ),
+        (
+            "/cal?m=1",
+            common::Page::html(r#"<a href="/cal?m=2">2</a>"#),
+        ),
+        (
+            "/cal?m=2",
+            common::Page::html(r#"<a href="/cal?m=3">3</a>"#),
+        ),
+        ("/cal?m=2&y=1", common::Page::html("other pattern")),
+        ("/cal?m=3", common::Page::html("trap")),
+    ])
+    .await;
+
+    let responses = Arc::new(RwLock::new(vec![]));
+    let mut scraper = Scraper {
+        responses: responses.clone(),
+    };
+
+    let handle = CrablerHandle::new();
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_handle(handle.clone())
+                .with_trap_heuristics(TrapDetection {
+                    max_query_variants: 2,
+                    ..TrapDetection::default()
+                }),
+        )
+        .await
+        .unwrap();
+
+    let mut responses = responses.read().unwrap().clone();
+    responses.sort();
+    assert_eq!(
+        responses,
+        vec![
+            (format!("{}/", base), 200),
+            (format!("{}/cal?m=1", base), 200),
+            (format!("{}/cal?m=2", base), 200),
+            (format!("{}/cal?m=2&y=1", base), 200),
+            (format!("{}/cal?m=3", base), 304),
+        ]
+    );
+    assert_eq!(handle.report().traps, vec![format!("{}/cal?m=3", base)]);
+}
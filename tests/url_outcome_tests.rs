@@ -0,0 +1,60 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_url_outcome_logger() {
+    let base = common::serve(vec![
+        (
+            "/",
+            common::Page::html(r#"<a href="/page">page</a><a href="/missing">missing</a>"#),
+        ),
+        ("/page", common::Page::html("page")),
+        ("/missing", common::Page::html("gone").status(404)),
+    ])
+    .await;
+
+    let outcomes = Arc::new(Mutex::new(vec![]));
+    let logged = outcomes.clone();
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_url_outcome_logger(move |outcome| {
+                    let kind = match outcome {
+                        UrlOutcome::Fetched { status, .. } => format!("fetched {}", status),
+                        UrlOutcome::Error { .. } => "error".to_string(),
+                        _ => "other".to_string(),
+                    };
+                    let path = outcome.url().trim_start_matches(base.as_str()).to_string();
+                    logged.lock().unwrap().push(format!("{} {}", kind, path));
+                }),
+        )
+        .await
+        .unwrap();
+
+    let mut outcomes = outcomes.lock().unwrap().clone();
+    outcomes.sort();
+    assert_eq!(
+        outcomes,
+        vec!["fetched 200 /", "fetched 200 /page", "fetched 404 /missing"]
+    );
+}
@@ -0,0 +1,111 @@
+extern crate crabler;
+
+use crabler::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+struct SharedStore {
+    urls: Arc<Mutex<HashSet<String>>>,
+}
+
+impl VisitedStore for SharedStore {
+    fn contains(&self, url: &str) -> bool {
+        self.urls.lock().unwrap().contains(url)
+    }
+
+    fn insert(&self, url: String) {
+        self.urls.lock().unwrap().insert(url);
+    }
+}
+
+async fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    common::serve_logged(|_| {
+        vec![
+            (
+                "/".to_string(),
+                common::Page::html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#),
+            ),
+            (
+                "/a".to_string(),
+                common::Page::html(r#"<a href="/">root</a>"#),
+            ),
+            ("/b".to_string(), common::Page::html("b")),
+            ("/c".to_string(), common::Page::html("c")),
+        ]
+    })
+    .await
+}
+
+#[async_std::test]
+async fn test_custom_visited_store() {
+    let (base, requests) = serve().await;
+    let store = SharedStore::default();
+    // already visited by an earlier crawl sharing the store
+    store.insert(format!("{}/b", base));
+
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_visited_store(store.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut requests = requests.lock().unwrap().clone();
+    requests.sort();
+    assert_eq!(requests, vec!["/", "/a", "/c"]);
+    assert_eq!(store.urls.lock().unwrap().len(), 4);
+}
+
+#[async_std::test]
+async fn test_bloom_visited_store() {
+    let (base, requests) = serve().await;
+
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_bloom_visited(1000, 0.01),
+        )
+        .await
+        .unwrap();
+
+    let mut requests = requests.lock().unwrap().clone();
+    requests.sort();
+    assert_eq!(requests, vec!["/", "/a", "/b", "/c"]);
+}
+
+#[test]
+fn test_bloom_false_positive_rate() {
+    let store = BloomVisitedStore::new(10_000, 0.01);
+    for i in 0..10_000 {
+        store.insert(format!("https://example.com/page/{}", i));
+    }
+
+    assert!((0..10_000).all(|i| store.contains(&format!("https://example.com/page/{}", i))));
+    let false_positives = (0..10_000)
+        .filter(|i| store.contains(&format!("https://example.com/other/{}", i)))
+        .count();
+    assert!(false_positives < 200, "{}", false_positives);
+}
@@ -0,0 +1,81 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[macro_use]
+mod common;
+
+#[derive(MutableWebScraper)]
+#[on_html("a[href]", link_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn link_handler(&self, mut response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            response.navigate(href).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split WARC file into (headers, block) of every record, checking framing on the way
+fn records(content: &[u8]) -> Vec<(String, String)> {
+    let mut records = vec![];
+    let mut rest = content;
+
+    while !rest.is_empty() {
+        let end = rest.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let headers = String::from_utf8(rest[..end].to_vec()).unwrap();
+        assert!(headers.starts_with("WARC/1.1\r\n"));
+
+        let length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Length: "))
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        let block = &rest[end + 4..end + 4 + length];
+        assert_eq!(&rest[end + 4 + length..end + 8 + length], b"\r\n\r\n");
+
+        records.push((headers, String::from_utf8(block.to_vec()).unwrap()));
+        rest = &rest[end + 8 + length..];
+    }
+
+    records
+}
+
+#[async_std::test]
+async fn test_warc_output() {
+    let base = common::serve(vec![
+        ("/", common::Page::html(r#"<a href="/page">p</a>"#)),
+        ("/page", common::Page::html("crab page")),
+    ])
+    .await;
+    let path = std::env::temp_dir().join(format!("crabler-{}.warc", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut scraper = Scraper {};
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![&format!("{}/", base)])
+                .with_warc_output(path),
+        )
+        .await
+        .unwrap();
+
+    let records = records(&std::fs::read(path).unwrap());
+    assert_eq!(records.len(), 3);
+    assert!(records[0].0.contains("WARC-Type: warcinfo"));
+
+    let page = records
+        .iter()
+        .find(|(headers, _)| headers.contains(&format!("WARC-Target-URI: {}/page", base)))
+        .unwrap();
+    assert!(page.0.contains("WARC-Type: response"));
+    assert!(page.1.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(page.1.ends_with("\r\n\r\ncrab page"));
+
+    std::fs::remove_file(path).unwrap();
+}